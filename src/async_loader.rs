@@ -0,0 +1,127 @@
+//! The async loader module.
+//!
+//! Provides the AsyncLoader which reads data from a tokio `AsyncRead`
+//! into DataTables without blocking the executor.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use datatable::DataTable;
+use error::{DataError, Warnings};
+use loader::{IncrementalLoader, LoaderOptions};
+
+/// The number of bytes read from the source at a time.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Loader which reads its input from an async source.
+///
+/// Each chunk read is passed to an `IncrementalLoader`, so the table is
+/// built in the same way as `Loader::load_file` while the task waiting on
+/// the source, such as a file or an uploaded request body, leaves the
+/// executor thread free.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rusty_data;
+/// extern crate tokio;
+///
+/// use rusty_data::async_loader::AsyncLoader;
+/// use rusty_data::loader::LoaderOptions;
+///
+/// # fn main() {
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+/// let upload: &[u8] = b"x,y\n1.0,2.0\n3.0,4.0\n";
+///
+/// let table = runtime.block_on(AsyncLoader::new(upload, options).load_file()).unwrap();
+///
+/// assert_eq!(table.rows(), 2);
+/// assert_eq!(&table[1][1], "4.0");
+/// # }
+/// ```
+pub struct AsyncLoader<R> {
+    reader: R,
+    loader: Option<IncrementalLoader>,
+    buf: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncLoader<R> {
+    /// Constructs a new AsyncLoader reading from the source with the given options.
+    pub fn new(reader: R, options: LoaderOptions) -> AsyncLoader<R> {
+        AsyncLoader {
+            reader,
+            loader: Some(IncrementalLoader::new(options)),
+            buf: vec![0; CHUNK_SIZE],
+        }
+    }
+
+    /// Reads the whole source, resolving to the table.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The source could not be read.
+    /// - Fails in the same way as `IncrementalLoader::feed` and `finish`.
+    pub fn load_file(self) -> Load<R, DataTable> {
+        Load {
+            loader: self,
+            finish: IncrementalLoader::finish,
+        }
+    }
+
+    /// Reads the whole source, resolving to the table and any warnings.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `load_file`.
+    pub fn load_file_with_warnings(self) -> Load<R, (DataTable, Warnings)> {
+        Load {
+            loader: self,
+            finish: IncrementalLoader::finish_with_warnings,
+        }
+    }
+}
+
+/// The future returned by `AsyncLoader::load_file` and `load_file_with_warnings`.
+pub struct Load<R, T> {
+    loader: AsyncLoader<R>,
+    finish: fn(IncrementalLoader) -> Result<T, DataError>,
+}
+
+impl<R: AsyncRead + Unpin, T> Future for Load<R, T> {
+    type Output = Result<T, DataError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let state = &mut this.loader;
+
+        loop {
+            let mut buf = ReadBuf::new(&mut state.buf);
+
+            match Pin::new(&mut state.reader).poll_read(cx, &mut buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(e)) => {
+                    state.loader = None;
+                    return Poll::Ready(Err(DataError::Io(e)));
+                }
+                Poll::Ready(Ok(())) => {}
+            }
+
+            // An empty read marks the end of the source.
+            if buf.filled().is_empty() {
+                let loader = state.loader.take().expect("polled after completion");
+                return Poll::Ready((this.finish)(loader));
+            }
+
+            let loader = state.loader.as_mut().expect("polled after completion");
+            if let Err(e) = loader.feed(buf.filled()) {
+                state.loader = None;
+                return Poll::Ready(Err(e));
+            }
+        }
+    }
+}
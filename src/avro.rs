@@ -0,0 +1,777 @@
+//! The avro module.
+//!
+//! Provides reading and writing of Avro object container files, so that
+//! tables keep the types of their columns when exchanged with Avro based
+//! pipelines. Built with the `avro` feature.
+//!
+//! Records whose fields are primitive types, enums, or unions of `null`
+//! with one of these are supported. Blocks may be uncompressed or use the
+//! `deflate` or `snappy` codecs.
+
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::str;
+
+use flate2::Compression;
+use flate2::Crc;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use category::JsonParser;
+use datatable::{cast_value, DataColumn, DataTable};
+use error::{CellLocation, DataError};
+use schema::DType;
+use snapshot::SnapshotReader;
+
+const MAGIC: &[u8; 4] = b"Obj\x01";
+/// The number of rows written in each block.
+const BLOCK_ROWS: usize = 4096;
+
+/// How the blocks of a container file are compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AvroCodec {
+    /// Blocks are not compressed.
+    #[default]
+    Null,
+    /// Blocks are compressed with raw DEFLATE.
+    Deflate,
+    /// Blocks are compressed with Snappy, each followed by the CRC-32 of its data.
+    Snappy,
+}
+
+impl AvroCodec {
+    /// The name of the codec in the `avro.codec` metadata.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            AvroCodec::Null => "null",
+            AvroCodec::Deflate => "deflate",
+            AvroCodec::Snappy => "snappy",
+        }
+    }
+
+    /// The codec with the given name, if it is supported.
+    fn from_name(name: &[u8]) -> Option<AvroCodec> {
+        [AvroCodec::Null, AvroCodec::Deflate, AvroCodec::Snappy]
+            .iter()
+            .cloned()
+            .find(|codec| codec.name().as_bytes() == name)
+    }
+
+    fn compress<'a>(&self, block: &'a [u8]) -> Result<Cow<'a, [u8]>, DataError> {
+        match *self {
+            AvroCodec::Null => Ok(Cow::Borrowed(block)),
+            AvroCodec::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(block)?;
+                Ok(Cow::Owned(encoder.finish()?))
+            }
+            AvroCodec::Snappy => {
+                let mut out = snap::raw::Encoder::new()
+                    .compress_vec(block)
+                    .map_err(io::Error::from)?;
+                out.extend_from_slice(&crc32(block).to_be_bytes());
+                Ok(Cow::Owned(out))
+            }
+        }
+    }
+
+    fn decompress<'a>(&self, block: &'a [u8]) -> Result<Cow<'a, [u8]>, DataError> {
+        match *self {
+            AvroCodec::Null => Ok(Cow::Borrowed(block)),
+            AvroCodec::Deflate => {
+                let mut out = Vec::new();
+                DeflateDecoder::new(block)
+                    .read_to_end(&mut out)
+                    .map_err(|_| DataError::InvalidStateError)?;
+                Ok(Cow::Owned(out))
+            }
+            AvroCodec::Snappy => {
+                if block.len() < 4 {
+                    return Err(DataError::InvalidStateError);
+                }
+                let (data, crc) = block.split_at(block.len() - 4);
+                let out = snap::raw::Decoder::new()
+                    .decompress_vec(data)
+                    .map_err(|_| DataError::InvalidStateError)?;
+                if crc32(&out).to_be_bytes() != crc {
+                    return Err(DataError::InvalidStateError);
+                }
+                Ok(Cow::Owned(out))
+            }
+        }
+    }
+}
+
+/// The Avro type of a field, without its nullability.
+#[derive(Clone, Debug, PartialEq)]
+enum AvroType {
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Enum(Vec<String>),
+}
+
+/// A field of the record schema.
+struct Field {
+    name: String,
+    avro_type: AvroType,
+    /// The branch of the union holding null, if the field is nullable.
+    null_branch: Option<i64>,
+}
+
+impl DataTable {
+    /// The Avro schema the table is written with, as JSON.
+    ///
+    /// Each column is a field of a record named `DataTable`. Columns with
+    /// a recorded type, see `DataColumn::dtype`, use it and other columns
+    /// use `DataColumn::guess_type` over all of their values. Booleans are
+    /// written as `boolean`, integers as `long`, floats as `double` and
+    /// strings as `string`, or as an `enum` if the column has categories
+    /// which are valid Avro names. Columns with missing values, or empty
+    /// values in a column which is not a string, are a union with `null`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A column has no name, a name is not a valid
+    ///   Avro name or two columns have the same name. See
+    ///   `loader::normalize_header` for making names valid.
+    pub fn avro_schema(&self) -> Result<String, DataError> {
+        Ok(schema_json(&self.avro_fields()?))
+    }
+
+    /// Writes the table as an Avro object container file.
+    ///
+    /// See `avro_schema` for how the columns are written.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The columns cannot be written, see `avro_schema`.
+    /// - DataCastError : A value does not parse as the recorded type of
+    ///   its column.
+    /// - Io : The writer failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "0.5", "true"], vec!["2", "", "false"]];
+    /// let table = DataTable::from_records(Some(vec!["id", "score", "valid"]), records).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// table.write_avro(&mut bytes).unwrap();
+    ///
+    /// let read = DataTable::read_avro(&bytes[..]).unwrap();
+    /// assert_eq!(read.headers(), table.headers());
+    /// assert_eq!(read[0].data(), vec!["1", "2"]);
+    /// assert_eq!(read[0].dtype().unwrap().name(), "i64");
+    ///
+    /// // The empty score is written as null.
+    /// assert!(read[1].is_missing(1));
+    /// ```
+    pub fn write_avro<W: Write>(&self, writer: &mut W) -> Result<(), DataError> {
+        self.write_avro_with(writer, AvroCodec::Null)
+    }
+
+    /// Writes the table as an Avro object container file with blocks
+    /// compressed by `codec`.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `write_avro`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::avro::AvroCodec;
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = (0..100).map(|i| vec![i.to_string(), "repeated text".to_string()]);
+    /// let table = DataTable::from_records(Some(vec!["id", "text"]), records).unwrap();
+    ///
+    /// let mut plain = Vec::new();
+    /// table.write_avro(&mut plain).unwrap();
+    ///
+    /// for &codec in &[AvroCodec::Deflate, AvroCodec::Snappy] {
+    ///     let mut bytes = Vec::new();
+    ///     table.write_avro_with(&mut bytes, codec).unwrap();
+    ///     assert!(bytes.len() < plain.len());
+    ///
+    ///     let read = DataTable::read_avro(&bytes[..]).unwrap();
+    ///     assert_eq!(read[1].data(), table[1].data());
+    /// }
+    /// ```
+    pub fn write_avro_with<W: Write>(&self,
+                                     writer: &mut W,
+                                     codec: AvroCodec)
+                                     -> Result<(), DataError> {
+        let fields = self.avro_fields()?;
+        let schema = schema_json(&fields);
+
+        let state = RandomState::new();
+        let mut sync = Vec::with_capacity(16);
+        for i in 0..2u64 {
+            let mut hasher = state.build_hasher();
+            hasher.write_u64(i);
+            sync.extend_from_slice(&hasher.finish().to_le_bytes());
+        }
+
+        let mut header = MAGIC.to_vec();
+        write_long(&mut header, 2);
+        write_bytes(&mut header, b"avro.schema");
+        write_bytes(&mut header, schema.as_bytes());
+        write_bytes(&mut header, b"avro.codec");
+        write_bytes(&mut header, codec.name().as_bytes());
+        write_long(&mut header, 0);
+        header.extend_from_slice(&sync);
+        writer.write_all(&header)?;
+
+        let enum_indices: Vec<HashMap<&str, i64>> = fields.iter()
+            .map(|f| match f.avro_type {
+                AvroType::Enum(ref symbols) => {
+                    symbols.iter().enumerate().map(|(i, s)| (&s[..], i as i64)).collect()
+                }
+                _ => HashMap::new(),
+            })
+            .collect();
+
+        let mut block = Vec::new();
+        let mut start = 0;
+        while start < self.rows() {
+            let end = (start + BLOCK_ROWS).min(self.rows());
+            block.clear();
+
+            for row in start..end {
+                for (col, (column, field)) in self.data_cols.iter().zip(fields.iter()).enumerate() {
+                    let location = || CellLocation {
+                        row: Some(row),
+                        column: Some(col),
+                        column_name: column.name.clone(),
+                    };
+                    encode_value(&mut block, column, row, field, &enum_indices[col], location)?;
+                }
+            }
+
+            let compressed = codec.compress(&block)?;
+            let mut framing = Vec::new();
+            write_long(&mut framing, (end - start) as i64);
+            write_long(&mut framing, compressed.len() as i64);
+            writer.write_all(&framing)?;
+            writer.write_all(&compressed)?;
+            writer.write_all(&sync)?;
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the table to an Avro object container file, see `write_avro`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The columns cannot be written, see `avro_schema`.
+    /// - DataCastError : A value does not parse as the recorded type of
+    ///   its column.
+    /// - Io : The file could not be written.
+    pub fn save_avro<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        self.save_avro_with(path, AvroCodec::Null)
+    }
+
+    /// Writes the table to an Avro object container file with blocks
+    /// compressed by `codec`, see `write_avro_with`.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `save_avro`.
+    pub fn save_avro_with<P: AsRef<Path>>(&self,
+                                          path: P,
+                                          codec: AvroCodec)
+                                          -> Result<(), DataError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_avro_with(&mut writer, codec)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a table from an Avro object container file.
+    ///
+    /// Each field of the record is a column, with its type recorded as
+    /// `i64` for `int` and `long`, `f64` for `float` and `double` and
+    /// `bool` for `boolean`. Enums become columns of strings with their
+    /// symbols as categories. Nulls are empty and marked as missing.
+    /// Blocks compressed with any `AvroCodec` are read.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The reader failed.
+    /// - InvalidStateError : The input is not a valid container file, uses
+    ///   another codec, or its schema holds unsupported types or an enum
+    ///   with a repeated symbol.
+    pub fn read_avro<R: Read>(mut reader: R) -> Result<DataTable, DataError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let mut reader = SnapshotReader { bytes: &bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut metadata = HashMap::new();
+        loop {
+            let mut count = read_long(&mut reader)?;
+            if count == 0 {
+                break;
+            }
+            if count < 0 {
+                // A negative count is followed by the size of the block in bytes.
+                count = count.checked_neg().ok_or(DataError::InvalidStateError)?;
+                read_long(&mut reader)?;
+            }
+            for _ in 0..count {
+                let key = read_str(&mut reader)?;
+                let value = read_bytes(&mut reader)?;
+                metadata.insert(key, value);
+            }
+        }
+
+        let codec = match metadata.get("avro.codec") {
+            None => AvroCodec::Null,
+            Some(&name) => AvroCodec::from_name(name).ok_or(DataError::InvalidStateError)?,
+        };
+        let schema = metadata.get("avro.schema").ok_or(DataError::InvalidStateError)?;
+        let schema = str::from_utf8(schema).map_err(|_| DataError::InvalidStateError)?;
+        let fields = parse_schema(schema)?;
+        let sync = reader.take(16)?;
+
+        let mut columns: Vec<DataColumn> = fields.iter()
+            .map(|f| {
+                let mut column = DataColumn::empty();
+                column.name = Some(f.name.clone());
+                column
+            })
+            .collect();
+
+        while !reader.bytes.is_empty() {
+            let count = read_long(&mut reader)?;
+            let size = read_long(&mut reader)?;
+            if count < 0 || size < 0 {
+                return Err(DataError::InvalidStateError);
+            }
+
+            let data = codec.decompress(reader.take(size as usize)?)?;
+            let mut block = SnapshotReader { bytes: &data };
+            for _ in 0..count {
+                for (column, field) in columns.iter_mut().zip(fields.iter()) {
+                    decode_value(&mut block, column, field)?;
+                }
+            }
+
+            if !block.bytes.is_empty() || reader.take(16)? != sync {
+                return Err(DataError::InvalidStateError);
+            }
+        }
+
+        for (column, field) in columns.iter_mut().zip(fields.iter()) {
+            let dtype = match field.avro_type {
+                AvroType::Boolean => DType::Bool,
+                AvroType::Int | AvroType::Long => DType::I64,
+                AvroType::Float | AvroType::Double => DType::F64,
+                AvroType::Bytes | AvroType::String => DType::String,
+                AvroType::Enum(ref symbols) => {
+                    let categories: HashMap<String, usize> =
+                        symbols.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect();
+                    // A repeated symbol would leave an index without a label.
+                    if categories.len() != symbols.len() {
+                        return Err(DataError::InvalidStateError);
+                    }
+                    column.set_categories(categories);
+                    DType::String
+                }
+            };
+            column.set_dtype(dtype);
+        }
+
+        Ok(DataTable { data_cols: columns })
+    }
+
+    /// Loads a table from an Avro object container file, see `read_avro`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : The file is not a valid container file, uses
+    ///   another codec, or its schema holds unsupported types.
+    pub fn load_avro<P: AsRef<Path>>(path: P) -> Result<DataTable, DataError> {
+        DataTable::read_avro(BufReader::new(File::open(path)?))
+    }
+
+    /// The fields the columns are written as.
+    fn avro_fields(&self) -> Result<Vec<Field>, DataError> {
+        let mut fields: Vec<Field> = Vec::with_capacity(self.cols());
+
+        for column in self.data_cols.iter() {
+            let name = match column.name {
+                Some(ref name) if valid_name(name) => name.clone(),
+                _ => return Err(DataError::InvalidStateError),
+            };
+            if fields.iter().any(|f| f.name == name) {
+                return Err(DataError::InvalidStateError);
+            }
+
+            let dtype = column.dtype().unwrap_or_else(|| column.guess_type(column.len()));
+            let avro_type = match dtype {
+                DType::Bool => AvroType::Boolean,
+                DType::I64 => AvroType::Long,
+                DType::F64 => AvroType::Double,
+                DType::String => {
+                    match column.category_labels() {
+                        Some(ref labels) if labels.iter().all(|l| valid_name(l)) => {
+                            AvroType::Enum(labels.iter().map(|l| l.to_string()).collect())
+                        }
+                        _ => AvroType::String,
+                    }
+                }
+            };
+
+            let nullable = (0..column.len()).any(|row| is_null(column, row, &avro_type));
+            fields.push(Field {
+                name,
+                avro_type,
+                null_branch: if nullable { Some(0) } else { None },
+            });
+        }
+
+        Ok(fields)
+    }
+}
+
+/// The CRC-32 which follows each block compressed with Snappy.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// Returns true if the value is written as null.
+fn is_null(column: &DataColumn, row: usize, avro_type: &AvroType) -> bool {
+    column.is_missing(row) || (column[row].is_empty() && *avro_type != AvroType::String)
+}
+
+/// Returns true if the name is a valid Avro name.
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') &&
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn schema_json(fields: &[Field]) -> String {
+    let fields: Vec<String> = fields.iter()
+        .map(|f| {
+            let avro_type = match f.avro_type {
+                AvroType::Boolean => "\"boolean\"".to_string(),
+                AvroType::Long => "\"long\"".to_string(),
+                AvroType::Double => "\"double\"".to_string(),
+                AvroType::Enum(ref symbols) => {
+                    format!(r#"{{"type":"enum","name":"{}_symbols","symbols":["{}"]}}"#,
+                            f.name,
+                            symbols.join("\",\""))
+                }
+                _ => "\"string\"".to_string(),
+            };
+            match f.null_branch {
+                Some(_) => format!(r#"{{"name":"{}","type":["null",{}]}}"#, f.name, avro_type),
+                None => format!(r#"{{"name":"{}","type":{}}}"#, f.name, avro_type),
+            }
+        })
+        .collect();
+
+    format!(r#"{{"type":"record","name":"DataTable","fields":[{}]}}"#, fields.join(","))
+}
+
+fn encode_value<F>(out: &mut Vec<u8>,
+                   column: &DataColumn,
+                   row: usize,
+                   field: &Field,
+                   enum_indices: &HashMap<&str, i64>,
+                   location: F)
+                   -> Result<(), DataError>
+    where F: FnOnce() -> CellLocation
+{
+    if let Some(null_branch) = field.null_branch {
+        if is_null(column, row, &field.avro_type) {
+            write_long(out, null_branch);
+            return Ok(());
+        }
+        write_long(out, 1 - null_branch);
+    }
+
+    let value = &column[row];
+    match field.avro_type {
+        AvroType::Boolean => out.push(cast_value::<bool, _>(value, location)? as u8),
+        AvroType::Int | AvroType::Long => write_long(out, cast_value(value, location)?),
+        AvroType::Float => {
+            out.extend_from_slice(&cast_value::<f32, _>(value, location)?.to_le_bytes())
+        }
+        AvroType::Double => {
+            out.extend_from_slice(&cast_value::<f64, _>(value, location)?.to_le_bytes())
+        }
+        AvroType::Bytes | AvroType::String => write_bytes(out, value.as_bytes()),
+        AvroType::Enum(_) => {
+            let index = enum_indices.get(value).ok_or(DataError::InvalidStateError)?;
+            write_long(out, *index);
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_value(reader: &mut SnapshotReader,
+                column: &mut DataColumn,
+                field: &Field)
+                -> Result<(), DataError> {
+    if let Some(null_branch) = field.null_branch {
+        match read_long(reader)? {
+            branch if branch == null_branch => {
+                column.push_missing("");
+                return Ok(());
+            }
+            branch if branch == 1 - null_branch => {}
+            _ => return Err(DataError::InvalidStateError),
+        }
+    }
+
+    match field.avro_type {
+        AvroType::Boolean => {
+            match reader.read_u8()? {
+                0 => column.push("false"),
+                1 => column.push("true"),
+                _ => return Err(DataError::InvalidStateError),
+            }
+        }
+        AvroType::Int | AvroType::Long => column.push_value(read_long(reader)?),
+        AvroType::Float => {
+            let bytes = reader.take(4)?;
+            column.push_value(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        }
+        AvroType::Double => {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(reader.take(8)?);
+            column.push_value(f64::from_le_bytes(bytes));
+        }
+        AvroType::Bytes | AvroType::String => column.push(read_str(reader)?),
+        AvroType::Enum(ref symbols) => {
+            let index = read_long(reader)?;
+            let symbol = usize::try_from(index).ok().and_then(|i| symbols.get(i));
+            column.push(&symbol.ok_or(DataError::InvalidStateError)?[..]);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a zig-zag encoded variable length integer.
+fn write_long(out: &mut Vec<u8>, value: i64) {
+    let mut n = ((value << 1) ^ (value >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(out, bytes.len() as i64);
+    out.extend_from_slice(bytes);
+}
+
+fn read_long(reader: &mut SnapshotReader) -> Result<i64, DataError> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = reader.read_u8()?;
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((n >> 1) as i64 ^ -((n & 1) as i64));
+        }
+    }
+    Err(DataError::InvalidStateError)
+}
+
+fn read_bytes<'a>(reader: &mut SnapshotReader<'a>) -> Result<&'a [u8], DataError> {
+    let len = read_long(reader)?;
+    let len = usize::try_from(len).map_err(|_| DataError::InvalidStateError)?;
+    reader.take(len)
+}
+
+fn read_str<'a>(reader: &mut SnapshotReader<'a>) -> Result<&'a str, DataError> {
+    str::from_utf8(read_bytes(reader)?).map_err(|_| DataError::InvalidStateError)
+}
+
+/// Reads the fields of a record schema.
+fn parse_schema(schema: &str) -> Result<Vec<Field>, DataError> {
+    let mut parser = JsonParser { chars: schema.chars().peekable() };
+    let schema = parser.parse_value().ok_or(DataError::InvalidStateError)?;
+    parser.skip_whitespace();
+    let record = schema.get("type").and_then(Json::as_str) == Some("record");
+    if parser.chars.next().is_some() || !record {
+        return Err(DataError::InvalidStateError);
+    }
+
+    let fields = match schema.get("fields") {
+        Some(Json::Array(fields)) => fields,
+        _ => return Err(DataError::InvalidStateError),
+    };
+
+    fields.iter()
+        .map(|field| {
+            let name = field.get("name").and_then(Json::as_str);
+            let name = name.ok_or(DataError::InvalidStateError)?.to_string();
+            let field_type = field.get("type").ok_or(DataError::InvalidStateError)?;
+
+            let (avro_type, null_branch) = match *field_type {
+                Json::Array(ref branches) => {
+                    let null = branches.iter().position(|b| b.as_str() == Some("null"));
+                    match (branches.len(), null) {
+                        (1, None) => (parse_type(&branches[0])?, None),
+                        (2, Some(null)) => (parse_type(&branches[1 - null])?, Some(null as i64)),
+                        _ => return Err(DataError::InvalidStateError),
+                    }
+                }
+                ref single => (parse_type(single)?, None),
+            };
+
+            Ok(Field { name, avro_type, null_branch })
+        })
+        .collect()
+}
+
+fn parse_type(json: &Json) -> Result<AvroType, DataError> {
+    let name = match *json {
+        Json::String(ref name) => name,
+        Json::Object(_) => {
+            let name = json.get("type").and_then(Json::as_str);
+            if name == Some("enum") {
+                return match json.get("symbols") {
+                    Some(Json::Array(symbols)) => {
+                        let symbols = symbols.iter().map(|s| s.as_str().map(String::from));
+                        symbols.collect::<Option<_>>()
+                            .map(AvroType::Enum)
+                            .ok_or(DataError::InvalidStateError)
+                    }
+                    _ => Err(DataError::InvalidStateError),
+                };
+            }
+            // Logical types are read as the type they are stored as.
+            return parse_type(json.get("type").ok_or(DataError::InvalidStateError)?);
+        }
+        _ => return Err(DataError::InvalidStateError),
+    };
+
+    match &name[..] {
+        "boolean" => Ok(AvroType::Boolean),
+        "int" => Ok(AvroType::Int),
+        "long" => Ok(AvroType::Long),
+        "float" => Ok(AvroType::Float),
+        "double" => Ok(AvroType::Double),
+        "bytes" => Ok(AvroType::Bytes),
+        "string" => Ok(AvroType::String),
+        _ => Err(DataError::InvalidStateError),
+    }
+}
+
+/// A parsed JSON value, keeping only what schemas need.
+enum Json {
+    /// A null, boolean or number.
+    Scalar,
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref entries) => entries.iter().find(|&(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> JsonParser<I> {
+    fn parse_value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            '"' => self.parse_string().map(Json::String),
+            '[' => {
+                self.chars.next();
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&']') {
+                    self.chars.next();
+                    return Some(Json::Array(values));
+                }
+                loop {
+                    values.push(self.parse_value()?);
+                    self.skip_whitespace();
+                    match self.chars.next()? {
+                        ',' => continue,
+                        ']' => return Some(Json::Array(values)),
+                        _ => return None,
+                    }
+                }
+            }
+            '{' => {
+                self.chars.next();
+                let mut entries = Vec::new();
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&'}') {
+                    self.chars.next();
+                    return Some(Json::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.parse_string()?;
+                    self.expect(':')?;
+                    entries.push((key, self.parse_value()?));
+                    self.skip_whitespace();
+                    match self.chars.next()? {
+                        ',' => continue,
+                        '}' => return Some(Json::Object(entries)),
+                        _ => return None,
+                    }
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '-' || c == '+' || c == '.') {
+                        break;
+                    }
+                    word.push(c);
+                    self.chars.next();
+                }
+                let scalar = ["null", "true", "false"].contains(&&word[..]) ||
+                             word.parse::<f64>().is_ok();
+                if scalar { Some(Json::Scalar) } else { None }
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! The rdata command line tool.
+//!
+//! Inspects and converts data files using rusty-data. Built with the
+//! `cli` feature.
+
+extern crate rusty_data;
+
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+
+use rusty_data::datatable::{DataColumn, DataTable};
+use rusty_data::loader::{Loader, LoaderOptions};
+use rusty_data::writer::WriterOptions;
+
+const USAGE: &str = "Usage:
+    rdata head [-n ROWS] FILE    Print the first rows of the file
+    rdata schema FILE            Print the guessed type of each column
+    rdata describe FILE          Print summary statistics of each column
+    rdata convert INPUT OUTPUT   Convert between formats
+
+Files ending in .csv, .txt or no extension are CSV, files ending in
+.tsv are tab delimited, files ending in .parquet are Parquet and files
+ending in .rdsnap are snapshots, see DataTable::save.";
+
+/// The number of values used to guess the type of a column.
+const SAMPLE_ROWS: usize = 1000;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(message) = run(&args) {
+        eprintln!("rdata: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(|a| &a[..]).collect();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    match args[..] {
+        ["head", file] => head(&mut out, file, 10),
+        ["head", "-n", rows, file] => {
+            let rows = rows.parse().map_err(|_| format!("invalid row count: {}", rows))?;
+            head(&mut out, file, rows)
+        }
+        ["schema", file] => schema(&mut out, file),
+        ["describe", file] => describe(&mut out, file),
+        ["convert", input, output] => convert(input, output),
+        ["help"] | ["--help"] | ["-h"] => writeln!(out, "{}", USAGE).map_err(fail),
+        _ => Err(format!("invalid arguments\n\n{}", USAGE)),
+    }
+}
+
+/// Describes the error for the user.
+fn fail<E: ToString>(error: E) -> String {
+    error.to_string()
+}
+
+fn head<W: Write>(out: &mut W, file: &str, rows: usize) -> Result<(), String> {
+    load(file)?.head(rows).write_csv(out, &WriterOptions::default()).map_err(fail)
+}
+
+fn schema<W: Write>(out: &mut W, file: &str) -> Result<(), String> {
+    let table = load(file)?;
+    let mut schema = DataTable::empty();
+    let mut names = named("column");
+    let mut types = named("type");
+    let mut missing = named("missing");
+
+    for (col, column) in table.data_cols.iter().enumerate() {
+        names.push(label(column, col));
+        types.push(column.guess_type(SAMPLE_ROWS).name());
+        missing.push_value(column.null_count());
+    }
+
+    schema.data_cols = vec![names, types, missing];
+    schema.write_csv(out, &WriterOptions::default()).map_err(fail)
+}
+
+fn describe<W: Write>(out: &mut W, file: &str) -> Result<(), String> {
+    let table = load(file)?;
+    let mut summary = DataTable::empty();
+    let mut columns: Vec<DataColumn> = ["column", "count", "missing", "mean", "std", "min", "max"]
+        .iter()
+        .map(|n| named(n))
+        .collect();
+
+    for (col, column) in table.data_cols.iter().enumerate() {
+        columns[0].push(label(column, col));
+        columns[1].push_value(column.len() - column.null_count());
+        columns[2].push_value(column.null_count());
+
+        // Only columns where every present value is a number are summarised.
+        let values: Option<Vec<f64>> = (0..column.len())
+            .filter(|&row| !column.is_missing(row))
+            .map(|row| column[row].trim().parse().ok())
+            .collect();
+        match values.filter(|v| !v.is_empty()) {
+            Some(values) => {
+                for (column, stat) in columns[3..].iter_mut().zip(numeric_summary(&values).iter()) {
+                    column.push_value(stat);
+                }
+            }
+            None => {
+                for column in columns[3..].iter_mut() {
+                    column.push_missing("");
+                }
+            }
+        }
+    }
+
+    summary.data_cols = columns;
+    summary.write_csv(out, &WriterOptions::default()).map_err(fail)
+}
+
+fn convert(input: &str, output: &str) -> Result<(), String> {
+    let table = load(input)?;
+
+    match Format::of(output)? {
+        Format::Snapshot => table.save(output).map_err(fail),
+        Format::Parquet => table.save_parquet(output).map_err(fail),
+        Format::Delimited(delimiter) => {
+            let options = WriterOptions { delimiter, ..WriterOptions::default() };
+            table.save_csv(output, &options).map_err(fail)
+        }
+    }
+}
+
+/// The supported file formats.
+enum Format {
+    Delimited(char),
+    Parquet,
+    Snapshot,
+}
+
+impl Format {
+    fn of(file: &str) -> Result<Format, String> {
+        match Path::new(file).extension().and_then(|e| e.to_str()) {
+            Some("rdsnap") => Ok(Format::Snapshot),
+            Some("parquet") => Ok(Format::Parquet),
+            Some("tsv") => Ok(Format::Delimited('\t')),
+            Some("csv") | Some("txt") | None => Ok(Format::Delimited(',')),
+            Some(ext) => Err(format!("unsupported file format: .{}", ext)),
+        }
+    }
+}
+
+fn load(file: &str) -> Result<DataTable, String> {
+    match Format::of(file)? {
+        Format::Snapshot => DataTable::load(file).map_err(fail),
+        Format::Parquet => DataTable::load_parquet(file).map_err(fail),
+        Format::Delimited(delimiter) => {
+            let options = LoaderOptions {
+                has_header: true,
+                delimiter,
+                quote_marker: Some('"'),
+                multiline_quotes: true,
+                ..LoaderOptions::default()
+            };
+            Loader::with_options(file, options).load_file().map_err(fail)
+        }
+    }
+}
+
+/// An empty column with the name.
+fn named(name: &str) -> DataColumn {
+    let mut column = DataColumn::empty();
+    column.name = Some(name.to_string());
+    column
+}
+
+/// The name of the column, or its index if it has no name.
+fn label(column: &DataColumn, col: usize) -> String {
+    column.name.clone().unwrap_or_else(|| col.to_string())
+}
+
+/// The mean, standard deviation, minimum and maximum of the values.
+fn numeric_summary(values: &[f64]) -> [f64; 4] {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0).max(1.0);
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    [mean, var.sqrt(), min, max]
+}
@@ -0,0 +1,315 @@
+//! The category module.
+//!
+//! Provides the CategoryMap which stores a fitted category encoding
+//! so that it can be saved and applied to other data.
+
+use std::char;
+use std::collections::HashMap;
+use std::fs;
+use std::iter::Peekable;
+use std::path::Path;
+
+use datatable::DataColumn;
+use error::DataError;
+
+/// An encoding of category labels as indices.
+///
+/// The label at position `i` of `labels` is encoded as `i`. Maps are
+/// saved as a JSON array of the labels in index order.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::category::{CategoryMap, UnseenCategory};
+/// use rusty_data::datatable::DataColumn;
+///
+/// let mut train: DataColumn = vec!["cat", "dog"].into_iter().collect();
+/// train.update_categories();
+///
+/// let map = train.category_map().unwrap();
+/// let json = map.to_json();
+/// assert_eq!(json, r#"["cat","dog"]"#);
+///
+/// let mut test: DataColumn = vec!["dog", "bird"].into_iter().collect();
+/// let map = CategoryMap::from_json(&json).unwrap();
+///
+/// // Unseen categories are refused by default.
+/// assert!(test.apply_categories(&map, UnseenCategory::Fail).is_err());
+///
+/// test.apply_categories(&map, UnseenCategory::Extend).unwrap();
+/// assert_eq!(test.category_labels().unwrap(), vec!["cat", "dog", "bird"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CategoryMap {
+    labels: Vec<String>,
+}
+
+/// How `apply_categories` treats values which are not in the map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnseenCategory {
+    /// Return an error and leave the column unchanged.
+    #[default]
+    Fail,
+    /// Give each unseen value a new index after the known categories,
+    /// in the order they first appear.
+    Extend,
+}
+
+impl CategoryMap {
+    /// Constructs a map encoding each label as its position.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A label appears more than once.
+    pub fn from_labels<I, S>(labels: I) -> Result<CategoryMap, DataError>
+        where I: IntoIterator<Item = S>,
+              S: Into<String>
+    {
+        let labels: Vec<String> = labels.into_iter().map(Into::into).collect();
+
+        let mut sorted: Vec<&String> = labels.iter().collect();
+        sorted.sort_unstable();
+        if sorted.windows(2).any(|w| w[0] == w[1]) {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(CategoryMap { labels })
+    }
+
+    /// The labels in index order.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// The number of categories.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns true if the map holds no categories.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Gets the index of the label, if it is in the map.
+    pub fn index(&self, label: &str) -> Option<usize> {
+        self.labels.iter().position(|l| l == label)
+    }
+
+    /// Gets the label with the index, if it is in the map.
+    pub fn label(&self, idx: usize) -> Option<&str> {
+        self.labels.get(idx).map(|l| &l[..])
+    }
+
+    /// Writes the map as a JSON array of labels.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+
+        for (i, label) in self.labels.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write_json_string(&mut json, label);
+        }
+
+        json.push(']');
+        json
+    }
+
+    /// Reads a map from a JSON array of labels.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The input is not a JSON array of distinct strings.
+    pub fn from_json(json: &str) -> Result<CategoryMap, DataError> {
+        let mut parser = JsonParser { chars: json.chars().peekable() };
+        let labels = parser.parse_array().ok_or(DataError::InvalidStateError)?;
+
+        parser.skip_whitespace();
+        if parser.chars.next().is_some() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        CategoryMap::from_labels(labels)
+    }
+
+    /// Saves the map to a file as JSON.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    /// Loads a map from a JSON file written by `save`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : The file does not hold a JSON array of distinct strings.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<CategoryMap, DataError> {
+        CategoryMap::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl DataColumn {
+    /// Gets the categories of the column as a CategoryMap.
+    ///
+    /// Returns `None` if the categories have not been set.
+    pub fn category_map(&self) -> Option<CategoryMap> {
+        let labels = self.category_labels()?;
+        Some(CategoryMap { labels: labels.into_iter().map(String::from).collect() })
+    }
+
+    /// Sets the categories of the column from the map.
+    ///
+    /// Known values are given the same index as in the map, so data
+    /// encoded with `numeric_category_data` matches the data the map was
+    /// fitted on. `unseen` decides what happens to any other values.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A value is not in the map and `unseen` is `Fail`.
+    pub fn apply_categories(&mut self,
+                            map: &CategoryMap,
+                            unseen: UnseenCategory)
+                            -> Result<(), DataError> {
+        let mut labels = map.labels.clone();
+        let mut categories = map.labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| (label.clone(), i))
+            .collect::<HashMap<_, _>>();
+
+        for val in self.iter() {
+            if categories.contains_key(val) {
+                continue;
+            }
+
+            match unseen {
+                UnseenCategory::Fail => return Err(DataError::InvalidStateError),
+                UnseenCategory::Extend => {
+                    categories.insert(val.to_string(), labels.len());
+                    labels.push(val.to_string());
+                }
+            }
+        }
+
+        self.set_categories(categories);
+        Ok(())
+    }
+}
+
+/// Appends the value to `json` as a quoted JSON string.
+pub(crate) fn write_json_string(json: &mut String, value: &str) {
+    json.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
+
+/// Reads the small subset of JSON used by `CategoryMap`.
+pub(crate) struct JsonParser<I: Iterator<Item = char>> {
+    pub(crate) chars: Peekable<I>,
+}
+
+impl<I: Iterator<Item = char>> JsonParser<I> {
+    pub(crate) fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    pub(crate) fn expect(&mut self, expected: char) -> Option<()> {
+        self.skip_whitespace();
+        if self.chars.next()? == expected { Some(()) } else { None }
+    }
+
+    fn parse_array(&mut self) -> Option<Vec<String>> {
+        let mut values = Vec::new();
+        self.expect('[')?;
+
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.chars.next();
+            return Some(values);
+        }
+
+        loop {
+            values.push(self.parse_string()?);
+
+            self.skip_whitespace();
+            match self.chars.next()? {
+                ',' => continue,
+                ']' => return Some(values),
+                _ => return None,
+            }
+        }
+    }
+
+    pub(crate) fn parse_string(&mut self) -> Option<String> {
+        let mut value = String::new();
+        self.expect('"')?;
+
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(value),
+                '\\' => {
+                    let c = match self.chars.next()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.parse_unicode_escape()?,
+                        _ => return None,
+                    };
+                    value.push(c);
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        let high = self.parse_hex()?;
+
+        if (0xD800..0xDC00).contains(&high) {
+            // A surrogate pair is written as two escapes.
+            if self.chars.next()? != '\\' || self.chars.next()? != 'u' {
+                return None;
+            }
+            let low = self.parse_hex()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+            return char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00));
+        }
+
+        char::from_u32(high)
+    }
+
+    fn parse_hex(&mut self) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..4 {
+            value = value * 16 + self.chars.next()?.to_digit(16)?;
+        }
+        Some(value)
+    }
+}
@@ -0,0 +1,208 @@
+//! The compare module.
+//!
+//! Provides the TableDiff which reports how two DataTables differ.
+
+use std::collections::HashMap;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+
+/// A value which differs between two tables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellChange {
+    /// The row in the original table.
+    pub row: usize,
+    /// The matching row in the other table.
+    pub other_row: usize,
+    /// The name of the column, or its index if it has no name.
+    pub column: String,
+    /// The original value, `None` if it is missing.
+    pub old: Option<String>,
+    /// The new value, `None` if it is missing.
+    pub new: Option<String>,
+}
+
+/// The differences between two tables, see `DataTable::compare`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableDiff {
+    /// The rows of the other table which have no match in the original.
+    pub added_rows: Vec<usize>,
+    /// The rows of the original table which have no match in the other.
+    pub removed_rows: Vec<usize>,
+    /// The values which differ between matching rows.
+    pub changed: Vec<CellChange>,
+    /// The columns only in the other table.
+    pub added_columns: Vec<String>,
+    /// The columns only in the original table.
+    pub removed_columns: Vec<String>,
+}
+
+impl TableDiff {
+    /// Returns true if the tables are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added_rows.is_empty() && self.removed_rows.is_empty() && self.changed.is_empty() &&
+        self.added_columns.is_empty() && self.removed_columns.is_empty()
+    }
+}
+
+impl DataTable {
+    /// Compares this table with `other`, matching rows by position.
+    ///
+    /// Columns are matched by name, and unnamed columns by position. Rows
+    /// beyond the end of the shorter table are reported as added or removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let before = DataTable::from_records(Some(vec!["x"]), vec![vec!["1"], vec!["2"]]).unwrap();
+    /// let records = vec![vec!["1"], vec!["3"], vec!["4"]];
+    /// let after = DataTable::from_records(Some(vec!["x"]), records).unwrap();
+    ///
+    /// let diff = before.compare(&after);
+    ///
+    /// assert_eq!(diff.added_rows, vec![2]);
+    /// assert_eq!(diff.changed[0].row, 1);
+    /// assert_eq!(diff.changed[0].new, Some("3".to_string()));
+    /// assert!(before.compare(&before).is_empty());
+    /// ```
+    pub fn compare(&self, other: &DataTable) -> TableDiff {
+        let shared = self.rows().min(other.rows());
+        let pairs: Vec<(usize, usize)> = (0..shared).map(|row| (row, row)).collect();
+
+        let mut diff = self.diff_rows(other, &pairs);
+        diff.removed_rows = (shared..self.rows()).collect();
+        diff.added_rows = (shared..other.rows()).collect();
+        diff
+    }
+
+    /// Compares this table with `other`, matching rows by their value in the `key` column.
+    ///
+    /// Columns are matched as in `compare`. The key column is not reported
+    /// as changed.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : Either table has no `key` column, or a key
+    ///   appears more than once in a table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["a", "1"], vec!["b", "2"]];
+    /// let before = DataTable::from_records(Some(vec!["id", "v"]), records).unwrap();
+    /// let records = vec![vec!["c", "9"], vec!["a", "5"]];
+    /// let after = DataTable::from_records(Some(vec!["id", "v"]), records).unwrap();
+    ///
+    /// let diff = before.compare_by(&after, "id").unwrap();
+    ///
+    /// assert_eq!(diff.removed_rows, vec![1]);
+    /// assert_eq!(diff.added_rows, vec![0]);
+    /// assert_eq!((diff.changed[0].row, diff.changed[0].other_row), (0, 1));
+    /// assert_eq!(diff.changed[0].column, "v");
+    /// ```
+    pub fn compare_by(&self, other: &DataTable, key: &str) -> Result<TableDiff, DataError> {
+        let left = self.column(key).ok_or(DataError::InvalidStateError)?;
+        let right = other.column(key).ok_or(DataError::InvalidStateError)?;
+        let rows = key_rows(left)?;
+        let other_rows = key_rows(right)?;
+
+        let mut pairs = Vec::new();
+        let mut removed = Vec::new();
+        for (row, val) in left.iter().enumerate() {
+            match other_rows.get(val) {
+                Some(&other_row) => pairs.push((row, other_row)),
+                None => removed.push(row),
+            }
+        }
+
+        let added = right.iter()
+            .enumerate()
+            .filter(|&(_, val)| !rows.contains_key(val))
+            .map(|(row, _)| row)
+            .collect();
+
+        let mut diff = self.diff_rows(other, &pairs);
+        diff.changed.retain(|c| c.column != key);
+        diff.removed_rows = removed;
+        diff.added_rows = added;
+        Ok(diff)
+    }
+
+    /// Compares the matched columns of each pair of rows.
+    fn diff_rows(&self, other: &DataTable, pairs: &[(usize, usize)]) -> TableDiff {
+        let mut diff = TableDiff::default();
+        let mut matched = vec![false; other.cols()];
+
+        for (col, column) in self.data_cols.iter().enumerate() {
+            let name = column_label(column, col);
+            let found = match column.name {
+                Some(ref n) => other.column_index(n),
+                None if col < other.cols() && other.data_cols[col].name.is_none() => Some(col),
+                None => None,
+            };
+
+            let other_col = match found {
+                Some(other_col) => other_col,
+                None => {
+                    diff.removed_columns.push(name);
+                    continue;
+                }
+            };
+            matched[other_col] = true;
+            let other_column = &other.data_cols[other_col];
+
+            for &(row, other_row) in pairs {
+                let old = cell(column, row);
+                let new = cell(other_column, other_row);
+
+                if old != new {
+                    diff.changed.push(CellChange {
+                        row,
+                        other_row,
+                        column: name.clone(),
+                        old: old.map(String::from),
+                        new: new.map(String::from),
+                    });
+                }
+            }
+        }
+
+        diff.added_columns = other.data_cols
+            .iter()
+            .enumerate()
+            .filter(|&(col, _)| !matched[col])
+            .map(|(col, column)| column_label(column, col))
+            .collect();
+
+        // Report changes row by row rather than column by column.
+        diff.changed.sort_by_key(|c| c.row);
+        diff
+    }
+}
+
+/// Maps each value of the key column to its row.
+fn key_rows(column: &DataColumn) -> Result<HashMap<&str, usize>, DataError> {
+    let mut rows = HashMap::with_capacity(column.len());
+
+    for (row, val) in column.iter().enumerate() {
+        if rows.insert(val, row).is_some() {
+            return Err(DataError::InvalidStateError);
+        }
+    }
+
+    Ok(rows)
+}
+
+/// The value at the row, or `None` if it is missing.
+pub(crate) fn cell(column: &DataColumn, row: usize) -> Option<&str> {
+    if column.is_missing(row) { None } else { column.get(row) }
+}
+
+/// The name of the column, or its index if it has no name.
+fn column_label(column: &DataColumn, col: usize) -> String {
+    column.name.clone().unwrap_or_else(|| col.to_string())
+}
@@ -4,16 +4,33 @@
 //! for converting the tables to various formats.
 
 use std;
+use std::any::Any;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::iter::FromIterator;
 use std::str::FromStr;
+use std::mem;
 use std::ops::Index;
+use std::sync::Arc;
 use std::vec::IntoIter;
 
-use num::traits::{One, Zero};
+use num::traits::{Float, One, Zero};
 
-use error::DataError;
+use error::{CastError, CellLocation, DataError, ErrorReport, Warnings};
+use parse;
+use schema::DType;
+use storage::{Compression, Interner, SmallString, Storage, StorageIter, StringArena};
 
 /// A data table consisting of varying column types and headers.
+///
+/// Tables are `Send` and `Sync`. Use a `SharedTable` to share one
+/// between threads without copying it.
+///
+/// Cloning a table is cheap, the columns of the clone share their
+/// values with the original until either is changed.
+#[derive(Clone)]
 pub struct DataTable {
     /// Vector of DataColumns.
     pub data_cols: Vec<DataColumn>,
@@ -32,13 +49,277 @@ impl DataTable {
 
     /// The number of rows in the DataTable.
     pub fn rows(&self) -> usize {
-        if self.data_cols.len() > 0 {
+        if !self.data_cols.is_empty() {
             return self.data_cols[0].len();
         }
 
         0usize
     }
 
+    /// Gets a view of the row at the index, if it exists.
+    pub fn row(&self, idx: usize) -> Option<Row<'_>> {
+        if idx < self.rows() {
+            Some(Row { table: self, idx })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the value in the given row and column, if it exists.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let table = DataTable::from_records(None::<Vec<&str>>, vec![vec!["a", "b"]]).unwrap();
+    ///
+    /// assert_eq!(table.cell(0, 1), Some("b"));
+    /// assert_eq!(table.cell(1, 0), None);
+    /// assert_eq!(table.cell(0, 2), None);
+    /// ```
+    pub fn cell(&self, row: usize, col: usize) -> Option<&str> {
+        self.data_cols.get(col).and_then(|c| c.get(row))
+    }
+
+    /// Returns an iterator over views of each row in the table.
+    pub fn iter_rows(&self) -> RowIter<'_> {
+        RowIter {
+            table: self,
+            next: 0,
+            end: self.rows(),
+        }
+    }
+
+    /// Finds the index of the column with the given name.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.column_index_by(name, HeaderMatch::Exact)
+    }
+
+    /// Finds the index of the first column whose name matches using `matching`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{DataTable, HeaderMatch};
+    ///
+    /// let table = DataTable::from_records(Some(vec!["Temperature "]), vec![vec!["20"]]).unwrap();
+    ///
+    /// assert_eq!(table.column_index("temperature"), None);
+    /// assert_eq!(table.column_index_by("temperature", HeaderMatch::Insensitive), Some(0));
+    /// ```
+    pub fn column_index_by(&self, name: &str, matching: HeaderMatch) -> Option<usize> {
+        self.data_cols
+            .iter()
+            .position(|c| c.name.as_ref().is_some_and(|n| matching.matches(n, name)))
+    }
+
+    /// Gets the column with the given name, if it exists.
+    pub fn column(&self, name: &str) -> Option<&DataColumn> {
+        self.column_by(name, HeaderMatch::Exact)
+    }
+
+    /// Gets the first column whose name matches using `matching`, if it exists.
+    pub fn column_by(&self, name: &str, matching: HeaderMatch) -> Option<&DataColumn> {
+        self.column_index_by(name, matching).map(|idx| &self.data_cols[idx])
+    }
+
+    /// Appends a row with one value for each column.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The number of values does not match the number of columns.
+    pub fn push_row<I, S>(&mut self, values: I) -> Result<(), DataError>
+        where I: IntoIterator<Item = S>,
+              S: Into<SmallString>
+    {
+        let rows = self.rows();
+        self.insert_row(rows, values)
+    }
+
+    /// Inserts a row at the index, shifting later rows along.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The index is greater than the number of rows,
+    ///   or the number of values does not match the number of columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let mut table = DataTable::from_records(Some(vec!["x", "y"]), vec![vec!["1", "2"]]).unwrap();
+    ///
+    /// table.insert_row(0, vec!["0", "1"]).unwrap();
+    /// table.push_row(vec!["2", "3"]).unwrap();
+    /// assert!(table.push_row(vec!["4"]).is_err());
+    ///
+    /// assert_eq!(table.remove_row(1), Some(vec!["1".to_string(), "2".to_string()]));
+    /// assert_eq!(table[1].data(), vec!["1", "3"]);
+    /// ```
+    pub fn insert_row<I, S>(&mut self, idx: usize, values: I) -> Result<(), DataError>
+        where I: IntoIterator<Item = S>,
+              S: Into<SmallString>
+    {
+        let values: Vec<SmallString> = values.into_iter().map(Into::into).collect();
+
+        if idx > self.rows() || values.len() != self.cols() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        for (column, val) in self.data_cols.iter_mut().zip(values) {
+            column.insert(idx, val);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the row at the index, returning its values if it exists.
+    pub fn remove_row(&mut self, idx: usize) -> Option<Vec<String>> {
+        if idx >= self.rows() {
+            return None;
+        }
+
+        Some(self.data_cols.iter_mut().map(|c| c.remove(idx)).collect())
+    }
+
+    /// Shortens the table to `rows` rows, doing nothing if it is already shorter.
+    pub fn truncate(&mut self, rows: usize) {
+        for column in self.data_cols.iter_mut() {
+            column.truncate(rows);
+        }
+    }
+
+    /// Keeps only the rows for which `keep` returns true.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["a", "1"], vec!["b", "5"], vec!["c", "2"]];
+    /// let mut table = DataTable::from_records(None::<Vec<&str>>, records).unwrap();
+    ///
+    /// table.retain(|row| row.get_as::<u32>(1).unwrap() < 3);
+    /// assert_eq!(table[0].data(), vec!["a", "c"]);
+    /// ```
+    pub fn retain<F: FnMut(Row) -> bool>(&mut self, keep: F) {
+        let mask: Vec<bool> = self.iter_rows().map(keep).collect();
+        self.retain_mask(&mask);
+    }
+
+    /// Keeps only the rows whose position in `mask` is true.
+    ///
+    /// # Panics
+    ///
+    /// - The mask is not the same length as the columns.
+    pub fn retain_mask(&mut self, mask: &[bool]) {
+        for column in self.data_cols.iter_mut() {
+            column.retain_mask(mask);
+        }
+    }
+
+    /// Shares identical values across every column through one string pool.
+    ///
+    /// Returns the interner, which can be used to intern other columns
+    /// into the same pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["PASS", "FAIL"], vec!["FAIL", "PASS"]];
+    /// let mut table = DataTable::from_records(None::<Vec<&str>>, records).unwrap();
+    ///
+    /// let interner = table.intern();
+    ///
+    /// assert_eq!(interner.len(), 2);
+    /// assert!(table[1].interner().unwrap().shares_pool(&interner));
+    /// ```
+    pub fn intern(&mut self) -> Interner {
+        let interner = Interner::new();
+
+        for column in self.data_cols.iter_mut() {
+            column.intern_with(&interner);
+        }
+
+        interner
+    }
+
+    /// Builds a new table from the rows at the given indices, in that order.
+    ///
+    /// Rows can be repeated. Values keep their missing mark.
+    ///
+    /// # Panics
+    ///
+    /// - An index is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["a"], vec!["b"], vec!["c"]];
+    /// let table = DataTable::from_records(Some(vec!["x"]), records).unwrap();
+    ///
+    /// assert_eq!(table.take_rows(&[2, 0, 0])[0].data(), vec!["c", "a", "a"]);
+    /// ```
+    pub fn take_rows(&self, rows: &[usize]) -> DataTable {
+        let mut table = DataTable {
+            data_cols: self.data_cols.iter().map(|c| c.empty_like()).collect(),
+        };
+
+        for &row in rows {
+            for (from, to) in self.data_cols.iter().zip(table.data_cols.iter_mut()) {
+                to.push_from(from, row);
+            }
+        }
+
+        table
+    }
+
+    /// Builds a new table from the columns with the given names, in that order.
+    ///
+    /// The new columns share their values with this table until either is changed.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has one of the names.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "a"], vec!["2", "b"]];
+    /// let table = DataTable::from_records(Some(vec!["id", "label"]), records).unwrap();
+    ///
+    /// let labels = table.select(&["label"]).unwrap();
+    /// assert_eq!(labels.cols(), 1);
+    /// assert_eq!(labels[0].data(), vec!["a", "b"]);
+    /// ```
+    pub fn select(&self, names: &[&str]) -> Result<DataTable, DataError> {
+        let data_cols = names.iter()
+            .map(|name| self.column(name).cloned().ok_or(DataError::InvalidStateError))
+            .collect::<Result<_, _>>()?;
+
+        Ok(DataTable { data_cols })
+    }
+
+    /// Builds a new table from the first `n` rows.
+    ///
+    /// If the table has at most `n` rows the columns share their values
+    /// with this table, otherwise the first `n` values are copied.
+    pub fn head(&self, n: usize) -> DataTable {
+        if n >= self.rows() {
+            return self.clone();
+        }
+
+        self.take_rows(&(0..n).collect::<Vec<_>>())
+    }
+
     /// Shrinks the table and it's underlying columns.
     pub fn shrink_to_fit(&mut self) {
         for col in self.data_cols.iter_mut() {
@@ -52,129 +333,1686 @@ impl DataTable {
     ///
     /// Uses column major ordering.
     ///
+    /// Floating point values written as `NaN` or `inf` are kept as is,
+    /// use `DataColumn::cast_float` to treat them differently. Use
+    /// `into_consistent_data_with` to choose what happens to values which
+    /// cannot be cast.
+    ///
     /// # Failures
     ///
     /// - DataCastError : Returned when the data cannot be cast into the requested type.
-    pub fn into_consistent_data<T: FromStr>(self, row_major: bool) -> Result<Vec<T>, DataError> {
+    ///   The error records the row, column index and column name of the first
+    ///   value which fails, in the requested ordering.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::error::DataError;
+    ///
+    /// let table = DataTable::from_records(None::<Vec<&str>>,
+    ///                                     vec![vec!["1.5", "NaN"], vec!["-inf", "2"]])
+    ///     .unwrap();
+    /// let data = table.into_consistent_data::<f64>(true).unwrap();
+    ///
+    /// assert_eq!(data[0], 1.5);
+    /// assert!(data[1].is_nan());
+    /// assert_eq!(data[2], ::std::f64::NEG_INFINITY);
+    ///
+    /// let table = DataTable::from_records(Some(vec!["a", "b"]),
+    ///                                     vec![vec!["1", "?"], vec!["?", "2"]])
+    ///     .unwrap();
+    ///
+    /// match table.clone().into_consistent_data::<f64>(true) {
+    ///     Err(DataError::DataCastError(e)) => {
+    ///         assert_eq!(e.location.row, Some(0));
+    ///         assert_eq!(e.location.column, Some(1));
+    ///         assert_eq!(e.location.column_name, Some("b".to_string()));
+    ///     }
+    ///     _ => panic!("expected a cast error"),
+    /// }
+    ///
+    /// match table.into_consistent_data::<f64>(false) {
+    ///     Err(DataError::DataCastError(e)) => {
+    ///         assert_eq!(e.location.row, Some(1));
+    ///         assert_eq!(e.location.column, Some(0));
+    ///     }
+    ///     _ => panic!("expected a cast error"),
+    /// }
+    /// ```
+    pub fn into_consistent_data<T>(self, row_major: bool) -> Result<Vec<T>, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.consistent_data(row_major, DataColumn::into_vec)
+    }
+
+    /// Consumes self and converts the DataTable into a single Vec as in
+    /// `into_consistent_data`, using the values stored by `cast_cached`.
+    ///
+    /// Columns whose cast values of the requested type are stored, such as
+    /// those checked by `convert_columns`, are not parsed again.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : Returned when the data cannot be cast into the requested type.
+    ///   The error records the row, column index and column name of the first
+    ///   value which fails, in the requested ordering.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::schema::DType;
+    ///
+    /// let records = vec![vec!["1", "2.5"], vec!["3", "4.5"]];
+    /// let mut table = DataTable::from_records(Some(vec!["a", "b"]), records).unwrap();
+    /// table.convert_columns(&[("a", DType::F64), ("b", DType::F64)]).unwrap();
+    ///
+    /// let data = table.into_consistent_data_cached::<f64>(true).unwrap();
+    /// assert_eq!(data, vec![1.0, 2.5, 3.0, 4.5]);
+    /// ```
+    pub fn into_consistent_data_cached<T>(self, row_major: bool) -> Result<Vec<T>, DataError>
+        where T: FromStr + 'static,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.consistent_data(row_major, DataColumn::into_vec_cached)
+    }
+
+    /// Converts each column with `into_vec` and joins them in the requested ordering.
+    fn consistent_data<T, F>(self, row_major: bool, into_vec: F) -> Result<Vec<T>, DataError>
+        where F: Fn(DataColumn) -> Result<Vec<T>, DataError>
+    {
         let cols = self.cols();
         let rows = self.rows();
 
         let mut table_data = Vec::with_capacity(cols * rows);
         if row_major {
             let mut column_iters = Vec::new();
+            // The failure in the earliest row, as each column stops at its own first.
+            let mut first_error: Option<(usize, DataError)> = None;
+
+            for (i, d) in self.data_cols.into_iter().enumerate() {
+                match in_column(into_vec(d), i) {
+                    Ok(x) => column_iters.push(x.into_iter()),
+                    Err(e) => {
+                        let row = match e {
+                            DataError::DataCastError(ref e) => e.location.row.unwrap_or(0),
+                            _ => 0,
+                        };
+                        if first_error.as_ref().is_none_or(|&(first, _)| row < first) {
+                            first_error = Some((row, e));
+                        }
+                    }
+                }
+            }
+
+            if let Some((_, e)) = first_error {
+                return Err(e);
+            }
+
+            for _ in 0..rows {
+                for column_iter in column_iters.iter_mut() {
+                    if let Some(x) = column_iter.next() {
+                        table_data.push(x);
+                    }
+                }
+            }
+        }
+        else {
+            for (i, d) in self.data_cols.into_iter().enumerate() {
+                match in_column(into_vec(d), i) {
+                    Ok(x) => table_data.extend(x),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        if table_data.len() != cols*rows {
+            return Err(DataError::InvalidStateError);
+        }
+        
+
+        Ok(table_data)
+    }
+
+    /// Consumes self and converts the DataTable into a single Vec, treating
+    /// values which cannot be cast according to `policy`.
+    ///
+    /// Uses the same ordering as `into_consistent_data`. Values marked as
+    /// missing are returned as `None`. Under `CastPolicy::SkipRow` a value
+    /// which cannot be cast drops its whole row from every column.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be cast and the policy is `Fail`.
+    ///   The error records the row and column of the first such value in
+    ///   the requested ordering.
+    /// - InvalidStateError : The columns have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{CastPolicy, DataTable};
+    ///
+    /// let records = vec![vec!["1", "2"], vec!["N/A", "4"], vec!["5", "6"]];
+    /// let table = DataTable::from_records(None::<Vec<&str>>, records).unwrap();
+    ///
+    /// assert!(table.clone().into_consistent_data::<u32>(true).is_err());
+    ///
+    /// let data = table.clone().into_consistent_data_with(true, CastPolicy::SkipRow).unwrap();
+    /// assert_eq!(data, vec![Some(1), Some(2), Some(5), Some(6)]);
+    ///
+    /// let data = table.into_consistent_data_with(false, CastPolicy::Default(0)).unwrap();
+    /// assert_eq!(data, vec![Some(1), Some(0), Some(5), Some(2), Some(4), Some(6)]);
+    /// ```
+    pub fn into_consistent_data_with<T>(self,
+                                        row_major: bool,
+                                        policy: CastPolicy<T>)
+                                        -> Result<Vec<Option<T>>, DataError>
+        where T: FromStr + Clone,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.into_consistent_data_with_warnings(row_major, policy).map(|(data, _)| data)
+    }
+
+    /// Consumes self and converts the DataTable into a single Vec as in
+    /// `into_consistent_data_with`, returning warnings alongside the data.
+    ///
+    /// A warning is recorded for each column holding values which could not
+    /// be cast, giving how many there were and what the policy did with them.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be cast and the policy is `Fail`.
+    ///   The error records the row and column of the first such value in
+    ///   the requested ordering.
+    /// - InvalidStateError : The columns have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{CastPolicy, DataTable};
+    ///
+    /// let records = vec![vec!["1", "x"], vec!["N/A", "?"]];
+    /// let table = DataTable::from_records(Some(vec!["a", "b"]), records).unwrap();
+    ///
+    /// let (data, warnings) =
+    ///     table.into_consistent_data_with_warnings::<u32>(true, CastPolicy::Null).unwrap();
+    ///
+    /// assert_eq!(data, vec![Some(1), None, None, None]);
+    /// assert_eq!(warnings.len(), 2);
+    /// assert_eq!(warnings.warnings()[1].to_string(),
+    ///            "column 1: 2 unparseable values coerced to NA");
+    /// ```
+    pub fn into_consistent_data_with_warnings<T>(self,
+                                                 row_major: bool,
+                                                 policy: CastPolicy<T>)
+                                                 -> Result<(Vec<Option<T>>, Warnings), DataError>
+        where T: FromStr + Clone,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let cols = self.cols();
+        let rows = self.rows();
+
+        if self.data_cols.iter().any(|d| d.len() != rows) {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut columns = Vec::with_capacity(cols);
+        let mut skip = vec![false; rows];
+        let mut warnings = Warnings::new();
+        let mut first_error: Option<(usize, DataError)> = None;
+
+        for (i, d) in self.data_cols.iter().enumerate() {
+            match d.cast_by_policy(&policy) {
+                Ok(cast) => {
+                    for (s, skipped) in skip.iter_mut().zip(cast.skip) {
+                        *s |= skipped;
+                    }
+                    if cast.coerced > 0 {
+                        let message = coercion_warning(&policy, cast.coerced);
+                        log_warn!("column {}: {}", i, message);
+                        warnings.push(None, Some(i), message);
+                    }
+                    columns.push(cast.values);
+                }
+                Err((idx, e)) => {
+                    // Row major order fails at the earliest row of any column.
+                    if first_error.as_ref().is_none_or(|&(first, _)| idx < first) {
+                        let mut location = d.location(idx);
+                        location.column = Some(i);
+                        let err = CastError::new::<T, _>(&d[idx], e).at(location);
+                        first_error = Some((idx, DataError::DataCastError(err)));
+                    }
+                    if !row_major {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some((_, e)) = first_error {
+            return Err(e);
+        }
+
+        let kept = skip.iter().filter(|&&s| !s).count();
+        let mut column_iters: Vec<_> =
+            columns.into_iter().map(|c| without_skipped(c, &skip).into_iter()).collect();
+
+        let mut table_data = Vec::with_capacity(cols * kept);
+        if row_major {
+            for _ in 0..kept {
+                for column_iter in column_iters.iter_mut() {
+                    table_data.extend(column_iter.next());
+                }
+            }
+        } else {
+            for column_iter in column_iters {
+                table_data.extend(column_iter);
+            }
+        }
+
+        Ok((table_data, warnings))
+    }
+
+    /// Consumes self and attempts to convert the DataTable into a single Vec,
+    /// reporting every cell which fails to cast.
+    ///
+    /// Uses the same ordering as `into_consistent_data`.
+    ///
+    /// # Failures
+    ///
+    /// - Returns an `ErrorReport` with a `DataCastError` for each cell which
+    ///   cannot be cast into the requested type.
+    pub fn into_consistent_data_report<T>(self, row_major: bool) -> Result<Vec<T>, ErrorReport>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let cols = self.cols();
+        let rows = self.rows();
+
+        let mut report = ErrorReport::new();
+        let mut columns = Vec::with_capacity(cols);
+
+        for (i, d) in self.data_cols.into_iter().enumerate() {
+            match d.cast_report::<T>() {
+                Ok(x) => columns.push(x),
+                Err(col_report) => {
+                    for entry in col_report.into_entries() {
+                        report.push(entry.row, Some(i), entry.error);
+                    }
+                }
+            }
+        }
+
+        if !report.is_empty() {
+            return Err(report);
+        }
+
+        let mut table_data = Vec::with_capacity(cols * rows);
+        if row_major {
+            let mut column_iters: Vec<_> = columns.into_iter().map(|c| c.into_iter()).collect();
+
+            for _ in 0..rows {
+                for column_iter in column_iters.iter_mut() {
+                    if let Some(x) = column_iter.next() {
+                        table_data.push(x);
+                    }
+                }
+            }
+        } else {
+            for column in columns {
+                table_data.extend(column);
+            }
+        }
+
+        if table_data.len() != cols * rows {
+            report.push(0, None, DataError::InvalidStateError);
+            return Err(report);
+        }
+
+        Ok(table_data)
+    }
+}
+
+/// Records the column index on the location of a cast error.
+fn in_column<T>(result: Result<T, DataError>, column: usize) -> Result<T, DataError> {
+    result.map_err(|e| match e {
+        DataError::DataCastError(mut e) => {
+            e.location.column = Some(column);
+            DataError::DataCastError(e)
+        }
+        e => e,
+    })
+}
+
+/// How floating point casts treat `NaN` and infinite values.
+///
+/// These are written as `NaN`, `inf` or `infinity`, in any case and
+/// optionally signed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Non-finite values are kept.
+    #[default]
+    Accept,
+    /// Non-finite values are treated as missing.
+    Missing,
+    /// Non-finite values fail to cast.
+    Reject,
+}
+
+/// What a cast does with a value which cannot be parsed to the requested type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CastPolicy<T> {
+    /// The cast fails with the first such value.
+    #[default]
+    Fail,
+    /// The row holding the value is left out of the result.
+    SkipRow,
+    /// The value is returned as `None`.
+    Null,
+    /// The value is replaced by the given one.
+    Default(T),
+}
+
+/// How column names are compared with the names used to look columns up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeaderMatch {
+    /// The names must be identical.
+    #[default]
+    Exact,
+    /// Case and leading or trailing whitespace are ignored.
+    Insensitive,
+}
+
+impl HeaderMatch {
+    /// Returns true if the header matches the name.
+    pub fn matches(self, header: &str, name: &str) -> bool {
+        match self {
+            HeaderMatch::Exact => header == name,
+            HeaderMatch::Insensitive => {
+                header.trim()
+                    .chars()
+                    .flat_map(char::to_lowercase)
+                    .eq(name.trim().chars().flat_map(char::to_lowercase))
+            }
+        }
+    }
+}
+
+impl Index<usize> for DataTable { 
+    type Output = DataColumn;
+
+    fn index(&self, idx: usize) -> &DataColumn {
+        &self.data_cols[idx]
+    }
+}
+
+/// A view of a single row in a DataTable.
+#[derive(Clone, Copy)]
+pub struct Row<'a> {
+    table: &'a DataTable,
+    idx: usize,
+}
+
+impl<'a> Row<'a> {
+    /// The index of the row within the table.
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+
+    /// The number of values in the row.
+    pub fn len(&self) -> usize {
+        self.table.cols()
+    }
+
+    /// Returns true if the row contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.table.cols() == 0
+    }
+
+    /// Gets the value in the given column, if it exists.
+    pub fn get(&self, col: usize) -> Option<&'a str> {
+        match self.table.data_cols.get(col) {
+            Some(column) => column.data.get(self.idx),
+            None => None,
+        }
+    }
+
+    /// Try to get the value in the given column as the requested type.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : The value could not be parsed to this type.
+    /// - InvalidStateError : The row has no value in the column.
+    pub fn get_as<T>(&self, col: usize) -> Result<T, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        match self.get(col) {
+            Some(val) => {
+                cast_value(val, || {
+                    let mut location = self.table.data_cols[col].location(self.idx);
+                    location.column = Some(col);
+                    location
+                })
+            }
+            None => Err(DataError::InvalidStateError),
+        }
+    }
+}
+
+/// An iterator over the rows of a DataTable.
+pub struct RowIter<'a> {
+    pub(crate) table: &'a DataTable,
+    pub(crate) next: usize,
+    pub(crate) end: usize,
+}
+
+impl<'a> Iterator for RowIter<'a> {
+    type Item = Row<'a>;
+
+    fn next(&mut self) -> Option<Row<'a>> {
+        if self.next < self.end {
+            self.next += 1;
+            Some(Row {
+                table: self.table,
+                idx: self.next - 1,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+/// A data column consisting of Strings.
+///
+/// Values are held as `SmallString`s so that short values do not
+/// need their own heap allocation. Columns can instead share a single
+/// `StringArena` between all of their values, see `use_arena`.
+pub struct DataColumn {
+    /// The name associated with the DataColumn.
+    pub name: Option<String>,
+    // Shared between clones until one of them is changed.
+    categories: Option<Arc<Categories>>,
+    data: Arc<Storage>,
+    cache: Option<Box<dyn Any + Send + Sync>>,
+    dtype: Option<DType>,
+    // Only as long as the last missing value, later values are present.
+    missing: Arc<Vec<bool>>,
+}
+
+impl DataColumn {
+    /// Constructs an empty data column.
+    pub fn empty() -> DataColumn {
+        DataColumn {
+            name: None,
+            categories: None,
+            data: Arc::new(Storage::Inline(Vec::new())),
+            cache: None,
+            dtype: None,
+            missing: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Constructs an empty data column backed by a `StringArena`.
+    pub fn empty_arena() -> DataColumn {
+        DataColumn {
+            name: None,
+            categories: None,
+            data: Arc::new(Storage::Arena(StringArena::new())),
+            cache: None,
+            dtype: None,
+            missing: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Constructs a data column holding each value converted to a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec![1.5, 2.5]);
+    /// assert_eq!(dc.data(), vec!["1.5", "2.5"]);
+    ///
+    /// let squares: DataColumn = (1..4).map(|x| x * x).collect();
+    /// assert_eq!(squares.data(), vec!["1", "4", "9"]);
+    /// ```
+    pub fn from_vec<T: ToString>(values: Vec<T>) -> DataColumn {
+        values.into_iter().collect()
+    }
+
+    /// Gets the length of the data column.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns true if the data column contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.data.len() == 0
+    }
+
+    /// Gets a view of the values in the column.
+    ///
+    /// The view borrows the column, so this does not copy or allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec!["a", "b"]);
+    /// let data = dc.data();
+    ///
+    /// assert_eq!(data.len(), 2);
+    /// assert_eq!(&data[1], "b");
+    /// assert_eq!(data, vec!["a", "b"]);
+    /// ```
+    pub fn data(&self) -> ColumnValues<'_> {
+        ColumnValues { column: self }
+    }
+
+    /// Gets the value at the index, if it exists.
+    ///
+    /// Unlike indexing this does not panic when the index is out of range.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.data.get(idx)
+    }
+
+    /// Gets the value at the index without checking that it is in range.
+    ///
+    /// Intended for hot loops which have already checked the length of
+    /// the column once.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than `self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec![1, 2, 3]);
+    /// let mut total = 0;
+    ///
+    /// for i in 0..dc.len() {
+    ///     // The loop bound keeps the index in range.
+    ///     total += unsafe { dc.get_unchecked(i) }.len();
+    /// }
+    ///
+    /// assert_eq!(total, 3);
+    /// ```
+    pub unsafe fn get_unchecked(&self, idx: usize) -> &str {
+        self.data.get_unchecked(idx)
+    }
+
+    /// Gets the values as a slice, if they are stored inline.
+    ///
+    /// Returns `None` for columns using an arena, compression or interning.
+    /// Call `use_inline` first to make sure a slice is available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec!["a", "b"]);
+    /// assert_eq!(&dc.as_slice().unwrap()[1][..], "b");
+    ///
+    /// dc.use_arena();
+    /// assert!(dc.as_slice().is_none());
+    /// ```
+    pub fn as_slice(&self) -> Option<&[SmallString]> {
+        match *self.data {
+            Storage::Inline(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the values in the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("a");
+    /// dc.push("bc");
+    ///
+    /// let total: usize = dc.iter().map(|s| s.len()).sum();
+    /// assert_eq!(total, 3);
+    /// ```
+    pub fn iter(&self) -> ColumnIter<'_> {
+        ColumnIter { inner: self.data.iter() }
+    }
+
+    /// Returns true if both columns share the same values.
+    ///
+    /// Clones share the values of the column until either is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc: DataColumn = vec!["a", "b"].into_iter().collect();
+    /// let mut copy = dc.clone();
+    /// assert!(copy.shares_data(&dc));
+    ///
+    /// copy.push("c");
+    /// assert!(!copy.shares_data(&dc));
+    /// assert_eq!(dc.data(), vec!["a", "b"]);
+    /// ```
+    pub fn shares_data(&self, other: &DataColumn) -> bool {
+        Arc::ptr_eq(&self.data, &other.data)
+    }
+
+    /// Returns true if the column is backed by a `StringArena`.
+    pub fn is_arena(&self) -> bool {
+        matches!(*self.data, Storage::Arena(_))
+    }
+
+    /// Moves the data in the column into a single `StringArena`.
+    ///
+    /// Columns holding many values benefit from the arena as it replaces
+    /// an allocation per long value with a few large allocations, and keeps
+    /// the values together in memory for faster iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("1.0");
+    /// dc.push("2.0");
+    ///
+    /// dc.use_arena();
+    /// dc.push("3.0");
+    ///
+    /// assert!(dc.is_arena());
+    /// assert_eq!(dc.into_vec::<f64>().unwrap(), vec![1.0, 2.0, 3.0]);
+    /// ```
+    pub fn use_arena(&mut self) {
+        let data = self.take_data();
+        self.data = Arc::new(Storage::Arena(data.into_arena()));
+    }
+
+    /// Moves the data in the column back into individual `SmallString`s.
+    ///
+    /// This also decompresses a column compressed with `compress` and
+    /// gives each value of an interned column its own copy.
+    pub fn use_inline(&mut self) {
+        let data = self.take_data();
+        self.data = Arc::new(Storage::Inline(data.into_inline()));
+    }
+
+    /// Compresses the data in the column.
+    ///
+    /// Values are decompressed transparently when accessed, and new values
+    /// can still be pushed onto a compressed column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::storage::Compression;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// for _ in 0..1000 {
+    ///     dc.push("PASS");
+    ///     dc.push("FAIL");
+    /// }
+    ///
+    /// dc.compress(Compression::Dictionary);
+    ///
+    /// assert_eq!(dc.compression(), Some(Compression::Dictionary));
+    /// assert_eq!(&dc[1], "FAIL");
+    /// ```
+    pub fn compress(&mut self, compression: Compression) {
+        let data = self.take_data();
+        self.data = Arc::new(data.into_compressed(compression));
+    }
+
+    /// The compression used by the column, if any.
+    pub fn compression(&self) -> Option<Compression> {
+        match *self.data {
+            Storage::RunLength(_) => Some(Compression::RunLength),
+            Storage::Dictionary(_) => Some(Compression::Dictionary),
+            _ => None,
+        }
+    }
+
+    /// Shares identical values in the column through a new string pool.
+    ///
+    /// Each distinct value is allocated once and values pushed later are
+    /// added to the same pool. See `intern_with` to share a pool between
+    /// columns and `DataTable::intern` to intern a whole table.
+    pub fn intern(&mut self) {
+        self.intern_with(&Interner::new());
+    }
+
+    /// Shares identical values in the column through the interner's pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::storage::Interner;
+    ///
+    /// let interner = Interner::new();
+    /// let mut a: DataColumn = vec!["PASS", "FAIL", "PASS"].into_iter().collect();
+    /// let mut b: DataColumn = vec!["FAIL", "FAIL"].into_iter().collect();
+    ///
+    /// a.intern_with(&interner);
+    /// b.intern_with(&interner);
+    /// b.push("SKIP");
+    ///
+    /// assert_eq!(interner.len(), 3);
+    /// assert_eq!(b.data(), vec!["FAIL", "FAIL", "SKIP"]);
+    /// ```
+    pub fn intern_with(&mut self, interner: &Interner) {
+        let data = self.take_data();
+        self.data = Arc::new(data.into_interned(interner.clone()));
+    }
+
+    /// The interner sharing the values of the column, if it is interned.
+    pub fn interner(&self) -> Option<&Interner> {
+        match *self.data {
+            Storage::Interned(ref i) => Some(i.interner()),
+            _ => None,
+        }
+    }
+
+    /// Gets an immutable reference to the categories Option.
+    pub fn categories(&self) -> Option<HashMap<String, usize>> {
+        self.categories.as_ref().map(|c| c.indices.clone())
+    }
+
+    /// Replaces the categories, used when applying a `CategoryMap`.
+    pub(crate) fn set_categories(&mut self, categories: HashMap<String, usize>) {
+        self.categories = Some(Arc::new(Categories::new(categories)));
+    }
+
+    /// Gets the category label with the given index.
+    ///
+    /// Returns `None` if the categories have not been set or no
+    /// category has the index. Use `category_labels` to decode many
+    /// indices at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc: DataColumn = vec!["cat", "dog", "cat"].into_iter().collect();
+    /// dc.update_categories();
+    ///
+    /// let idx = dc.categories().unwrap()["dog"];
+    /// assert_eq!(dc.decode_category(idx), Some("dog"));
+    /// assert_eq!(dc.decode_category(5), None);
+    /// ```
+    pub fn decode_category(&self, idx: usize) -> Option<&str> {
+        self.categories.as_ref()?.labels.get(idx)?.as_deref()
+    }
+
+    /// Gets the category labels ordered by their index.
+    ///
+    /// The label for index `i` is at position `i`, so this is the
+    /// inverse of `categories`. Returns `None` if the categories have
+    /// not been set, or if some index below the largest has no label.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc: DataColumn = vec!["cat", "dog", "cat"].into_iter().collect();
+    /// dc.update_categories();
+    ///
+    /// let labels = dc.category_labels().unwrap();
+    /// let predictions = vec![1, 0, 0];
+    /// let decoded: Vec<&str> = predictions.iter().map(|&p| labels[p]).collect();
+    ///
+    /// assert_eq!(decoded, vec!["dog", "cat", "cat"]);
+    /// ```
+    pub fn category_labels(&self) -> Option<Vec<&str>> {
+        self.categories.as_ref()?.labels.iter().map(|label| label.as_deref()).collect()
+    }
+
+    /// Update the categories set using the current data.
+    ///
+    /// Categories are indexed in the order they first appear in the
+    /// column, so the same data always gives the same indices. See
+    /// `update_categories_sorted` to index them in sorted order instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    ///
+    /// dc.push("Class1".to_string());
+    /// dc.push("Class2".to_string());
+    /// dc.push("Class2".to_string());
+    ///
+    /// dc.update_categories();
+    /// let categories = dc.categories().unwrap();
+    ///
+    /// // Note that `contains` requires a reference so we pass an &str.
+    /// assert!(categories.contains_key("Class2"));
+    /// assert_eq!(categories.len(), 2);
+    /// ```
+    pub fn update_categories(&mut self) {
+        let mut categories = HashMap::new();
+        let mut count = 0usize;
+
+        for s in self.data.iter() {
+            if !categories.contains_key(s) {
+                categories.insert(s.to_string(), count);
+                count += 1usize;
+            }
+
+        }
+        self.set_categories(categories);
+    }
+
+    /// Update the categories set using the current data, indexing the
+    /// categories in sorted order.
+    ///
+    /// Unlike `update_categories` the indices do not depend on the order
+    /// of the rows, so files holding the same categories in a different
+    /// order are encoded identically.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc: DataColumn = vec!["b", "c", "a", "b"].into_iter().collect();
+    ///
+    /// dc.update_categories();
+    /// assert_eq!(dc.category_labels().unwrap(), vec!["b", "c", "a"]);
+    ///
+    /// dc.update_categories_sorted();
+    /// assert_eq!(dc.category_labels().unwrap(), vec!["a", "b", "c"]);
+    /// ```
+    pub fn update_categories_sorted(&mut self) {
+        let mut labels: Vec<&str> = self.data.iter().collect();
+        labels.sort_unstable();
+        labels.dedup();
+
+        let categories = labels.into_iter()
+            .enumerate()
+            .map(|(i, s)| (s.to_string(), i))
+            .collect();
+        self.set_categories(categories);
+    }
+
+    /// Produce a numerical vector representation of the category data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    ///
+    /// dc.push("Class1".to_string());
+    /// dc.push("Class2".to_string());
+    /// dc.push("Class2".to_string());
+    ///
+    /// dc.update_categories();
+    ///
+    /// let data = dc.numeric_category_data::<f64>().unwrap();
+    ///
+    /// println!("The data is: {:?}", data);
+    /// ```
+    pub fn numeric_category_data<T: Zero + One>(&self) -> Result<Vec<Vec<T>>, DataError> {
+        if let Some(ref categories) = self.categories {
+            let categories = &categories.indices;
+            let mut outer_vec = Vec::new();
+
+            for _ in 0..categories.len() {
+                outer_vec.push(Vec::<T>::new())
+            }
+
+            for d in self.data.iter() {
+                match categories.get(d) {
+                    Some(x) => {
+                        for (i, category_vec) in outer_vec.iter_mut().enumerate() {
+                            if *x == i {
+                                category_vec.push(T::one());
+                            } else {
+                                category_vec.push(T::zero());
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(DataError::InvalidStateError);
+                    }
+                }
+            }
+            return Ok(outer_vec);
+        }
+
+        Err(DataError::InvalidStateError)
+    }
+
+    /// The location of the value at `idx` within this column.
+    fn location(&self, idx: usize) -> CellLocation {
+        CellLocation {
+            row: Some(idx),
+            column: None,
+            column_name: self.name.clone(),
+        }
+    }
+
+    /// Pushes a new value to the column.
+    ///
+    /// Accepts a `String`, a `&str` or anything else which converts to a `SmallString`.
+    pub fn push<S: Into<SmallString>>(&mut self, val: S) {
+        self.invalidate();
+        Arc::make_mut(&mut self.data).push(val.into());
+    }
+
+    /// Inserts a value at the index, shifting later values along.
+    ///
+    /// # Panics
+    ///
+    /// - The index is greater than the length of the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.extend(vec!["a", "c", "d"]);
+    ///
+    /// dc.insert(1, "b");
+    /// assert_eq!(dc.remove(3), "d");
+    /// assert_eq!(dc.data(), vec!["a", "b", "c"]);
+    ///
+    /// dc.truncate(1);
+    /// assert_eq!(dc.data(), vec!["a"]);
+    /// ```
+    pub fn insert<S: Into<SmallString>>(&mut self, idx: usize, val: S) {
+        assert!(idx <= self.len(), "index {} out of bounds for column of length {}", idx, self.len());
+
+        self.invalidate();
+        let val = val.into();
+        Arc::make_mut(&mut self.data).edit(|v| v.insert(idx, val));
+
+        if idx < self.missing.len() {
+            Arc::make_mut(&mut self.missing).insert(idx, false);
+        }
+    }
+
+    /// Removes and returns the value at the index, shifting later values back.
+    ///
+    /// # Panics
+    ///
+    /// - The index is out of range.
+    pub fn remove(&mut self, idx: usize) -> String {
+        assert!(idx < self.len(), "index {} out of bounds for column of length {}", idx, self.len());
+
+        self.invalidate();
+        if idx < self.missing.len() {
+            Arc::make_mut(&mut self.missing).remove(idx);
+        }
+
+        Arc::make_mut(&mut self.data).edit(|v| v.remove(idx)).into_string()
+    }
+
+    /// Applies an edit to the present values in place.
+    ///
+    /// The edit must keep the number of values, missing values keep their mark.
+    pub(crate) fn edit_values<F, T>(&mut self, f: F) -> T
+        where F: FnOnce(&mut Vec<SmallString>) -> T
+    {
+        self.invalidate();
+        Arc::make_mut(&mut self.data).edit(f)
+    }
+
+    /// Shortens the column to `len` values, doing nothing if it is already shorter.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+
+        self.invalidate();
+        Arc::make_mut(&mut self.missing).truncate(len);
+        Arc::make_mut(&mut self.data).edit(|v| v.truncate(len));
+    }
+
+    /// Keeps only the values for which `keep` returns true.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.extend(vec!["1", "", "3"]);
+    ///
+    /// dc.retain(|s| !s.is_empty());
+    /// assert_eq!(dc.data(), vec!["1", "3"]);
+    /// ```
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, keep: F) {
+        let mask: Vec<bool> = self.iter().map(keep).collect();
+        self.retain_mask(&mask);
+    }
+
+    /// Keeps only the values whose position in `mask` is true.
+    ///
+    /// # Panics
+    ///
+    /// - The mask is not the same length as the column.
+    pub fn retain_mask(&mut self, mask: &[bool]) {
+        assert_eq!(mask.len(), self.len(), "mask length does not match the column length");
+
+        self.invalidate();
+        self.missing = Arc::new(self.missing
+            .iter()
+            .zip(mask)
+            .filter(|&(_, &keep)| keep)
+            .map(|(&missing, _)| missing)
+            .collect());
+
+        let mut keep = mask.iter();
+        Arc::make_mut(&mut self.data).edit(|v| v.retain(|_| *keep.next().unwrap_or(&false)));
+    }
+
+    /// Finds the indices of every present value equal to `val`.
+    pub(crate) fn positions(&self, val: &str) -> Vec<usize> {
+        let mut positions = self.data.positions(val);
+        if self.has_missing() {
+            positions.retain(|&idx| !self.is_missing(idx));
+        }
+        positions
+    }
+
+    /// An empty column with the same name as this one.
+    pub(crate) fn empty_like(&self) -> DataColumn {
+        let mut empty = DataColumn::empty();
+        empty.name = self.name.clone();
+        empty
+    }
+
+    /// Pushes the value at `idx` of `from`, keeping its missing mark.
+    pub(crate) fn push_from(&mut self, from: &DataColumn, idx: usize) {
+        if from.is_missing(idx) {
+            self.push_missing(&from[idx]);
+        } else {
+            self.push(&from[idx]);
+        }
+    }
+
+    /// Pushes a value by formatting it with `Display`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push_value(2.5);
+    /// dc.push_opt(None::<f64>);
+    /// dc.push_opt(Some(true));
+    ///
+    /// assert_eq!(dc.data(), vec!["2.5", "", "true"]);
+    /// assert!(dc.is_missing(1));
+    /// ```
+    pub fn push_value<T: fmt::Display>(&mut self, val: T) {
+        self.push(val.to_string());
+    }
+
+    /// Pushes the value if there is one, otherwise pushes an empty value marked as missing.
+    pub fn push_opt<T: fmt::Display>(&mut self, val: Option<T>) {
+        match val {
+            Some(val) => self.push_value(val),
+            None => self.push_missing(""),
+        }
+    }
+
+    /// Pushes a value which is marked as missing.
+    ///
+    /// The text of the value, such as `"n/a"`, is kept so that it can be
+    /// written back out unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("1.0");
+    /// dc.push_missing("n/a");
+    ///
+    /// assert!(!dc.is_missing(0));
+    /// assert!(dc.is_missing(1));
+    /// assert_eq!(&dc[1], "n/a");
+    /// ```
+    pub fn push_missing<S: Into<SmallString>>(&mut self, val: S) {
+        let idx = self.len();
+        self.push(val);
+        Arc::make_mut(&mut self.missing).resize(idx, false);
+        Arc::make_mut(&mut self.missing).push(true);
+    }
+
+    /// Returns true if the value at the index is marked as missing.
+    pub fn is_missing(&self, idx: usize) -> bool {
+        self.missing.get(idx).cloned().unwrap_or(false)
+    }
+
+    /// Replaces the missing marks with one mark for each value.
+    pub(crate) fn set_missing_mask(&mut self, mut mask: Vec<bool>) {
+        let len = mask.iter().rposition(|&m| m).map_or(0, |idx| idx + 1);
+        mask.truncate(len);
+        self.missing = Arc::new(mask);
+    }
+
+    /// Returns true if any value in the column is marked as missing.
+    pub fn has_missing(&self) -> bool {
+        self.missing.contains(&true)
+    }
+
+    /// The number of values in the column which are marked as missing.
+    pub fn null_count(&self) -> usize {
+        self.missing.iter().filter(|&&m| m).count()
+    }
+
+    /// The fraction of values in the column which are present.
+    ///
+    /// An empty column is complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push_opt(Some(1));
+    /// dc.push_opt(None::<i32>);
+    /// dc.push_opt(Some(3));
+    /// dc.push_opt(Some(4));
+    ///
+    /// assert_eq!(dc.null_count(), 1);
+    /// assert_eq!(dc.completeness(), 0.75);
+    /// ```
+    pub fn completeness(&self) -> f64 {
+        if self.is_empty() {
+            return 1.0;
+        }
+
+        (self.len() - self.null_count()) as f64 / self.len() as f64
+    }
+
+    /// Try to get the element at the index as the requested type.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : The element at the given index could not be parsed to this type.
+    ///   The error records the value, the requested type and the underlying parse error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::error::DataError;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("1.5".to_string());
+    /// dc.push("abc".to_string());
+    ///
+    /// assert_eq!(dc.get_as::<f64>(0).unwrap(), 1.5);
+    ///
+    /// match dc.get_as::<f64>(1) {
+    ///     Err(DataError::DataCastError(e)) => {
+    ///         assert_eq!(e.value, "abc");
+    ///         assert_eq!(e.location.row, Some(1));
+    ///         assert!(e.source().is_some());
+    ///     }
+    ///     _ => panic!("expected a cast error"),
+    /// }
+    /// ```
+    pub fn get_as<T>(&self, idx: usize) -> Result<T, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        cast_value(&self[idx], || self.location(idx))
+    }
+
+    /// Shrink the column to fit the data.
+    pub fn shrink_to_fit(&mut self) {
+        // Shared values are left for the other clones to shrink.
+        if let Some(data) = Arc::get_mut(&mut self.data) {
+            data.shrink_to_fit();
+        }
+        if let Some(missing) = Arc::get_mut(&mut self.missing) {
+            missing.shrink_to_fit();
+        }
+    }
+
+    /// Consumes self and returns a Vec of the requested type.
+    ///
+    /// Use `into_vec_with` to choose what happens to values which cannot be parsed.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : Returned when the data cannot be parsed to the requested type.
+    ///   The error records the row and column name of the first value which fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::error::DataError;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec!["1", "2", "x"]);
+    /// dc.name = Some("count".to_string());
+    ///
+    /// match dc.into_vec::<u32>() {
+    ///     Err(DataError::DataCastError(e)) => {
+    ///         assert_eq!(e.location.row, Some(2));
+    ///         assert_eq!(e.location.column_name, Some("count".to_string()));
+    ///     }
+    ///     _ => panic!("expected a cast error"),
+    /// }
+    /// ```
+    pub fn into_vec<T>(self) -> Result<Vec<T>, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let mut casted_data = Vec::<T>::with_capacity(self.data.len());
+
+        for (i, d) in self.data.iter().enumerate() {
+            casted_data.push(cast_value(d, || self.location(i))?);
+        }
+
+        Ok(casted_data)
+    }
+
+    /// Consumes self and returns a Vec of the requested type, using the
+    /// values stored by `cast_cached` if they are of that type.
+    ///
+    /// Otherwise the data is parsed as in `into_vec`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : Returned when the data cannot be parsed to the requested type.
+    ///   The error records the row and column name of the first value which fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec!["1", "2"]);
+    /// dc.cast_cached::<u8>().unwrap();
+    ///
+    /// assert_eq!(dc.into_vec_cached::<u8>().unwrap(), vec![1, 2]);
+    /// ```
+    pub fn into_vec_cached<T>(mut self) -> Result<Vec<T>, DataError>
+        where T: FromStr + 'static,
+              T::Err: Error + Send + Sync + 'static
+    {
+        if let Some(cache) = self.cache.take() {
+            if let Ok(cached) = cache.downcast::<Vec<T>>() {
+                return Ok(*cached);
+            }
+        }
+
+        self.into_vec()
+    }
+
+    /// Consumes self and returns a Vec of the requested type, treating values
+    /// which cannot be parsed according to `policy`.
+    ///
+    /// Values marked as missing are returned as `None`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed and the policy is `Fail`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{CastPolicy, DataColumn};
+    ///
+    /// let dc = DataColumn::from_vec(vec!["3", "N/A", "7"]);
+    ///
+    /// assert!(dc.clone().into_vec_with::<u8>(CastPolicy::Fail).is_err());
+    /// assert_eq!(dc.clone().into_vec_with(CastPolicy::Null).unwrap(),
+    ///            vec![Some(3u8), None, Some(7)]);
+    /// assert_eq!(dc.into_vec_with(CastPolicy::SkipRow).unwrap(), vec![Some(3u8), Some(7)]);
+    /// ```
+    pub fn into_vec_with<T>(self, policy: CastPolicy<T>) -> Result<Vec<Option<T>>, DataError>
+        where T: FromStr + Clone,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.into_vec_with_warnings(policy).map(|(values, _)| values)
+    }
+
+    /// Consumes self and returns a Vec of the requested type as in
+    /// `into_vec_with`, returning warnings alongside the values.
+    ///
+    /// A warning is recorded if any values could not be parsed, giving
+    /// how many there were and what the policy did with them.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed and the policy is `Fail`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{CastPolicy, DataColumn};
+    ///
+    /// let dc = DataColumn::from_vec(vec!["3", "N/A", "n/a"]);
+    ///
+    /// let (values, warnings) = dc.into_vec_with_warnings(CastPolicy::Default(0u8)).unwrap();
+    ///
+    /// assert_eq!(values, vec![Some(3), Some(0), Some(0)]);
+    /// assert_eq!(warnings.warnings()[0].to_string(),
+    ///            "2 unparseable values replaced by the default");
+    /// ```
+    pub fn into_vec_with_warnings<T>(self,
+                                     policy: CastPolicy<T>)
+                                     -> Result<(Vec<Option<T>>, Warnings), DataError>
+        where T: FromStr + Clone,
+              T::Err: Error + Send + Sync + 'static
+    {
+        match self.cast_by_policy(&policy) {
+            Ok(cast) => {
+                let mut warnings = Warnings::new();
+                if cast.coerced > 0 {
+                    let message = coercion_warning(&policy, cast.coerced);
+                    log_warn!("{}", message);
+                    warnings.push(None, None, message);
+                }
+                Ok((without_skipped(cast.values, &cast.skip), warnings))
+            }
+            Err((idx, e)) => {
+                let err = CastError::new::<T, _>(&self[idx], e).at(self.location(idx));
+                Err(DataError::DataCastError(err))
+            }
+        }
+    }
+
+    /// Cast the data to the requested type.
+    ///
+    /// Returns a Vec of the requested type wrapped in an option.
+    /// Use `try_cast` to find the values which cannot be parsed, or
+    /// `cast_with` to choose what happens to them.
+    pub fn cast<T: FromStr>(&self) -> Option<Vec<T>> {
+        let mut casted_data = Vec::<T>::with_capacity(self.data.len());
+
+        for d in self.data.iter() {
+            match T::from_str(d) {
+                Ok(x) => casted_data.push(x),
+                Err(_) => return None,
+            }
+        }
+
+        Some(casted_data)
+    }
+
+    /// Cast the data to the requested type, collecting the values which fail.
+    ///
+    /// Returns the parsed values in row order, along with the row index and
+    /// raw text of each value which could not be parsed. Values marked as
+    /// missing are left out of both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec!["4", "four", "5", ""]);
+    /// dc.push_missing("NA");
+    ///
+    /// let (values, failures) = dc.try_cast::<i32>();
+    ///
+    /// assert_eq!(values, vec![4, 5]);
+    /// assert_eq!(failures, vec![(1, "four".to_string()), (3, String::new())]);
+    /// ```
+    pub fn try_cast<T: FromStr>(&self) -> (Vec<T>, Vec<(usize, String)>) {
+        let mut casted_data = Vec::<T>::with_capacity(self.data.len());
+        let mut failures = Vec::new();
+
+        for (idx, d) in self.data.iter().enumerate() {
+            if self.is_missing(idx) {
+                continue;
+            }
+
+            match T::from_str(d) {
+                Ok(x) => casted_data.push(x),
+                Err(_) => failures.push((idx, d.to_string())),
+            }
+        }
+
+        (casted_data, failures)
+    }
+
+    /// Cast the data to the requested type, treating values which cannot be
+    /// parsed according to `policy`.
+    ///
+    /// Values marked as missing are returned as `None`. Returns `None`
+    /// if a value cannot be parsed and the policy is `Fail`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{CastPolicy, DataColumn};
+    ///
+    /// let dc = DataColumn::from_vec(vec!["1.5", "N/A", "2.5"]);
+    ///
+    /// assert_eq!(dc.cast_with::<f64>(CastPolicy::Fail), None);
+    /// assert_eq!(dc.cast_with(CastPolicy::Default(0.0)),
+    ///            Some(vec![Some(1.5), Some(0.0), Some(2.5)]));
+    /// ```
+    pub fn cast_with<T>(&self, policy: CastPolicy<T>) -> Option<Vec<Option<T>>>
+        where T: FromStr + Clone
+    {
+        let cast = self.cast_by_policy(&policy).ok()?;
+        Some(without_skipped(cast.values, &cast.skip))
+    }
+
+    /// Casts each value, applying `policy` to those which cannot be parsed.
+    ///
+    /// Returns the row and error of the first failure under `CastPolicy::Fail`.
+    fn cast_by_policy<T>(&self, policy: &CastPolicy<T>) -> Result<PolicyCast<T>, (usize, T::Err)>
+        where T: FromStr + Clone
+    {
+        let mut casted = Vec::with_capacity(self.len());
+        let mut skip = vec![false; self.len()];
+        let mut coerced = 0;
 
-            for d in self.data_cols.into_iter() {
-                column_iters.push(d.into_iter_cast::<T>());
+        for (idx, val) in self.data.iter().enumerate() {
+            if self.is_missing(idx) {
+                casted.push(None);
+                continue;
             }
 
-            for _ in 0..rows {
-                for i in 0..cols {
-                    match column_iters[i].next() {
-                        Some(Ok(x)) => table_data.push(x),
-                        Some(Err(_)) => return Err(DataError::DataCastError),
-                        None =>{},
+            match T::from_str(val) {
+                Ok(x) => casted.push(Some(x)),
+                Err(e) => {
+                    coerced += 1;
+                    match *policy {
+                        CastPolicy::Fail => return Err((idx, e)),
+                        CastPolicy::SkipRow => {
+                            skip[idx] = true;
+                            casted.push(None);
+                        }
+                        CastPolicy::Null => casted.push(None),
+                        CastPolicy::Default(ref x) => casted.push(Some(x.clone())),
                     }
                 }
             }
         }
-        else {
-            for d in self.data_cols.into_iter() {
-                match d.into_vec() {
-                    Ok(x) => table_data.extend(x),
-                    Err(e) => return Err(e),
-                }
+
+        Ok(PolicyCast {
+            values: casted,
+            skip,
+            coerced,
+        })
+    }
+
+    /// Cast the data to the requested type and keep the result on the column.
+    ///
+    /// Later calls for the same type return the stored values without parsing
+    /// the data again. The stored values are dropped when the column is modified
+    /// or when a different type is cached.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : Returned when the data cannot be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("1.5");
+    /// dc.push("2.5");
+    ///
+    /// let sum: f64 = dc.cast_cached::<f64>().unwrap().iter().sum();
+    /// assert_eq!(sum, 4.0);
+    /// assert!(dc.cached::<f64>().is_some());
+    ///
+    /// dc.push("3.5");
+    /// assert!(dc.cached::<f64>().is_none());
+    /// ```
+    pub fn cast_cached<T>(&mut self) -> Result<&[T], DataError>
+        where T: FromStr + Send + Sync + 'static,
+              T::Err: Error + Send + Sync + 'static
+    {
+        if self.cached::<T>().is_none() {
+            let mut casted_data = Vec::<T>::with_capacity(self.data.len());
+
+            for (i, d) in self.data.iter().enumerate() {
+                casted_data.push(cast_value(d, || self.location(i))?);
             }
-        }
 
-        if table_data.len() != cols*rows {
-            return Err(DataError::InvalidStateError);
+            self.cache = Some(Box::new(casted_data));
         }
-        
 
-        Ok(table_data)
+        Ok(self.cached::<T>().expect("cache was just filled"))
     }
-}
 
-impl Index<usize> for DataTable { 
-    type Output = DataColumn;
+    /// Gets the values stored by `cast_cached`, if they are of the requested type.
+    pub fn cached<T: 'static>(&self) -> Option<&[T]> {
+        match self.cache {
+            Some(ref cache) => cache.downcast_ref::<Vec<T>>().map(|v| &v[..]),
+            None => None,
+        }
+    }
 
-    fn index(&self, idx: usize) -> &DataColumn {
-        &self.data_cols[idx]
+    /// Drops any values stored by `cast_cached`.
+    pub fn clear_cache(&mut self) {
+        self.cache = None;
     }
-}
 
-/// A data column consisting of Strings. 
-pub struct DataColumn {
-    /// The name associated with the DataColumn.
-    pub name: Option<String>,
-    categories: Option<HashMap<String, usize>>,
-    data: Vec<String>,
-}
+    /// Stores values already parsed from the column, as `cast_cached` does.
+    pub(crate) fn set_cache<T: Send + Sync + 'static>(&mut self, values: Vec<T>) {
+        self.cache = Some(Box::new(values));
+    }
 
-impl DataColumn {
-    /// Constructs an empty data column.
-    pub fn empty() -> DataColumn {
-        DataColumn {
-            name: None,
-            categories: None,
-            data: Vec::new(),
-        }
+    /// The type recorded by `DataTable::convert_columns`.
+    ///
+    /// Any change to the values of the column clears the recorded type.
+    pub fn dtype(&self) -> Option<DType> {
+        self.dtype
     }
 
-    /// Gets the length of the data column.
-    pub fn len(&self) -> usize {
-        self.data.len()
+    /// Records the type which every present value parses as.
+    pub(crate) fn set_dtype(&mut self, dtype: DType) {
+        self.dtype = Some(dtype);
     }
 
-    /// Gets an immutable reference to the underlying data.
-    pub fn data(&self) -> &Vec<String> {
-        &self.data
+    /// Drops everything derived from the current values.
+    fn invalidate(&mut self) {
+        self.cache = None;
+        self.dtype = None;
     }
 
-    /// Gets an immutable reference to the categories Option.
-    pub fn categories(&self) -> Option<HashMap<String, usize>> {
-        match self.categories {
-            None => None,
-            Some(ref x) => Some(x.clone()),
-        }
+    /// Takes the values out of the column, copying them if they are shared.
+    fn take_data(&mut self) -> Storage {
+        unshare(mem::replace(&mut self.data, Arc::new(Storage::Inline(Vec::new()))))
     }
 
-    /// Update the categories set using the current data.
+    /// Cast the data to floats, treating non-finite values according to `policy`.
+    ///
+    /// Values marked as missing are returned as `None`, as are non-finite
+    /// values when the policy is `NonFinite::Missing`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed, or was non-finite under `NonFinite::Reject`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::datatable::{DataColumn, NonFinite};
     ///
     /// let mut dc = DataColumn::empty();
+    /// dc.push("1.5");
+    /// dc.push("NaN");
     ///
-    /// dc.push("Class1".to_string());
-    /// dc.push("Class2".to_string());
-    /// dc.push("Class2".to_string());
-    ///
-    /// dc.update_categories();
-    /// let categories = dc.categories().unwrap();
-    ///
-    /// // Note that `contains` requires a reference so we pass an &str.
-    /// assert!(categories.contains_key("Class2"));
-    /// assert_eq!(categories.len(), 2);
+    /// assert_eq!(dc.cast_float::<f64>(NonFinite::Missing).unwrap(), vec![Some(1.5), None]);
+    /// assert!(dc.cast_float::<f64>(NonFinite::Reject).is_err());
     /// ```
-    pub fn update_categories(&mut self) {
-        let mut categories = HashMap::new();
-        let mut count = 0usize;
+    pub fn cast_float<T>(&self, policy: NonFinite) -> Result<Vec<Option<T>>, DataError>
+        where T: Float + FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let mut casted = Vec::with_capacity(self.len());
 
-        for s in self.data.iter() {
-            if !categories.contains_key(s) {
-                categories.insert(s.clone(), count);
-                count += 1usize;
+        for (idx, val) in self.data.iter().enumerate() {
+            if self.is_missing(idx) {
+                casted.push(None);
+                continue;
             }
 
+            let x: T = cast_value(val, || self.location(idx))?;
+
+            if x.is_finite() {
+                casted.push(Some(x));
+            } else {
+                match policy {
+                    NonFinite::Accept => casted.push(Some(x)),
+                    NonFinite::Missing => casted.push(None),
+                    NonFinite::Reject => {
+                        let err = CastError::rejected::<T>(val).at(self.location(idx));
+                        return Err(DataError::DataCastError(err));
+                    }
+                }
+            }
         }
-        categories.shrink_to_fit();
-        self.categories = Some(categories);
+
+        Ok(casted)
     }
 
-    /// Produce a numerical vector representation of the category data.
+    /// Cast the data after removing currency formatting, see `parse::strip_currency`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A cleaned value could not be parsed to the requested type.
     ///
     /// # Examples
     ///
@@ -182,101 +2020,113 @@ impl DataColumn {
     /// use rusty_data::datatable::DataColumn;
     ///
     /// let mut dc = DataColumn::empty();
+    /// dc.push("$1,200");
+    /// dc.push("(1,234.50)");
     ///
-    /// dc.push("Class1".to_string());
-    /// dc.push("Class2".to_string());
-    /// dc.push("Class2".to_string());
-    ///
-    /// dc.update_categories();
-    ///
-    /// let data = dc.numeric_category_data::<f64>().unwrap();
-    ///
-    /// println!("The data is: {:?}", data);
+    /// assert_eq!(dc.cast_currency::<f64>().unwrap(), vec![1200.0, -1234.5]);
     /// ```
-    pub fn numeric_category_data<T: Zero + One>(&self) -> Result<Vec<Vec<T>>, DataError> {
-        if let Some(ref categories) = self.categories {
-            let mut outer_vec = Vec::new();
-
-            for _ in 0..categories.len() {
-                outer_vec.push(Vec::<T>::new())
-            }
-
-            for d in self.data.iter() {
-                match categories.get(d) {
-                    Some(x) => {
-                        for i in 0..categories.len() {
-                            if *x == i {
-                                outer_vec[i].push(T::one());
-                            } else {
-                                outer_vec[i].push(T::zero());
-                            }
-                        }
-                    }
-                    None => {
-                        return Err(DataError::InvalidStateError);
-                    }
-                }
-            }
-            return Ok(outer_vec);
-        }
-
-        Err(DataError::InvalidStateError)
+    pub fn cast_currency<T>(&self) -> Result<Vec<T>, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.cast_cleaned(parse::strip_currency)
     }
 
-    /// Pushes a new &str to the column.
-    pub fn push(&mut self, val: String) {
-        self.data.push(val);
+    /// Cast the data after removing percent signs, see `parse::strip_percent`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A cleaned value could not be parsed to the requested type.
+    pub fn cast_percent<T>(&self) -> Result<Vec<T>, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.cast_cleaned(parse::strip_percent)
     }
 
-    /// Try to get the element at the index as the requested type.
+    /// Cast the data after removing thousands separators, see `parse::strip_thousands`.
     ///
     /// # Failures
     ///
-    /// - DataCastError : The element at the given index could not be parsed to this type. 
-    pub fn get_as<T: FromStr>(&self, idx: usize) -> Result<T, DataError> {
-        match T::from_str(self.data[idx].as_ref()) {
-            Ok(x) => Ok(x),
-            Err(_) => Err(DataError::DataCastError),
-        }
+    /// - DataCastError : A cleaned value could not be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("1,234,567");
+    /// dc.push("89");
+    ///
+    /// assert_eq!(dc.cast_grouped::<u32>(',').unwrap(), vec![1234567, 89]);
+    /// ```
+    pub fn cast_grouped<T>(&self, separator: char) -> Result<Vec<T>, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.cast_cleaned(|val| parse::strip_thousands(val, separator))
     }
 
-    /// Shrink the column to fit the data.
-    pub fn shrink_to_fit(&mut self) {
-        self.data.shrink_to_fit();
+    /// Cast each value after passing it through `clean`.
+    fn cast_cleaned<T, F>(&self, clean: F) -> Result<Vec<T>, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static,
+              F: Fn(&str) -> Cow<str>
+    {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(idx, val)| {
+                T::from_str(&clean(val)).map_err(|e| {
+                    DataError::DataCastError(CastError::new::<T, _>(val, e).at(self.location(idx)))
+                })
+            })
+            .collect()
     }
 
-    /// Consumes self and returns a Vec of the requested type.
+    /// Cast the data to the requested type, reporting every value which fails.
     ///
     /// # Failures
     ///
-    /// - DataCastError : Returned when the data cannot be parsed to the requested type.
-    pub fn into_vec<T: FromStr>(self) -> Result<Vec<T>, DataError> {
-        let mut casted_data = Vec::<T>::with_capacity(self.data.len());
-
-        for d in self.data.into_iter() {
-            match T::from_str(d.as_ref()) {
-                Ok(x) => casted_data.push(x),
-                Err(_) => return Err(DataError::DataCastError),
-            }
-        }
-
-        Ok(casted_data)
-    }
-
-    /// Cast the data to the requested type.
+    /// - Returns an `ErrorReport` with a `DataCastError` for each value which
+    ///   cannot be parsed to the requested type.
     ///
-    /// Returns a Vec of the requested type wrapped in an option.
-    pub fn cast<T: FromStr>(&self) -> Option<Vec<T>> {
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    ///
+    /// dc.push("1.5".to_string());
+    /// dc.push("N/A".to_string());
+    /// dc.push("?".to_string());
+    ///
+    /// let report = dc.cast_report::<f64>().unwrap_err();
+    ///
+    /// assert_eq!(report.len(), 2);
+    /// assert_eq!(report.entries()[0].row, 1);
+    /// ```
+    pub fn cast_report<T>(&self) -> Result<Vec<T>, ErrorReport>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
         let mut casted_data = Vec::<T>::with_capacity(self.data.len());
+        let mut report = ErrorReport::new();
 
-        for d in self.data.iter() {
-            match T::from_str(&d[..]) {
+        for (i, d) in self.data.iter().enumerate() {
+            match cast_value(d, || self.location(i)) {
                 Ok(x) => casted_data.push(x),
-                Err(_) => return None,
+                Err(e) => report.push(i, None, e),
             }
         }
 
-        Some(casted_data)
+        if report.is_empty() {
+            Ok(casted_data)
+        } else {
+            Err(report)
+        }
     }
 
     /// Consumes self and returns an iterator which parses
@@ -284,19 +2134,209 @@ impl DataColumn {
     ///
     /// The iterator will return a result on `next()` detailing
     /// the outcome of the parse.
-    pub fn into_iter_cast<U: FromStr>
-        (self)
-         -> std::iter::Map<IntoIter<String>, fn(String) -> Result<U, <U as FromStr>::Err>>
+    pub fn into_iter_cast<U>(self) -> FromStrIter<IntoIter<SmallString>, U>
         where U: FromStr
     {
-        from_str_iter::<_, U>(self.data.into_iter())
+        from_str_iter::<_, U>(unshare(self.data).into_inline().into_iter())
+    }
+}
+
+/// The values cast by `DataColumn::cast_by_policy`.
+struct PolicyCast<T> {
+    values: Vec<Option<T>>,
+    // Whether each row is to be skipped.
+    skip: Vec<bool>,
+    // The number of values which could not be parsed.
+    coerced: usize,
+}
+
+/// Describes what `policy` did with the values which could not be parsed.
+fn coercion_warning<T>(policy: &CastPolicy<T>, coerced: usize) -> String {
+    let action = match *policy {
+        CastPolicy::Fail => "failed",
+        CastPolicy::SkipRow => "skipped with their rows",
+        CastPolicy::Null => "coerced to NA",
+        CastPolicy::Default(_) => "replaced by the default",
+    };
+
+    format!("{} unparseable values {}", coerced, action)
+}
+
+/// Drops the values whose rows are marked in `skip`.
+fn without_skipped<T>(values: Vec<T>, skip: &[bool]) -> Vec<T> {
+    values.into_iter().zip(skip).filter(|&(_, &skipped)| !skipped).map(|(val, _)| val).collect()
+}
+
+/// Gets the value out of the `Arc`, copying it if it is shared.
+fn unshare<T: Clone>(shared: Arc<T>) -> T {
+    Arc::try_unwrap(shared).unwrap_or_else(|shared| (*shared).clone())
+}
+
+/// The categories of a column, indexed by label and by index.
+struct Categories {
+    indices: HashMap<String, usize>,
+    // The label of each index up to the largest, `None` for indices without one.
+    labels: Vec<Option<String>>,
+}
+
+impl Categories {
+    fn new(mut indices: HashMap<String, usize>) -> Categories {
+        indices.shrink_to_fit();
+
+        let len = indices.values().max().map_or(0, |&i| i + 1);
+        let mut labels = vec![None; len];
+        for (label, &i) in indices.iter() {
+            labels[i] = Some(label.clone());
+        }
+
+        Categories { indices, labels }
+    }
+}
+
+/// Clones share the values of the column until either is changed.
+///
+/// Values stored by `cast_cached` are not cloned.
+impl Clone for DataColumn {
+    fn clone(&self) -> DataColumn {
+        DataColumn {
+            name: self.name.clone(),
+            categories: self.categories.clone(),
+            data: self.data.clone(),
+            cache: None,
+            dtype: self.dtype,
+            missing: self.missing.clone(),
+        }
+    }
+}
+
+impl<T: ToString> FromIterator<T> for DataColumn {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> DataColumn {
+        let mut column = DataColumn::empty();
+        for val in iter {
+            column.push(val.to_string());
+        }
+        column
+    }
+}
+
+impl<S: Into<SmallString>> Extend<S> for DataColumn {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a DataColumn {
+    type Item = &'a str;
+    type IntoIter = ColumnIter<'a>;
+
+    fn into_iter(self) -> ColumnIter<'a> {
+        self.iter()
+    }
+}
+
+/// A borrowed view of the values in a DataColumn, see `DataColumn::data`.
+#[derive(Clone, Copy)]
+pub struct ColumnValues<'a> {
+    column: &'a DataColumn,
+}
+
+impl<'a> ColumnValues<'a> {
+    /// Gets the number of values.
+    pub fn len(&self) -> usize {
+        self.column.len()
+    }
+
+    /// Returns true if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.column.is_empty()
+    }
+
+    /// Gets the value at the index, if it exists.
+    pub fn get(&self, idx: usize) -> Option<&'a str> {
+        self.column.get(idx)
+    }
+
+    /// Gets an iterator over the values.
+    pub fn iter(&self) -> ColumnIter<'a> {
+        self.column.iter()
+    }
+
+    /// Copies the references to each value into a Vec.
+    pub fn to_vec(&self) -> Vec<&'a str> {
+        self.iter().collect()
+    }
+}
+
+impl<'a> Index<usize> for ColumnValues<'a> {
+    type Output = str;
+    fn index(&self, idx: usize) -> &str {
+        &self.column[idx]
+    }
+}
+
+impl<'a> IntoIterator for ColumnValues<'a> {
+    type Item = &'a str;
+    type IntoIter = ColumnIter<'a>;
+
+    fn into_iter(self) -> ColumnIter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a, 'b> PartialEq<ColumnValues<'b>> for ColumnValues<'a> {
+    fn eq(&self, other: &ColumnValues<'b>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, S: AsRef<str>> PartialEq<Vec<S>> for ColumnValues<'a> {
+    fn eq(&self, other: &Vec<S>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter().map(AsRef::as_ref))
     }
 }
 
+impl<'a> fmt::Debug for ColumnValues<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the values in a DataColumn.
+pub struct ColumnIter<'a> {
+    inner: StorageIter<'a>,
+}
+
+impl<'a> Iterator for ColumnIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Parses the value, attaching the location given by `location` on failure.
+pub(crate) fn cast_value<T, F>(value: &str, location: F) -> Result<T, DataError>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static,
+          F: FnOnce() -> CellLocation
+{
+    T::from_str(value).map_err(|e| {
+        DataError::DataCastError(CastError::new::<T, _>(value, e).at(location()))
+    })
+}
+
+/// An iterator which parses each item of `I` to the type `U`.
+pub type FromStrIter<I, U> =
+    std::iter::Map<I, fn(<I as Iterator>::Item) -> Result<U, <U as FromStr>::Err>>;
+
 /// Converts the iterator to a FromStr iterator.
-fn from_str_iter<I, U>
-    (iter: I)
-     -> std::iter::Map<I, fn(<I as Iterator>::Item) -> Result<U, <U as FromStr>::Err>>
+fn from_str_iter<I, U>(iter: I) -> FromStrIter<I, U>
     where I: Iterator,
           <I as Iterator>::Item: AsRef<str>,
           U: FromStr
@@ -311,8 +2351,11 @@ fn from_str_iter<I, U>
 }
 
 impl Index<usize> for DataColumn { 
-    type Output = String;
-    fn index(&self, idx: usize) -> &String {
-        &self.data[idx]
+    type Output = str;
+    fn index(&self, idx: usize) -> &str {
+        match self.data.get(idx) {
+            Some(s) => s,
+            None => panic!("index {} out of bounds for column of length {}", idx, self.len()),
+        }
     }
 }
@@ -0,0 +1,304 @@
+//! The disk module.
+//!
+//! Provides the DiskBackedTable which keeps columns beyond a
+//! memory budget in temporary files.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::mem;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use storage::SmallString;
+
+static SPILL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+enum ColumnSlot {
+    Memory(DataColumn),
+    Disk {
+        path: PathBuf,
+        name: Option<String>,
+        len: usize,
+    },
+}
+
+/// A table which spills columns to disk once a memory budget is used.
+///
+/// Columns are kept in memory in order until the budget is reached and
+/// the remaining columns are written to temporary files. Spilled columns
+/// are read back one at a time when an operation needs them, so tables
+/// larger than the available memory can still be filtered and selected
+/// from. The temporary files are removed when the table is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::{DataColumn, DataTable};
+/// use rusty_data::disk::DiskBackedTable;
+///
+/// let mut table = DataTable::empty();
+/// for c in 0..3 {
+///     let mut column = DataColumn::empty();
+///     for r in 0..100 {
+///         column.push((r * c).to_string());
+///     }
+///     table.data_cols.push(column);
+/// }
+///
+/// // A tiny budget forces all but the first column onto disk.
+/// let disk_table = DiskBackedTable::from_table(table, 1).unwrap();
+/// assert_eq!(disk_table.spilled(), 2);
+///
+/// let evens = disk_table.filter(1, |s| s.parse::<u32>().unwrap() % 2 == 0).unwrap();
+/// assert_eq!(evens.rows(), 50);
+/// assert_eq!(&evens[2][1], "4");
+/// ```
+pub struct DiskBackedTable {
+    columns: Vec<ColumnSlot>,
+}
+
+impl DiskBackedTable {
+    /// Constructs a DiskBackedTable keeping at most `budget` bytes of columns in memory.
+    ///
+    /// # Failures
+    ///
+    /// - Io : A column could not be written to a temporary file.
+    pub fn from_table(table: DataTable, budget: usize) -> Result<DiskBackedTable, DataError> {
+        let mut used = 0usize;
+        let mut columns = Vec::with_capacity(table.cols());
+        let mut spilling = false;
+
+        for column in table.data_cols {
+            let size = estimate_size(&column);
+
+            if !spilling && (columns.is_empty() || used + size <= budget) {
+                used += size;
+                columns.push(ColumnSlot::Memory(column));
+            } else {
+                spilling = true;
+                columns.push(spill(column)?);
+            }
+        }
+
+        Ok(DiskBackedTable { columns })
+    }
+
+    /// The number of columns in the table.
+    pub fn cols(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The number of rows in the table.
+    pub fn rows(&self) -> usize {
+        match self.columns.first() {
+            Some(ColumnSlot::Memory(column)) => column.len(),
+            Some(&ColumnSlot::Disk { len, .. }) => len,
+            None => 0,
+        }
+    }
+
+    /// The number of columns currently held on disk.
+    pub fn spilled(&self) -> usize {
+        self.columns
+            .iter()
+            .filter(|c| match **c {
+                ColumnSlot::Disk { .. } => true,
+                ColumnSlot::Memory(_) => false,
+            })
+            .count()
+    }
+
+    /// Gets the column at the index, reading it from disk if it was spilled.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The index is out of range.
+    /// - Io : The column could not be read back from disk.
+    pub fn column(&self, idx: usize) -> Result<ColumnHandle<'_>, DataError> {
+        match self.columns.get(idx) {
+            Some(ColumnSlot::Memory(column)) => Ok(ColumnHandle::Memory(column)),
+            Some(&ColumnSlot::Disk { ref path, ref name, len }) => {
+                Ok(ColumnHandle::Paged(page_in(path, name, len)?))
+            }
+            None => Err(DataError::InvalidStateError),
+        }
+    }
+
+    /// Builds an in-memory DataTable from the columns at the given indices.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : An index is out of range.
+    /// - Io : A column could not be read back from disk.
+    pub fn select(&self, cols: &[usize]) -> Result<DataTable, DataError> {
+        let mut table = DataTable::empty();
+
+        for &idx in cols {
+            let column = match self.column(idx)? {
+                ColumnHandle::Memory(column) => copy_rows(column, |_| true),
+                ColumnHandle::Paged(column) => column,
+            };
+            table.data_cols.push(column);
+        }
+
+        Ok(table)
+    }
+
+    /// Builds an in-memory DataTable from the rows where `predicate` holds
+    /// for the value in column `col`.
+    ///
+    /// Only one spilled column is read from disk at a time.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The column index is out of range.
+    /// - Io : A column could not be read back from disk.
+    pub fn filter<F>(&self, col: usize, mut predicate: F) -> Result<DataTable, DataError>
+        where F: FnMut(&str) -> bool
+    {
+        let mask: Vec<bool> = {
+            let column = self.column(col)?;
+            (0..column.len()).map(|i| predicate(&column[i])).collect()
+        };
+
+        let mut table = DataTable::empty();
+
+        for idx in 0..self.cols() {
+            let column = self.column(idx)?;
+            table.data_cols.push(copy_rows(&column, |i| mask[i]));
+        }
+
+        Ok(table)
+    }
+
+    /// Consumes the table and reads every column back into memory.
+    ///
+    /// # Failures
+    ///
+    /// - Io : A column could not be read back from disk.
+    pub fn into_table(mut self) -> Result<DataTable, DataError> {
+        let mut table = DataTable::empty();
+
+        for slot in mem::take(&mut self.columns) {
+            match slot {
+                ColumnSlot::Memory(column) => table.data_cols.push(column),
+                ColumnSlot::Disk { path, name, len } => {
+                    let column = page_in(&path, &name, len);
+                    // The file is no longer tracked by the table so remove it here.
+                    let _ = fs::remove_file(&path);
+                    table.data_cols.push(column?);
+                }
+            }
+        }
+
+        Ok(table)
+    }
+}
+
+impl Drop for DiskBackedTable {
+    fn drop(&mut self) {
+        for slot in self.columns.iter() {
+            if let ColumnSlot::Disk { ref path, .. } = *slot {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// A column borrowed from memory or read back from disk.
+#[allow(clippy::large_enum_variant)]
+pub enum ColumnHandle<'a> {
+    /// The column was held in memory.
+    Memory(&'a DataColumn),
+    /// The column was read from disk.
+    Paged(DataColumn),
+}
+
+impl<'a> Deref for ColumnHandle<'a> {
+    type Target = DataColumn;
+
+    fn deref(&self) -> &DataColumn {
+        match *self {
+            ColumnHandle::Memory(column) => column,
+            ColumnHandle::Paged(ref column) => column,
+        }
+    }
+}
+
+/// Approximates the bytes of memory used by the column.
+fn estimate_size(column: &DataColumn) -> usize {
+    let values: usize = column.data().iter().map(|s| s.len()).sum();
+    values + column.len() * mem::size_of::<SmallString>()
+}
+
+/// Copies the rows of the column for which `keep` holds into a new column.
+fn copy_rows<F>(column: &DataColumn, keep: F) -> DataColumn
+    where F: Fn(usize) -> bool
+{
+    let mut copy = DataColumn::empty();
+    copy.name = column.name.clone();
+
+    for i in 0..column.len() {
+        if keep(i) {
+            copy.push(&column[i]);
+        }
+    }
+
+    copy
+}
+
+/// Writes the column to a new temporary file.
+fn spill(column: DataColumn) -> Result<ColumnSlot, DataError> {
+    let path = temp_path(&env::temp_dir(), "col");
+    let mut writer = BufWriter::new(File::create(&path)?);
+
+    for i in 0..column.len() {
+        let val = &column[i];
+        writer.write_all(&(val.len() as u64).to_le_bytes())?;
+        writer.write_all(val.as_bytes())?;
+    }
+    writer.flush()?;
+
+    Ok(ColumnSlot::Disk {
+        path,
+        len: column.len(),
+        name: column.name,
+    })
+}
+
+/// A new path in the directory for a temporary file with the extension.
+pub(crate) fn temp_path(dir: &Path, extension: &str) -> PathBuf {
+    dir.join(format!("rusty-data-{}-{}.{}",
+                     process::id(),
+                     SPILL_COUNT.fetch_add(1, Ordering::SeqCst),
+                     extension))
+}
+
+/// Reads a spilled column back from its file.
+fn page_in(path: &Path, name: &Option<String>, len: usize) -> Result<DataColumn, DataError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut column = DataColumn::empty();
+    column.name = name.clone();
+
+    let mut len_buf = [0u8; 8];
+    let mut val = Vec::new();
+
+    for _ in 0..len {
+        reader.read_exact(&mut len_buf)?;
+        val.resize(u64::from_le_bytes(len_buf) as usize, 0);
+        reader.read_exact(&mut val)?;
+
+        match ::std::str::from_utf8(&val) {
+            Ok(s) => column.push(s),
+            Err(_) => return Err(DataError::InvalidStateError),
+        }
+    }
+
+    Ok(column)
+}
@@ -1,31 +1,394 @@
-//! Module for errors within the rusty-data crate.
+//! Module for errors and warnings within the rusty-data crate.
 
 use std::fmt;
 use std::error::Error;
+use std::io;
 
 /// Errors related to Data functions.
 #[derive(Debug)]
 pub enum DataError {
     /// An error for failed data casting.
-    DataCastError,
+    DataCastError(CastError),
     /// An error reported when the data state was invalid for the operation.
     InvalidStateError,
+    /// An IO error encountered while reading or writing data.
+    Io(io::Error),
+    /// A line of input could not be split into fields.
+    Parse(LoaderError),
+    /// A line of input did not match the shape of the table.
+    Malformed(LoaderError),
 }
 
 impl fmt::Display for DataError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &DataError::DataCastError => write!(f, "DataCastError"),
-            &DataError::InvalidStateError => write!(f, "InvalidStateError"),
+        match *self {
+            DataError::DataCastError(ref e) => write!(f, "DataCastError: {}", e),
+            DataError::InvalidStateError => write!(f, "InvalidStateError"),
+            DataError::Io(ref e) => write!(f, "IoError: {}", e),
+            DataError::Parse(ref e) => write!(f, "ParseError: {}", e),
+            DataError::Malformed(ref e) => write!(f, "MalformedError: {}", e),
         }
     }
 }
 
 impl Error for DataError {
     fn description(&self) -> &str {
-        match self {
-            &DataError::DataCastError => "Failed to cast data.",
-            &DataError::InvalidStateError => "Operation was not valid for state of object.",
+        match *self {
+            DataError::DataCastError(_) => "Failed to cast data.",
+            DataError::InvalidStateError => "Operation was not valid for state of object.",
+            DataError::Io(_) => "An IO error occurred.",
+            DataError::Parse(_) => "Failed to parse a line of input.",
+            DataError::Malformed(_) => "Input data was malformed.",
         }
     }
-}
\ No newline at end of file
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            DataError::DataCastError(ref e) => Some(e),
+            DataError::InvalidStateError => None,
+            DataError::Io(ref e) => Some(e),
+            DataError::Parse(ref e) |
+            DataError::Malformed(ref e) => Some(e),
+        }
+    }
+}
+
+/// The position of a value within a table.
+///
+/// Each part is optional as not every operation knows where its
+/// data came from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CellLocation {
+    /// The row index of the value.
+    pub row: Option<usize>,
+    /// The column index of the value.
+    pub column: Option<usize>,
+    /// The name of the column holding the value.
+    pub column_name: Option<String>,
+}
+
+impl fmt::Display for CellLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+
+        if let Some(row) = self.row {
+            parts.push(format!("row {}", row));
+        }
+
+        if let Some(column) = self.column {
+            parts.push(format!("column {}", column));
+        }
+
+        write!(f, "{}", parts.join(", "))?;
+
+        if let Some(ref name) = self.column_name {
+            write!(f, " ('{}')", name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Details of a value which could not be cast.
+#[derive(Debug)]
+pub struct CastError {
+    /// The name of the type the value was cast to.
+    pub expected: &'static str,
+    /// The value which failed to cast.
+    pub value: String,
+    /// Where the value was found.
+    pub location: CellLocation,
+    source: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl CastError {
+    /// Constructs a new CastError for a failed cast to `T`.
+    ///
+    /// The underlying parse error is kept as the source of this error.
+    pub fn new<T, E>(value: &str, source: E) -> CastError
+        where E: Error + Send + Sync + 'static
+    {
+        CastError {
+            expected: ::std::any::type_name::<T>(),
+            value: value.to_string(),
+            location: CellLocation::default(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    /// Constructs a new CastError for a value which parsed as `T` but was
+    /// rejected by the options of the cast.
+    pub fn rejected<T>(value: &str) -> CastError {
+        CastError {
+            expected: ::std::any::type_name::<T>(),
+            value: value.to_string(),
+            location: CellLocation::default(),
+            source: None,
+        }
+    }
+
+    /// Returns the error with the given location attached.
+    pub fn at(mut self, location: CellLocation) -> CastError {
+        self.location = location;
+        self
+    }
+}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "could not cast {:?} to {}", self.value, self.expected)?;
+
+        if self.location != CellLocation::default() {
+            write!(f, " at {}", self.location)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for CastError {
+    fn description(&self) -> &str {
+        "Failed to cast value."
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.source {
+            Some(ref e) => Some(&**e),
+            None => None,
+        }
+    }
+}
+
+impl From<io::Error> for DataError {
+    fn from(e: io::Error) -> DataError {
+        DataError::Io(e)
+    }
+}
+
+/// The kinds of problem the loader can report for a single line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoaderErrorKind {
+    /// The line contained more fields than the table has columns.
+    ///
+    /// The error is given the last column of the table, which the extra
+    /// fields follow.
+    TooManyFields {
+        /// The number of columns in the table.
+        expected: usize,
+        /// The number of fields found on the line.
+        found: usize,
+    },
+    /// The line contained fewer fields than the table has columns.
+    TooFewFields {
+        /// The number of columns in the table.
+        expected: usize,
+        /// The number of fields found on the line.
+        found: usize,
+    },
+    /// A quoted field was not closed before the end of the line.
+    UnterminatedQuote,
+}
+
+/// An error encountered while loading a data file.
+///
+/// Records where in the file the problem occurred so that a bad row
+/// can be located without searching the file by hand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoaderError {
+    /// The kind of problem found.
+    pub kind: LoaderErrorKind,
+    /// The line number in the file, starting from 1.
+    pub line: usize,
+    /// The index of the column the problem relates to.
+    pub column: usize,
+    /// The name of the column, if the file has a header.
+    pub column_name: Option<String>,
+    /// The offending text.
+    pub text: String,
+}
+
+impl fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+
+        if let Some(ref name) = self.column_name {
+            write!(f, " ('{}')", name)?;
+        }
+
+        match self.kind {
+            LoaderErrorKind::TooManyFields { expected, found } |
+            LoaderErrorKind::TooFewFields { expected, found } => {
+                write!(f, ": expected {} fields but found {}", expected, found)?
+            }
+            LoaderErrorKind::UnterminatedQuote => write!(f, ": unterminated quote")?,
+        }
+
+        write!(f, " in {:?}", self.text)
+    }
+}
+
+impl Error for LoaderError {
+    fn description(&self) -> &str {
+        match self.kind {
+            LoaderErrorKind::TooManyFields { .. } => "Line contained too many fields.",
+            LoaderErrorKind::TooFewFields { .. } => "Line contained too few fields.",
+            LoaderErrorKind::UnterminatedQuote => "Line contained an unterminated quote.",
+        }
+    }
+}
+
+/// A single failure recorded in an `ErrorReport`.
+#[derive(Debug)]
+pub struct ReportEntry {
+    /// The row of the failing cell.
+    ///
+    /// For reports produced while loading this is the line number in the file.
+    pub row: usize,
+    /// The column of the failing cell, if known.
+    pub column: Option<usize>,
+    /// The reason for the failure.
+    pub error: DataError,
+}
+
+/// A collection of every failure found by an operation.
+///
+/// Returned by the `_report` variants of casts and loads which keep going
+/// after the first failure.
+#[derive(Debug, Default)]
+pub struct ErrorReport {
+    entries: Vec<ReportEntry>,
+}
+
+impl ErrorReport {
+    /// Constructs an empty ErrorReport.
+    pub fn new() -> ErrorReport {
+        ErrorReport { entries: Vec::new() }
+    }
+
+    /// Records a new failure in the report.
+    pub fn push(&mut self, row: usize, column: Option<usize>, error: DataError) {
+        self.entries.push(ReportEntry {
+            row,
+            column,
+            error,
+        });
+    }
+
+    /// The number of failures in the report.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no failures were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets an immutable reference to the recorded failures.
+    pub fn entries(&self) -> &[ReportEntry] {
+        &self.entries
+    }
+
+    /// Consumes the report and returns the recorded failures.
+    pub fn into_entries(self) -> Vec<ReportEntry> {
+        self.entries
+    }
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} errors", self.entries.len())?;
+
+        for entry in self.entries.iter() {
+            match entry.column {
+                Some(column) => write!(f, "\n  row {}, column {}: {}", entry.row, column, entry.error)?,
+                None => write!(f, "\n  row {}: {}", entry.row, entry.error)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ErrorReport {
+    fn description(&self) -> &str {
+        "Multiple errors occurred."
+    }
+}
+
+/// A non-fatal problem noticed during an operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// The line or row the warning relates to, if any.
+    pub line: Option<usize>,
+    /// The column the warning relates to, if any.
+    pub column: Option<usize>,
+    /// A description of what happened.
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+
+        if let Some(column) = self.column {
+            write!(f, "column {}: ", column)?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A collection of warnings produced alongside a successful result.
+///
+/// Lenient operations which repair or coerce data record what they did here
+/// so that the changes remain observable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Warnings {
+    warnings: Vec<Warning>,
+}
+
+impl Warnings {
+    /// Constructs an empty Warnings collection.
+    pub fn new() -> Warnings {
+        Warnings { warnings: Vec::new() }
+    }
+
+    /// Records a new warning.
+    pub fn push(&mut self, line: Option<usize>, column: Option<usize>, message: String) {
+        self.warnings.push(Warning {
+            line,
+            column,
+            message,
+        });
+    }
+
+    /// The number of warnings recorded.
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Returns true if no warnings were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Gets an immutable reference to the recorded warnings.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+}
+
+impl fmt::Display for Warnings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} warnings", self.warnings.len())?;
+
+        for warning in self.warnings.iter() {
+            write!(f, "\n  {}", warning)?;
+        }
+
+        Ok(())
+    }
+}
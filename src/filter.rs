@@ -0,0 +1,161 @@
+//! The filter module.
+//!
+//! Provides common row filters over DataTables which do not need
+//! a closure to be written.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash};
+
+use datatable::DataTable;
+use error::DataError;
+
+/// How numeric filters treat values which are missing or do not parse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonNumeric {
+    /// Return an error for values which do not parse and drop missing values.
+    #[default]
+    Fail,
+    /// Drop the rows holding these values.
+    Drop,
+    /// Keep the rows holding these values.
+    Keep,
+}
+
+impl DataTable {
+    /// Gets the rows whose value in the named column equals `value`.
+    ///
+    /// Missing values never match. Compressed columns compare each
+    /// distinct value once rather than every row.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["A3", "1"], vec!["B1", "2"], vec!["A3", "3"]];
+    /// let table = DataTable::from_records(Some(vec!["site", "reading"]), records).unwrap();
+    ///
+    /// let a3 = table.filter_eq("site", "A3").unwrap();
+    /// assert_eq!(a3[1].data(), vec!["1", "3"]);
+    /// ```
+    pub fn filter_eq(&self, column: &str, value: &str) -> Result<DataTable, DataError> {
+        let column = self.column(column).ok_or(DataError::InvalidStateError)?;
+        Ok(self.take_rows(&column.positions(value)))
+    }
+
+    /// Gets the rows whose value in the named column is between `low` and
+    /// `high` inclusive.
+    ///
+    /// Values are parsed as floats. Missing values are dropped and values
+    /// which do not parse return an error, see `filter_between_with`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    /// - DataCastError : A value could not be parsed as a float.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::filter::NonNumeric;
+    ///
+    /// let records = vec![vec!["5"], vec!["12.5"], vec!["30"], vec!["n/a"]];
+    /// let table = DataTable::from_records(Some(vec!["temp"]), records).unwrap();
+    ///
+    /// assert!(table.filter_between("temp", 10.0, 30.0).is_err());
+    ///
+    /// let mild = table.filter_between_with("temp", 10.0, 30.0, NonNumeric::Drop).unwrap();
+    /// assert_eq!(mild[0].data(), vec!["12.5", "30"]);
+    /// ```
+    pub fn filter_between(&self,
+                          column: &str,
+                          low: f64,
+                          high: f64)
+                          -> Result<DataTable, DataError> {
+        self.filter_between_with(column, low, high, NonNumeric::Fail)
+    }
+
+    /// Gets the rows whose value in the named column is between `low` and
+    /// `high` inclusive, treating missing and unparseable values using `policy`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    /// - DataCastError : A value could not be parsed as a float and the policy is `Fail`.
+    pub fn filter_between_with(&self,
+                               column: &str,
+                               low: f64,
+                               high: f64,
+                               policy: NonNumeric)
+                               -> Result<DataTable, DataError> {
+        let column = self.column(column).ok_or(DataError::InvalidStateError)?;
+        let mut rows = Vec::new();
+
+        for idx in 0..column.len() {
+            let keep = if column.is_missing(idx) {
+                policy == NonNumeric::Keep
+            } else {
+                match column.get_as::<f64>(idx) {
+                    Ok(val) => low <= val && val <= high,
+                    Err(e) => {
+                        match policy {
+                            NonNumeric::Fail => return Err(e),
+                            NonNumeric::Drop => false,
+                            NonNumeric::Keep => true,
+                        }
+                    }
+                }
+            };
+
+            if keep {
+                rows.push(idx);
+            }
+        }
+
+        Ok(self.take_rows(&rows))
+    }
+
+    /// Gets the rows whose value in the named column is in `values`.
+    ///
+    /// Missing values never match.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1"], vec!["2"], vec!["3"], vec!["4"]];
+    /// let table = DataTable::from_records(Some(vec!["id"]), records).unwrap();
+    ///
+    /// let wanted: HashSet<&str> = vec!["2", "4", "9"].into_iter().collect();
+    /// assert_eq!(table.filter_isin("id", &wanted).unwrap()[0].data(), vec!["2", "4"]);
+    /// ```
+    pub fn filter_isin<S, H>(&self,
+                             column: &str,
+                             values: &HashSet<S, H>)
+                             -> Result<DataTable, DataError>
+        where S: Borrow<str> + Hash + Eq,
+              H: BuildHasher
+    {
+        let column = self.column(column).ok_or(DataError::InvalidStateError)?;
+
+        let rows: Vec<usize> = column.iter()
+            .enumerate()
+            .filter(|&(idx, val)| !column.is_missing(idx) && values.contains(val))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        Ok(self.take_rows(&rows))
+    }
+}
@@ -0,0 +1,123 @@
+//! The fingerprint module.
+//!
+//! Provides stable hashes over the headers and values of DataTables.
+//!
+//! The hashes use 64 bit FNV-1a, so they are the same across runs,
+//! platforms and versions of Rust, unlike the hashes of `std::hash`.
+
+use datatable::{DataColumn, DataTable};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A 64 bit FNV-1a hasher.
+pub(crate) struct Fnv(pub(crate) u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Fnv {
+        Fnv(FNV_OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u64(&mut self, val: u64) {
+        self.write(&val.to_le_bytes());
+    }
+
+    /// Writes the value prefixed by its length, so that adjacent values
+    /// cannot run together.
+    fn write_str(&mut self, val: &str) {
+        self.write_u64(val.len() as u64);
+        self.write(val.as_bytes());
+    }
+
+    fn write_cell(&mut self, column: &DataColumn, row: usize) {
+        if column.is_missing(row) {
+            // Missing values are told apart from present ones with the same text.
+            self.write(&[0]);
+        } else {
+            self.write(&[1]);
+        }
+        self.write_str(&column[row]);
+    }
+}
+
+impl DataTable {
+    /// Computes a stable hash of the headers and values of the table.
+    ///
+    /// The hash changes if any name, value or missing mark changes, or if
+    /// the rows or columns are reordered. See `fingerprint_unordered` for
+    /// a hash which ignores the order of the rows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let a = DataTable::from_records(Some(vec!["x"]), vec![vec!["1"], vec!["2"]]).unwrap();
+    /// let b = DataTable::from_records(Some(vec!["x"]), vec![vec!["2"], vec!["1"]]).unwrap();
+    ///
+    /// // The hash is the same on every run.
+    /// assert_eq!(a.fingerprint(), 0xfc56_5008_d38b_6fb5);
+    ///
+    /// assert_ne!(a.fingerprint(), b.fingerprint());
+    /// assert_eq!(a.fingerprint_unordered(), b.fingerprint_unordered());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = self.header_hasher();
+
+        for row in 0..self.rows() {
+            for column in self.data_cols.iter() {
+                hasher.write_cell(column, row);
+            }
+        }
+
+        hasher.0
+    }
+
+    /// Computes a stable hash of the headers and values of the table which
+    /// does not depend on the order of the rows.
+    ///
+    /// Tables holding the same rows the same number of times have the same
+    /// hash. The order of the columns still matters.
+    pub fn fingerprint_unordered(&self) -> u64 {
+        let mut rows = 0u64;
+
+        for row in 0..self.rows() {
+            let mut hasher = Fnv::new();
+            for column in self.data_cols.iter() {
+                hasher.write_cell(column, row);
+            }
+            // Addition does not depend on the order but keeps repeated rows.
+            rows = rows.wrapping_add(hasher.0);
+        }
+
+        let mut hasher = self.header_hasher();
+        hasher.write_u64(rows);
+        hasher.0
+    }
+
+    /// A hasher which has been given the shape and names of the table.
+    fn header_hasher(&self) -> Fnv {
+        let mut hasher = Fnv::new();
+        hasher.write_u64(self.cols() as u64);
+        hasher.write_u64(self.rows() as u64);
+
+        for column in self.data_cols.iter() {
+            match column.name {
+                Some(ref name) => {
+                    hasher.write(&[1]);
+                    hasher.write_str(name);
+                }
+                None => hasher.write(&[0]),
+            }
+        }
+
+        hasher
+    }
+}
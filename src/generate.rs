@@ -0,0 +1,230 @@
+//! The generate module.
+//!
+//! Provides the TableSpec which builds random DataTables, for
+//! benchmarks and for testing code which consumes tables.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use schema::DType;
+
+/// How the values of a generated column are drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Distribution {
+    /// Floats drawn uniformly from `low` up to `high`.
+    Uniform {
+        /// The smallest value.
+        low: f64,
+        /// The value which all values are below.
+        high: f64,
+    },
+    /// Floats drawn from a normal distribution.
+    Normal {
+        /// The mean of the values.
+        mean: f64,
+        /// The standard deviation of the values.
+        std_dev: f64,
+    },
+    /// Integers drawn uniformly from `low` to `high` inclusive.
+    Integer {
+        /// The smallest value.
+        low: i64,
+        /// The largest value.
+        high: i64,
+    },
+    /// Booleans which are true with probability `p`.
+    Bool {
+        /// The probability of `true`.
+        p: f64,
+    },
+    /// Labels drawn uniformly from the categories.
+    ///
+    /// The column's categories are set in this order.
+    Categories(Vec<String>),
+    /// Consecutive integers counting up from `start`, such as row ids.
+    Sequence {
+        /// The first value.
+        start: i64,
+    },
+}
+
+/// A column of a generated table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSpec {
+    /// The name of the column.
+    pub name: String,
+    /// How the values are drawn.
+    pub distribution: Distribution,
+    /// The probability of each value being missing.
+    pub missing_rate: f64,
+}
+
+impl ColumnSpec {
+    /// Constructs a ColumnSpec with no missing values.
+    pub fn new<S: Into<String>>(name: S, distribution: Distribution) -> ColumnSpec {
+        ColumnSpec {
+            name: name.into(),
+            distribution,
+            missing_rate: 0.0,
+        }
+    }
+}
+
+/// A description of a random table.
+///
+/// Tables generated from the same spec, including its seed, are the same.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::generate::{ColumnSpec, Distribution, TableSpec};
+///
+/// let mut score = ColumnSpec::new("score", Distribution::Normal { mean: 50.0, std_dev: 10.0 });
+/// score.missing_rate = 0.1;
+///
+/// let spec = TableSpec {
+///     rows: 1000,
+///     seed: 7,
+///     columns: vec![
+///         ColumnSpec::new("id", Distribution::Sequence { start: 1 }),
+///         ColumnSpec::new("group", Distribution::Categories(vec!["a".into(), "b".into()])),
+///         score,
+///     ],
+/// };
+///
+/// let table = spec.generate().unwrap();
+/// assert_eq!(table.rows(), 1000);
+/// assert_eq!(table[0][999], *"1000");
+/// assert_eq!(table[1].category_labels().unwrap(), vec!["a", "b"]);
+/// assert!(table[2].null_count() > 50 && table[2].null_count() < 150);
+///
+/// assert!(spec.generate().unwrap().compare(&table).is_empty());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableSpec {
+    /// The number of rows.
+    pub rows: usize,
+    /// The seed of the random number generator.
+    pub seed: u64,
+    /// The columns, in order.
+    pub columns: Vec<ColumnSpec>,
+}
+
+impl TableSpec {
+    /// Generates a table from the spec.
+    ///
+    /// Missing values are empty and marked as missing. The type of each
+    /// column is recorded, see `DataColumn::dtype`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A distribution has an empty range, a negative
+    ///   standard deviation, a probability outside `0` to `1` or no
+    ///   categories, or a missing rate is outside `0` to `1`.
+    pub fn generate(&self) -> Result<DataTable, DataError> {
+        for column in self.columns.iter() {
+            validate(column)?;
+        }
+
+        let mut rng = SplitMix64 { state: self.seed };
+        let mut table = DataTable::empty();
+
+        for spec in self.columns.iter() {
+            let mut column = DataColumn::empty();
+            column.name = Some(spec.name.clone());
+
+            for row in 0..self.rows {
+                // Always draw so the values do not depend on the missing rate.
+                let val = draw(&spec.distribution, &mut rng, row);
+
+                if spec.missing_rate > 0.0 && rng.next_f64() < spec.missing_rate {
+                    column.push_missing("");
+                } else {
+                    column.push(val);
+                }
+            }
+
+            let dtype = match spec.distribution {
+                Distribution::Uniform { .. } | Distribution::Normal { .. } => DType::F64,
+                Distribution::Integer { .. } | Distribution::Sequence { .. } => DType::I64,
+                Distribution::Bool { .. } => DType::Bool,
+                Distribution::Categories(ref labels) => {
+                    let categories: HashMap<String, usize> =
+                        labels.iter().enumerate().map(|(i, l)| (l.clone(), i)).collect();
+                    column.set_categories(categories);
+                    DType::String
+                }
+            };
+            column.set_dtype(dtype);
+
+            table.data_cols.push(column);
+        }
+
+        Ok(table)
+    }
+}
+
+/// Checks that values can be drawn for the column.
+fn validate(column: &ColumnSpec) -> Result<(), DataError> {
+    let probability = |p: f64| (0.0..=1.0).contains(&p);
+
+    let valid = match column.distribution {
+        Distribution::Uniform { low, high } => low < high && (high - low).is_finite(),
+        Distribution::Normal { mean, std_dev } => mean.is_finite() && std_dev >= 0.0,
+        Distribution::Integer { low, high } => low <= high,
+        Distribution::Bool { p } => probability(p),
+        Distribution::Categories(ref labels) => !labels.is_empty(),
+        Distribution::Sequence { .. } => true,
+    };
+
+    if valid && probability(column.missing_rate) {
+        Ok(())
+    } else {
+        Err(DataError::InvalidStateError)
+    }
+}
+
+/// Draws the value for the row.
+fn draw(distribution: &Distribution, rng: &mut SplitMix64, row: usize) -> String {
+    match *distribution {
+        Distribution::Uniform { low, high } => (low + rng.next_f64() * (high - low)).to_string(),
+        Distribution::Normal { mean, std_dev } => {
+            // Box-Muller transform, 1 - u keeps the logarithm finite.
+            let u = 1.0 - rng.next_f64();
+            let v = rng.next_f64();
+            let z = (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos();
+            (mean + std_dev * z).to_string()
+        }
+        Distribution::Integer { low, high } => {
+            let span = (high as i128 - low as i128 + 1) as u128;
+            (low as i128 + (rng.next_u64() as u128 % span) as i128).to_string()
+        }
+        Distribution::Bool { p } => (rng.next_f64() < p).to_string(),
+        Distribution::Categories(ref labels) => {
+            labels[(rng.next_u64() % labels.len() as u64) as usize].clone()
+        }
+        Distribution::Sequence { start } => (start as i128 + row as i128).to_string(),
+    }
+}
+
+/// A small, fast generator which is not suitable for cryptography.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A float from `0` up to `1`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
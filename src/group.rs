@@ -0,0 +1,331 @@
+//! The group module.
+//!
+//! Provides grouping of the rows of a DataTable by the values of
+//! key columns, and aggregation over each group.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+
+/// An aggregation computed over the values of a column in each group.
+///
+/// Every aggregation skips missing values. The numeric aggregations
+/// parse the values as floats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Agg {
+    /// The number of present values.
+    Count,
+    /// The sum of the values.
+    Sum,
+    /// The mean of the values.
+    Mean,
+    /// The sample standard deviation of the values.
+    Std,
+    /// The smallest value.
+    Min,
+    /// The largest value.
+    Max,
+    /// The first value in table order.
+    First,
+    /// The last value in table order.
+    Last,
+}
+
+impl Agg {
+    fn is_numeric(self) -> bool {
+        !matches!(self, Agg::Count | Agg::First | Agg::Last)
+    }
+}
+
+/// The rows of a table grouped by the values of key columns.
+///
+/// Created by `DataTable::group_by`. Groups are ordered by where their
+/// key first appears in the table.
+pub struct GroupBy<'a> {
+    table: &'a DataTable,
+    keys: Vec<usize>,
+    groups: Vec<Vec<usize>>,
+}
+
+impl DataTable {
+    /// Groups the rows by their values in the `keys` columns.
+    ///
+    /// Rows with a missing key are left out of every group.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has one of the names.
+    pub fn group_by(&self, keys: &[&str]) -> Result<GroupBy<'_>, DataError> {
+        let start = Instant::now();
+        let names = keys;
+        let keys = keys.iter()
+            .map(|name| self.column_index(name).ok_or(DataError::InvalidStateError))
+            .collect::<Result<Vec<usize>, DataError>>()?;
+
+        let mut lookup: HashMap<Vec<&str>, usize> = HashMap::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+
+        for row in 0..self.rows() {
+            let key: Option<Vec<&str>> = keys.iter()
+                .map(|&k| {
+                    let column = &self.data_cols[k];
+                    if column.is_missing(row) { None } else { column.get(row) }
+                })
+                .collect();
+
+            if let Some(key) = key {
+                let next = groups.len();
+                let group = *lookup.entry(key).or_insert(next);
+                if group == next {
+                    groups.push(Vec::new());
+                }
+                groups[group].push(row);
+            }
+        }
+
+        log_info!("grouped {} rows into {} groups by {:?} in {:?}",
+                  self.rows(),
+                  groups.len(),
+                  names,
+                  start.elapsed());
+
+        Ok(GroupBy {
+            table: self,
+            keys,
+            groups,
+        })
+    }
+}
+
+impl<'a> GroupBy<'a> {
+    /// The number of groups.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns true if there are no groups.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The rows of the table in each group.
+    pub fn groups(&self) -> &[Vec<usize>] {
+        &self.groups
+    }
+
+    /// Computes aggregations over each group.
+    ///
+    /// Each entry of `spec` names the column to aggregate, the aggregation
+    /// and the name of the output column. The result has one row per group,
+    /// holding the key columns followed by the output columns. Aggregations
+    /// with no values to work on, such as the mean of only missing values,
+    /// give a missing value.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has one of the names.
+    /// - DataCastError : A value of a numeric aggregation could not be parsed as a float.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::group::Agg;
+    ///
+    /// let records = vec![vec!["a", "1", "x"], vec!["b", "5", "y"], vec!["a", "3", "z"]];
+    /// let table = DataTable::from_records(Some(vec!["site", "value", "id"]), records).unwrap();
+    ///
+    /// let summary = table.group_by(&["site"]).unwrap()
+    ///     .agg(&[("value", Agg::Mean, "value_mean"),
+    ///            ("value", Agg::Std, "value_std"),
+    ///            ("id", Agg::Count, "n")])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(summary.headers(), vec!["site", "value_mean", "value_std", "n"]);
+    /// assert_eq!(summary[0].data(), vec!["a", "b"]);
+    /// assert_eq!(summary[1].data(), vec!["2", "5"]);
+    /// assert!(summary[2].is_missing(1));
+    /// assert_eq!(summary[3].data(), vec!["2", "1"]);
+    /// ```
+    pub fn agg(&self, spec: &[(&str, Agg, &str)]) -> Result<DataTable, DataError> {
+        let start = Instant::now();
+        let sources = spec.iter()
+            .map(|&(name, _, _)| self.table.column(name).ok_or(DataError::InvalidStateError))
+            .collect::<Result<Vec<&DataColumn>, DataError>>()?;
+
+        let mut table = self.key_table();
+        let mut outputs: Vec<DataColumn> = spec.iter()
+            .map(|&(_, _, name)| {
+                let mut column = DataColumn::empty();
+                column.name = Some(name.to_string());
+                column
+            })
+            .collect();
+
+        for rows in self.groups.iter() {
+            let mut acc: Vec<Accumulator> = vec![Accumulator::default(); spec.len()];
+
+            for &row in rows {
+                for (i, (&(_, agg, _), source)) in spec.iter().zip(sources.iter()).enumerate() {
+                    if source.is_missing(row) {
+                        continue;
+                    }
+
+                    if agg.is_numeric() {
+                        acc[i].push(source.get_as(row)?);
+                    } else {
+                        acc[i].push_row(row);
+                    }
+                }
+            }
+
+            for (i, output) in outputs.iter_mut().enumerate() {
+                acc[i].finish(spec[i].1, sources[i], output);
+            }
+        }
+
+        table.data_cols.extend(outputs);
+
+        log_info!("aggregated {} groups into {} columns in {:?}",
+                  self.groups.len(),
+                  spec.len(),
+                  start.elapsed());
+        Ok(table)
+    }
+
+    /// Calls `f` with a table of the rows in each group, joining the results.
+    ///
+    /// `f` returns a table with any number of rows, such as a single row
+    /// of statistics. Each returned row is prefixed with the key of its
+    /// group. The returned columns are named after those of the first group.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A result has a different number of columns to
+    ///   the first.
+    /// - Any error returned by `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["a", "1"], vec!["b", "5"], vec!["a", "3"], vec!["a", "2"]];
+    /// let table = DataTable::from_records(Some(vec!["site", "value"]), records).unwrap();
+    ///
+    /// // Keeps the two largest values of each site.
+    /// let top = table.group_by(&["site"]).unwrap()
+    ///     .apply(|group| {
+    ///         let mut top = group.nlargest(2, "value")?;
+    ///         top.data_cols.remove(0);
+    ///         Ok(top)
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(top.headers(), vec!["site", "value"]);
+    /// assert_eq!(top[0].data(), vec!["a", "a", "b"]);
+    /// assert_eq!(top[1].data(), vec!["3", "2", "5"]);
+    /// ```
+    pub fn apply<F>(&self, mut f: F) -> Result<DataTable, DataError>
+        where F: FnMut(&DataTable) -> Result<DataTable, DataError>
+    {
+        let keys = self.key_table();
+        let mut table = DataTable {
+            data_cols: keys.data_cols.iter().map(|c| c.empty_like()).collect(),
+        };
+        let mut results: Option<Vec<DataColumn>> = None;
+
+        for (group, rows) in self.groups.iter().enumerate() {
+            let result = f(&self.table.take_rows(rows))?;
+
+            let columns = results.get_or_insert_with(|| {
+                result.data_cols.iter().map(|c| c.empty_like()).collect()
+            });
+            if result.cols() != columns.len() {
+                return Err(DataError::InvalidStateError);
+            }
+
+            for row in 0..result.rows() {
+                for (from, to) in keys.data_cols.iter().zip(table.data_cols.iter_mut()) {
+                    to.push_from(from, group);
+                }
+                for (from, to) in result.data_cols.iter().zip(columns.iter_mut()) {
+                    to.push_from(from, row);
+                }
+            }
+        }
+
+        table.data_cols.extend(results.unwrap_or_default());
+        Ok(table)
+    }
+
+    /// A table holding the key columns with one row per group.
+    fn key_table(&self) -> DataTable {
+        let firsts: Vec<usize> = self.groups.iter().map(|rows| rows[0]).collect();
+        let mut table = DataTable::empty();
+
+        for &k in self.keys.iter() {
+            let from = &self.table.data_cols[k];
+            let mut column = from.empty_like();
+            for &row in firsts.iter() {
+                column.push_from(from, row);
+            }
+            table.data_cols.push(column);
+        }
+
+        table
+    }
+}
+
+/// The running state of one aggregation over one group.
+#[derive(Clone, Default)]
+struct Accumulator {
+    count: usize,
+    mean: f64,
+    // The sum of squared differences from the mean, updated as in Welford's method.
+    m2: f64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    first: Option<usize>,
+    last: Option<usize>,
+}
+
+impl Accumulator {
+    fn push(&mut self, val: f64) {
+        self.count += 1;
+        self.sum += val;
+
+        let delta = val - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (val - self.mean);
+
+        self.min = Some(self.min.map_or(val, |m| m.min(val)));
+        self.max = Some(self.max.map_or(val, |m| m.max(val)));
+    }
+
+    fn push_row(&mut self, row: usize) {
+        self.count += 1;
+        self.first = self.first.or(Some(row));
+        self.last = Some(row);
+    }
+
+    fn finish(&self, agg: Agg, source: &DataColumn, output: &mut DataColumn) {
+        let n = self.count;
+
+        match agg {
+            Agg::Count => output.push_value(n),
+            Agg::Sum => output.push_value(self.sum),
+            Agg::Mean => output.push_opt(if n > 0 { Some(self.mean) } else { None }),
+            Agg::Std => {
+                output.push_opt(if n > 1 { Some((self.m2 / (n - 1) as f64).sqrt()) } else { None })
+            }
+            Agg::Min => output.push_opt(self.min),
+            Agg::Max => output.push_opt(self.max),
+            Agg::First => output.push_opt(self.first.map(|row| &source[row])),
+            Agg::Last => output.push_opt(self.last.map(|row| &source[row])),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! The history module.
+//!
+//! Provides the VersionedTable which records changes to a DataTable
+//! so that they can be undone.
+//!
+//! The history is a list of snapshots rather than a changelog of the
+//! inverse of each mutation on `DataTable`. The table's many mutating
+//! methods would each need an inverse kept in step with them, while a
+//! snapshot undoes any change. A snapshot is a clone of the table, whose
+//! columns share their values with the current table until they are
+//! changed, so each change keeps a copy of only the columns it touched.
+//! A change to one value of a large column still keeps the whole column,
+//! so long sessions of small edits should `clear_history` once they no
+//! longer need to undo them.
+//!
+//! The table can only be changed through `edit` and `try_edit`, so no
+//! change escapes the history. `into_table` ends the history.
+
+use std::ops::Deref;
+
+use datatable::DataTable;
+use error::DataError;
+
+/// A DataTable which keeps earlier versions so that changes can be undone.
+///
+/// Changes are made through `edit`, which records the table as it was
+/// before the change under a label. The table is read through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::DataTable;
+/// use rusty_data::history::VersionedTable;
+///
+/// let records = vec![vec!["1", "a"], vec!["2", "b"]];
+/// let table = DataTable::from_records(Some(vec!["id", "label"]), records).unwrap();
+/// let mut versioned = VersionedTable::new(table);
+///
+/// versioned.edit("drop first row", |t| {
+///     for column in t.data_cols.iter_mut() {
+///         column.remove(0);
+///     }
+/// });
+/// versioned.edit("drop labels", |t| {
+///     t.data_cols.remove(1);
+/// });
+/// assert_eq!(versioned.changes(), vec!["drop first row", "drop labels"]);
+///
+/// assert_eq!(versioned.undo(), Some("drop labels".to_string()));
+/// assert_eq!(versioned.cols(), 2);
+/// assert_eq!(versioned[0].data(), vec!["2"]);
+/// ```
+///
+/// The table cannot be changed without recording the change.
+///
+/// ```compile_fail
+/// use rusty_data::datatable::{DataColumn, DataTable};
+/// use rusty_data::history::VersionedTable;
+///
+/// let mut versioned = VersionedTable::new(DataTable::empty());
+/// versioned.data_cols.push(DataColumn::empty());
+/// ```
+pub struct VersionedTable {
+    table: DataTable,
+    // The label of each change and the table before it, oldest first.
+    history: Vec<(String, DataTable)>,
+}
+
+impl VersionedTable {
+    /// Constructs a VersionedTable with no recorded changes.
+    pub fn new(table: DataTable) -> VersionedTable {
+        VersionedTable {
+            table,
+            history: Vec::new(),
+        }
+    }
+
+    /// Records the current table under the label, so `undo` returns to it.
+    pub fn checkpoint<S: Into<String>>(&mut self, label: S) {
+        self.history.push((label.into(), self.table.clone()));
+    }
+
+    /// Records a checkpoint with the label and then applies the change.
+    pub fn edit<S, F, T>(&mut self, label: S, change: F) -> T
+        where S: Into<String>,
+              F: FnOnce(&mut DataTable) -> T
+    {
+        self.checkpoint(label);
+        change(&mut self.table)
+    }
+
+    /// Applies a change which can fail, recording it only if it succeeds.
+    ///
+    /// # Failures
+    ///
+    /// - Returns the error of the change, leaving the table as it was before.
+    pub fn try_edit<S, F, T>(&mut self, label: S, change: F) -> Result<T, DataError>
+        where S: Into<String>,
+              F: FnOnce(&mut DataTable) -> Result<T, DataError>
+    {
+        self.checkpoint(label);
+
+        change(&mut self.table).inspect_err(|_| {
+            self.undo();
+        })
+    }
+
+    /// Returns the table to before the latest change.
+    ///
+    /// Returns the label of the change, or `None` if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<String> {
+        let (label, table) = self.history.pop()?;
+        self.table = table;
+        Some(label)
+    }
+
+    /// The labels of the recorded changes, oldest first.
+    pub fn changes(&self) -> Vec<&str> {
+        self.history.iter().map(|(label, _)| &label[..]).collect()
+    }
+
+    /// Forgets every recorded change, keeping the current table.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Consumes self and returns the current table, forgetting the history.
+    pub fn into_table(self) -> DataTable {
+        self.table
+    }
+}
+
+impl Deref for VersionedTable {
+    type Target = DataTable;
+
+    fn deref(&self) -> &DataTable {
+        &self.table
+    }
+}
+
+impl From<DataTable> for VersionedTable {
+    fn from(table: DataTable) -> VersionedTable {
+        VersionedTable::new(table)
+    }
+}
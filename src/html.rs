@@ -0,0 +1,758 @@
+//! The html module.
+//!
+//! Provides loading of the `<table>` elements of HTML pages into
+//! DataTables, as many small reference datasets are only published as
+//! tables on web pages.
+//!
+//! Pages are read leniently, as browsers do. Omitted end tags, unquoted
+//! attributes and the common named entities are understood, and the
+//! contents of scripts and styles are skipped.
+
+use std::fs;
+use std::path::Path;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+
+/// The largest `colspan` or `rowspan` of a cell.
+const MAX_SPAN: usize = 1000;
+
+/// The elements which never have contents or an end tag.
+const VOID_ELEMENTS: [&str; 14] = ["area", "base", "br", "col", "embed", "hr", "img", "input",
+                                   "link", "meta", "param", "source", "track", "wbr"];
+
+/// The elements whose contents are not markup.
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// Which table of a page to load.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableSelector {
+    /// The table with the index, counting every table in the order they start.
+    Index(usize),
+    /// The first table which matches the CSS selector, or is within an element which does.
+    ///
+    /// Selectors are made of tag names, `*`, `#id`, `.class`, `[attr]` and
+    /// `[attr=value]`, combined with descendant (space) and child (`>`)
+    /// combinators. Several selectors may be separated by commas.
+    Css(String),
+}
+
+/// Options used to fine tune loading HTML tables
+///
+/// The column names are taken from the rows of the table's `<thead>`, or
+/// else from its first row if every cell of that row is a `<th>`. A table
+/// with several header rows has names joined from each row, so that a
+/// heading spanning several columns prefixes each of their names.
+///
+/// Cells spanning several columns or rows are repeated in each of them.
+/// The text of a cell has its whitespace collapsed, and rows which are
+/// shorter than the table are padded with missing values. Tables within
+/// the cells of the table are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::DataTable;
+/// use rusty_data::html::{HtmlOptions, TableSelector};
+///
+/// let page = r#"<html><body>
+///   <table id="nav"><tr><td>Home</td></table>
+///   <table class="wikitable">
+///     <tr><th>City<th>Population
+///     <tr><td>Bath<td>88,859
+///     <tr><td>Leeds &amp; Bradford<td>
+///   </table>
+/// </body></html>"#;
+///
+/// let options = HtmlOptions { table: TableSelector::Css("table.wikitable".to_string()) };
+/// let table = DataTable::from_html_str(page, &options).unwrap();
+///
+/// assert_eq!(table.headers(), vec!["City", "Population"]);
+/// assert_eq!(table[0].data(), vec!["Bath", "Leeds & Bradford"]);
+/// assert_eq!(&table[1][0], "88,859");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// The table to load
+    pub table: TableSelector,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> HtmlOptions {
+        HtmlOptions { table: TableSelector::Index(0) }
+    }
+}
+
+impl DataTable {
+    /// Loads a table from an HTML page, see `HtmlOptions`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No table matches the selector, or the CSS
+    ///   selector is not valid.
+    pub fn from_html_str(text: &str, options: &HtmlOptions) -> Result<DataTable, DataError> {
+        let selector = match options.table {
+            TableSelector::Css(ref css) => Some(Selector::parse(css)?),
+            TableSelector::Index(_) => None,
+        };
+
+        let mut tokens = Tokenizer { text, pos: 0, raw_text: None };
+        let mut stack: Vec<Element> = Vec::new();
+        let mut tables = 0;
+        let mut builder: Option<TableBuilder> = None;
+
+        while let Some(token) = tokens.next_token() {
+            match token {
+                Token::Start(element) => {
+                    let name = element.name.clone();
+                    let void = VOID_ELEMENTS.contains(&&name[..]);
+                    close_implied(&mut stack, &name);
+                    stack.push(element);
+
+                    match builder {
+                        Some(ref mut table) => table.start(&stack[stack.len() - 1]),
+                        None if name == "table" => {
+                            let chosen = match selector {
+                                Some(ref selector) => {
+                                    (0..stack.len()).any(|i| selector.matches(&stack[..=i]))
+                                }
+                                None => options.table == TableSelector::Index(tables),
+                            };
+                            if chosen {
+                                builder = Some(TableBuilder::default());
+                            }
+                            tables += 1;
+                        }
+                        None => {}
+                    }
+
+                    if void {
+                        stack.pop();
+                    }
+                }
+                Token::End(name) => {
+                    if let Some(ref mut table) = builder {
+                        if table.end(&name) {
+                            return Ok(builder.take().expect("table was checked").finish());
+                        }
+                    }
+                    if let Some(open) = stack.iter().rposition(|e| e.name == name) {
+                        stack.truncate(open);
+                    }
+                }
+                Token::Text(text) => {
+                    if let Some(ref mut table) = builder {
+                        table.text(&decode(text));
+                    }
+                }
+            }
+        }
+
+        builder.map(TableBuilder::finish).ok_or(DataError::InvalidStateError)
+    }
+
+    /// Loads a table from an HTML file, see `HtmlOptions`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : No table matches the selector, or the CSS
+    ///   selector is not valid.
+    pub fn load_html<P: AsRef<Path>>(path: P,
+                                     options: &HtmlOptions)
+                                     -> Result<DataTable, DataError> {
+        DataTable::from_html_str(&fs::read_to_string(path)?, options)
+    }
+}
+
+/// Closes the open elements whose end tags may be omitted before `name` starts.
+fn close_implied(stack: &mut Vec<Element>, name: &str) {
+    // The elements closed by `name`, and the elements which stop the search.
+    let (closes, scope): (&[&str], &[&str]) = match name {
+        "td" | "th" => (&["td", "th"], &["tr", "table"]),
+        "tr" => (&["tr"], &["thead", "tbody", "tfoot", "table"]),
+        "thead" | "tbody" | "tfoot" => (&["thead", "tbody", "tfoot"], &["table"]),
+        "li" => (&["li"], &["ul", "ol"]),
+        "p" => (&["p"], &["td", "th", "table", "div"]),
+        _ => return,
+    };
+
+    for i in (0..stack.len()).rev() {
+        if scope.contains(&&stack[i].name[..]) {
+            return;
+        }
+        if closes.contains(&&stack[i].name[..]) {
+            stack.truncate(i);
+            return;
+        }
+    }
+}
+
+/// An element and its attributes.
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl Element {
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.iter().find(|a| a.0 == key).map(|a| &a.1[..])
+    }
+
+    /// The value of a `colspan` or `rowspan` attribute.
+    fn span(&self, key: &str) -> usize {
+        self.attribute(key)
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .unwrap_or(1)
+            .clamp(1, MAX_SPAN)
+    }
+}
+
+/// A cell of the table being read.
+struct Cell {
+    text: String,
+    header: bool,
+    colspan: usize,
+    rowspan: usize,
+}
+
+/// A row of the table being read.
+struct Row {
+    cells: Vec<Cell>,
+    head: bool,
+}
+
+/// Collects the rows of the chosen table.
+#[derive(Default)]
+struct TableBuilder {
+    rows: Vec<Row>,
+    in_head: bool,
+    in_cell: bool,
+    // The number of tables open within the chosen table.
+    nested: usize,
+}
+
+impl TableBuilder {
+    fn start(&mut self, element: &Element) {
+        if element.name == "table" {
+            self.nested += 1;
+        }
+        if self.nested > 0 {
+            return;
+        }
+
+        match &element.name[..] {
+            "thead" => self.in_head = true,
+            "tbody" | "tfoot" => self.in_head = false,
+            "tr" => {
+                self.rows.push(Row { cells: Vec::new(), head: self.in_head });
+                self.in_cell = false;
+            }
+            "td" | "th" => {
+                if self.rows.is_empty() {
+                    self.rows.push(Row { cells: Vec::new(), head: self.in_head });
+                }
+                let row = self.rows.last_mut().expect("row was added");
+                row.cells.push(Cell {
+                    text: String::new(),
+                    header: element.name == "th",
+                    colspan: element.span("colspan"),
+                    rowspan: element.span("rowspan"),
+                });
+                self.in_cell = true;
+            }
+            "br" => self.text(" "),
+            _ => {}
+        }
+    }
+
+    /// Handles an end tag, returning true if it ends the table.
+    fn end(&mut self, name: &str) -> bool {
+        match name {
+            "table" if self.nested == 0 => true,
+            "table" => {
+                self.nested -= 1;
+                false
+            }
+            _ if self.nested > 0 => false,
+            "thead" => {
+                self.in_head = false;
+                false
+            }
+            "td" | "th" | "tr" => {
+                self.in_cell = false;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.nested > 0 || !self.in_cell {
+            return;
+        }
+        if let Some(cell) = self.rows.last_mut().and_then(|r| r.cells.last_mut()) {
+            cell.text.push_str(text);
+        }
+    }
+
+    fn finish(self) -> DataTable {
+        let head_rows = match self.rows.iter().take_while(|r| r.head).count() {
+            0 if self.rows.first().is_some_and(|r| !r.cells.is_empty() &&
+                                                   r.cells.iter().all(|c| c.header)) => 1,
+            count => count,
+        };
+
+        let grid = expand_spans(self.rows);
+        let width = grid.iter().map(|r| r.len()).max().unwrap_or(0);
+        let mut columns: Vec<DataColumn> = (0..width).map(|_| DataColumn::empty()).collect();
+
+        for (col, column) in columns.iter_mut().enumerate() {
+            if head_rows > 0 {
+                let mut parts: Vec<&str> = Vec::new();
+                for row in &grid[..head_rows] {
+                    if let Some(Some(part)) = row.get(col) {
+                        if !part.is_empty() && parts.last() != Some(&&part[..]) {
+                            parts.push(part);
+                        }
+                    }
+                }
+                column.name = Some(parts.join(" "));
+            }
+
+            for row in &grid[head_rows..] {
+                match row.get(col) {
+                    Some(Some(text)) => column.push(&text[..]),
+                    _ => column.push_missing(""),
+                }
+            }
+        }
+
+        DataTable { data_cols: columns }
+    }
+}
+
+/// Lays out the cells of the rows, repeating cells which span several
+/// columns or rows. Positions without a cell are `None`.
+fn expand_spans(rows: Vec<Row>) -> Vec<Vec<Option<String>>> {
+    // The text and remaining rows of the cells spanning down each column.
+    let mut carried: Vec<Option<(String, usize)>> = Vec::new();
+    let mut grid = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let mut out = Vec::new();
+        let mut cells = row.cells.into_iter();
+
+        loop {
+            let col = out.len();
+            if let Some(&mut Some((ref text, ref mut remaining))) = carried.get_mut(col) {
+                out.push(Some(text.clone()));
+                *remaining -= 1;
+                if *remaining == 0 {
+                    carried[col] = None;
+                }
+                continue;
+            }
+
+            match cells.next() {
+                Some(cell) => {
+                    let text = cell.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    for _ in 0..cell.colspan {
+                        let col = out.len();
+                        if cell.rowspan > 1 {
+                            if carried.len() <= col {
+                                carried.resize(col + 1, None);
+                            }
+                            carried[col] = Some((text.clone(), cell.rowspan - 1));
+                        }
+                        out.push(Some(text.clone()));
+                    }
+                }
+                None if carried[col.min(carried.len())..].iter().any(|c| c.is_some()) => {
+                    out.push(None)
+                }
+                None => break,
+            }
+        }
+
+        grid.push(out);
+    }
+
+    grid
+}
+
+/// A part of an HTML page.
+enum Token<'a> {
+    Start(Element),
+    End(String),
+    Text(&'a str),
+}
+
+/// Splits an HTML page into tokens.
+struct Tokenizer<'a> {
+    text: &'a str,
+    pos: usize,
+    // The element whose contents are being skipped.
+    raw_text: Option<String>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        loop {
+            let rest = &self.text[self.pos..];
+            if rest.is_empty() {
+                return None;
+            }
+
+            if let Some(name) = self.raw_text.take() {
+                self.pos += find_end_tag(rest, &name).unwrap_or(rest.len());
+                continue;
+            }
+
+            let bytes = rest.as_bytes();
+            if bytes[0] != b'<' {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                return Some(Token::Text(&rest[..end]));
+            }
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                self.pos += comment.find("-->").map_or(rest.len(), |end| end + 7);
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                self.pos += rest.find('>').map_or(rest.len(), |end| end + 1);
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                self.pos += rest.find('>').map_or(rest.len(), |end| end + 1);
+                let name = tag_name(tag);
+                if !name.is_empty() {
+                    return Some(Token::End(name));
+                }
+            } else if bytes.get(1).is_some_and(|b| b.is_ascii_alphabetic()) {
+                return Some(self.start_tag());
+            } else {
+                self.pos += 1;
+                return Some(Token::Text("<"));
+            }
+        }
+    }
+
+    fn start_tag(&mut self) -> Token<'a> {
+        let text = self.text;
+        let name = tag_name(&text[self.pos + 1..]);
+        self.pos += 1 + name.len();
+
+        let mut attributes = Vec::new();
+        loop {
+            let rest = text[self.pos..].trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            self.pos = text.len() - rest.len();
+            if rest.is_empty() {
+                break;
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let key_end = rest.find(|c: char| c.is_whitespace() || "=>/".contains(c))
+                .unwrap_or(rest.len())
+                .max(1);
+            let key = rest[..key_end].to_ascii_lowercase();
+            let after = rest[key_end..].trim_start();
+
+            let value = match after.strip_prefix('=') {
+                Some(value) => {
+                    let value = value.trim_start();
+                    let (raw, len) = match value.chars().next() {
+                        Some(q) if q == '"' || q == '\'' => {
+                            let close = value[1..].find(q).map_or(value.len(), |i| i + 1);
+                            (&value[1..close], (close + 1).min(value.len()))
+                        }
+                        _ => {
+                            let end = value.find(|c: char| c.is_whitespace() || c == '>')
+                                .unwrap_or(value.len());
+                            (&value[..end], end)
+                        }
+                    };
+                    self.pos = text.len() - value.len() + len;
+                    decode(raw)
+                }
+                None => {
+                    self.pos = text.len() - after.len();
+                    String::new()
+                }
+            };
+            attributes.push((key, value));
+        }
+
+        if RAW_TEXT_ELEMENTS.contains(&&name[..]) {
+            self.raw_text = Some(name.clone());
+        }
+
+        Token::Start(Element { name, attributes })
+    }
+}
+
+/// The lowercased name at the start of a tag.
+fn tag_name(text: &str) -> String {
+    let end = text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+        .unwrap_or(text.len());
+    text[..end].to_ascii_lowercase()
+}
+
+/// Finds the end tag of the element, ignoring case.
+fn find_end_tag(text: &str, name: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(start) = text[from..].find("</") {
+        let start = from + start;
+        if tag_name(&text[start + 2..]) == name {
+            return Some(start);
+        }
+        from = start + 2;
+    }
+    None
+}
+
+/// Replaces the character references in the text.
+///
+/// References which are not known are left as they are, as browsers do.
+/// The references which may appear without their closing `;`.
+const LEGACY: [&str; 5] = ["amp", "lt", "gt", "quot", "nbsp"];
+
+fn decode(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest[1..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '#')).map(|e| e + 1);
+        let name = &rest[1..end.unwrap_or(rest.len())];
+        // The basic references are also recognised without the `;`, as in browsers.
+        let (reference, len) = if rest[1 + name.len()..].starts_with(';') {
+            (name, name.len() + 2)
+        } else if LEGACY.contains(&name) {
+            (name, name.len() + 1)
+        } else {
+            ("", 0)
+        };
+
+        match character(reference) {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+/// The character named by a reference, without its `&` and `;`.
+fn character(reference: &str) -> Option<char> {
+    if let Some(number) = reference.strip_prefix('#') {
+        let code = match number.strip_prefix('x').or_else(|| number.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => number.parse().ok(),
+        };
+        return code.and_then(::std::char::from_u32);
+    }
+
+    let c = match reference {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "minus" => '\u{2212}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "hellip" => '\u{2026}',
+        "middot" => '\u{b7}',
+        "deg" => '\u{b0}',
+        "plusmn" => '\u{b1}',
+        "times" => '\u{d7}',
+        "divide" => '\u{f7}',
+        "micro" => '\u{b5}',
+        "sup2" => '\u{b2}',
+        "sup3" => '\u{b3}',
+        "frac14" => '\u{bc}',
+        "frac12" => '\u{bd}',
+        "frac34" => '\u{be}',
+        "cent" => '\u{a2}',
+        "pound" => '\u{a3}',
+        "yen" => '\u{a5}',
+        "euro" => '\u{20ac}',
+        "copy" => '\u{a9}',
+        "reg" => '\u{ae}',
+        "sect" => '\u{a7}',
+        "laquo" => '\u{ab}',
+        "raquo" => '\u{bb}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// A part of a CSS selector which matches a single element.
+#[derive(Default)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        let classes = element.attribute("class").unwrap_or("");
+        self.tag.as_ref().is_none_or(|tag| *tag == element.name) &&
+        self.id.as_ref().is_none_or(|id| element.attribute("id") == Some(&id[..])) &&
+        self.classes.iter().all(|class| classes.split_whitespace().any(|c| c == class)) &&
+        self.attributes.iter().all(|(key, value)| {
+            match (element.attribute(key), value) {
+                (Some(actual), Some(value)) => actual == value,
+                (found, None) => found.is_some(),
+                (None, _) => false,
+            }
+        })
+    }
+}
+
+/// A CSS selector, as a list of alternatives.
+///
+/// Each alternative is a list of compounds from the outermost, each with
+/// whether it must be the parent of the next rather than any ancestor.
+struct Selector {
+    alternatives: Vec<Vec<(Compound, bool)>>,
+}
+
+impl Selector {
+    fn parse(css: &str) -> Result<Selector, DataError> {
+        let mut alternatives = Vec::new();
+        for alternative in css.split(',') {
+            let mut parts: Vec<(Compound, bool)> = Vec::new();
+            let mut child = false;
+            let mut rest = alternative.trim();
+            if rest.is_empty() {
+                return Err(DataError::InvalidStateError);
+            }
+
+            while !rest.is_empty() {
+                if let Some(after) = rest.strip_prefix('>') {
+                    if parts.is_empty() || child {
+                        return Err(DataError::InvalidStateError);
+                    }
+                    child = true;
+                    rest = after.trim_start();
+                    continue;
+                }
+
+                let (compound, after) = parse_compound(rest)?;
+                if let Some(last) = parts.last_mut() {
+                    last.1 = child;
+                }
+                parts.push((compound, false));
+                child = false;
+                rest = after.trim_start();
+            }
+
+            if child {
+                return Err(DataError::InvalidStateError);
+            }
+            alternatives.push(parts);
+        }
+
+        Ok(Selector { alternatives })
+    }
+
+    /// Returns true if the last of the open elements matches.
+    fn matches(&self, stack: &[Element]) -> bool {
+        self.alternatives.iter().any(|parts| matches_from(parts, stack))
+    }
+}
+
+/// Returns true if the last compound matches the last element, and the
+/// others match its ancestors.
+fn matches_from(parts: &[(Compound, bool)], stack: &[Element]) -> bool {
+    let (last, outer) = match (parts.split_last(), stack.split_last()) {
+        (Some(((last, _), outer)), Some((element, _))) if last.matches(element) => {
+            (stack.len() - 1, outer)
+        }
+        _ => return false,
+    };
+
+    match outer.last() {
+        None => true,
+        Some(&(_, true)) => matches_from(outer, &stack[..last]),
+        Some(&(_, false)) => (1..=last).any(|end| matches_from(outer, &stack[..end])),
+    }
+}
+
+/// Parses the compound at the start of the text, returning the rest.
+fn parse_compound(text: &str) -> Result<(Compound, &str), DataError> {
+    let mut compound = Compound::default();
+    let ident_end = |s: &str| {
+        s.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_')).unwrap_or(s.len())
+    };
+
+    let mut rest = text;
+    if let Some(after) = rest.strip_prefix('*') {
+        rest = after;
+    } else {
+        let end = ident_end(rest);
+        if end > 0 {
+            compound.tag = Some(rest[..end].to_ascii_lowercase());
+            rest = &rest[end..];
+        }
+    }
+
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some(marker) if marker == '#' || marker == '.' => {
+                let name = chars.as_str();
+                let end = ident_end(name);
+                if end == 0 {
+                    return Err(DataError::InvalidStateError);
+                }
+                if marker == '#' {
+                    compound.id = Some(name[..end].to_string());
+                } else {
+                    compound.classes.push(name[..end].to_string());
+                }
+                rest = &name[end..];
+            }
+            Some('[') => {
+                let close = rest.find(']').ok_or(DataError::InvalidStateError)?;
+                let inner = &rest[1..close];
+                let attribute = match inner.find('=') {
+                    Some(eq) => {
+                        let value = inner[eq + 1..].trim().trim_matches(|c| c == '"' || c == '\'');
+                        (inner[..eq].trim().to_ascii_lowercase(), Some(value.to_string()))
+                    }
+                    None => (inner.trim().to_ascii_lowercase(), None),
+                };
+                if attribute.0.is_empty() {
+                    return Err(DataError::InvalidStateError);
+                }
+                compound.attributes.push(attribute);
+                rest = &rest[close + 1..];
+            }
+            _ => break,
+        }
+    }
+
+    if rest.len() == text.len() {
+        return Err(DataError::InvalidStateError);
+    }
+    Ok((compound, rest))
+}
@@ -0,0 +1,813 @@
+//! The interop module.
+//!
+//! Provides conversions between DataTables and the in-memory
+//! layouts used by other data libraries.
+
+use std::collections::HashMap;
+use std::str;
+#[cfg(feature = "parquet")]
+use std::fs::File;
+#[cfg(any(feature = "csv", feature = "parquet"))]
+use std::io;
+#[cfg(feature = "parquet")]
+use std::path::Path;
+#[cfg(feature = "arrow")]
+use std::error::Error;
+#[cfg(feature = "arrow")]
+use std::str::FromStr;
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
+
+#[cfg(feature = "arrow")]
+use arrow::array::{Array, ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+#[cfg(feature = "parquet")]
+use arrow::compute::concat_batches;
+#[cfg(feature = "arrow")]
+use arrow::datatypes::{DataType, Field, Schema};
+#[cfg(feature = "arrow")]
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+#[cfg(feature = "parquet")]
+use arrow::record_batch::RecordBatchReader;
+#[cfg(feature = "arrow")]
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+#[cfg(feature = "parquet")]
+use parquet::basic::Compression;
+#[cfg(feature = "parquet")]
+use parquet::errors::ParquetError;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+
+#[cfg(feature = "arrow")]
+use datatable::cast_value;
+use datatable::{DataColumn, DataTable, Row};
+#[cfg(feature = "arrow")]
+use error::CellLocation;
+use error::{DataError, LoaderError, LoaderErrorKind};
+#[cfg(feature = "arrow")]
+use schema::DType;
+
+/// The values of a column in Apache Arrow's `Utf8` layout.
+///
+/// Value `i` is held in `values[offsets[i]..offsets[i + 1]]`, so there
+/// is one more offset than there are values. These buffers can be passed
+/// directly to an Arrow `StringArray`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Utf8Buffers {
+    /// The start of each value within `values`, followed by the end of the last.
+    pub offsets: Vec<i32>,
+    /// The bytes of every value back to back.
+    pub values: Vec<u8>,
+}
+
+impl Utf8Buffers {
+    /// The number of values held in the buffers.
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Returns true if the buffers hold no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DataColumn {
+    /// Copies the column into Arrow's `Utf8` layout.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The column holds more bytes than a 32 bit offset can address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::empty();
+    /// dc.push("ab");
+    /// dc.push("c");
+    ///
+    /// let buffers = dc.to_utf8_buffers().unwrap();
+    ///
+    /// assert_eq!(buffers.offsets, vec![0, 2, 3]);
+    /// assert_eq!(buffers.values, b"abc".to_vec());
+    ///
+    /// let round_trip = DataColumn::from_utf8_buffers(&buffers).unwrap();
+    /// assert_eq!(round_trip.data(), dc.data());
+    /// ```
+    pub fn to_utf8_buffers(&self) -> Result<Utf8Buffers, DataError> {
+        let mut buffers = Utf8Buffers {
+            offsets: Vec::with_capacity(self.len() + 1),
+            values: Vec::new(),
+        };
+        buffers.offsets.push(0);
+
+        for i in 0..self.len() {
+            buffers.values.extend_from_slice(self[i].as_bytes());
+
+            if buffers.values.len() > i32::MAX as usize {
+                return Err(DataError::InvalidStateError);
+            }
+            buffers.offsets.push(buffers.values.len() as i32);
+        }
+
+        Ok(buffers)
+    }
+
+    /// Constructs a column from values in Arrow's `Utf8` layout.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The offsets are out of order or out of range, or
+    ///   a value is not valid UTF-8.
+    pub fn from_utf8_buffers(buffers: &Utf8Buffers) -> Result<DataColumn, DataError> {
+        let mut column = DataColumn::empty();
+
+        for window in buffers.offsets.windows(2) {
+            let (start, end) = (window[0], window[1]);
+
+            if start < 0 || end < start || end as usize > buffers.values.len() {
+                return Err(DataError::InvalidStateError);
+            }
+
+            match str::from_utf8(&buffers.values[start as usize..end as usize]) {
+                Ok(val) => column.push(val),
+                Err(_) => return Err(DataError::InvalidStateError),
+            }
+        }
+
+        Ok(column)
+    }
+}
+
+impl DataTable {
+    /// Copies each column into Arrow's `Utf8` layout along with its name.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A column holds more bytes than a 32 bit offset can address.
+    pub fn to_utf8_columns(&self) -> Result<Vec<(Option<String>, Utf8Buffers)>, DataError> {
+        self.data_cols
+            .iter()
+            .map(|column| Ok((column.name.clone(), column.to_utf8_buffers()?)))
+            .collect()
+    }
+
+    /// Constructs a table from named columns in Arrow's `Utf8` layout.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The columns have different lengths or hold invalid buffers.
+    pub fn from_utf8_columns(columns: &[(Option<String>, Utf8Buffers)]) -> Result<DataTable, DataError> {
+        let mut table = DataTable::empty();
+
+        for (name, buffers) in columns {
+            if buffers.len() != columns[0].1.len() {
+                return Err(DataError::InvalidStateError);
+            }
+
+            let mut column = DataColumn::from_utf8_buffers(buffers)?;
+            column.name = name.clone();
+            table.data_cols.push(column);
+        }
+
+        Ok(table)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl DataTable {
+    /// Converts the table to an Arrow `RecordBatch`.
+    ///
+    /// Each column becomes a nullable field named after the column, or its
+    /// index if it has no name. Columns with a type recorded by
+    /// `convert_columns` become `Boolean`, `Int64` or `Float64` arrays and
+    /// the rest become `Utf8` arrays. Missing values become nulls.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A present value does not parse as the recorded type of its column.
+    /// - InvalidStateError : Arrow rejected the columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate arrow;
+    /// extern crate rusty_data;
+    ///
+    /// use arrow::datatypes::DataType;
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::schema::DType;
+    ///
+    /// # fn main() {
+    /// let records = vec![vec!["ann", "31"], vec!["bob", "42"]];
+    /// let mut table = DataTable::from_records(Some(vec!["name", "age"]), records).unwrap();
+    /// table.convert_columns(&[("age", DType::I64)]).unwrap();
+    ///
+    /// let batch = table.to_record_batch().unwrap();
+    /// assert_eq!(batch.num_rows(), 2);
+    /// assert_eq!(batch.schema().field(1).data_type(), &DataType::Int64);
+    ///
+    /// let round_trip = DataTable::from_record_batch(&batch).unwrap();
+    /// assert_eq!(round_trip[1].data(), vec!["31", "42"]);
+    /// assert_eq!(round_trip[1].dtype(), Some(DType::I64));
+    /// # }
+    /// ```
+    pub fn to_record_batch(&self) -> Result<RecordBatch, DataError> {
+        let mut fields = Vec::with_capacity(self.cols());
+        let mut arrays = Vec::with_capacity(self.cols());
+
+        for (idx, column) in self.data_cols.iter().enumerate() {
+            let array = arrow_array(column, idx)?;
+            let name = column.name.clone().unwrap_or_else(|| idx.to_string());
+
+            fields.push(Field::new(name, array.data_type().clone(), true));
+            arrays.push(array);
+        }
+
+        // The row count is given so that a table without columns converts.
+        let options = RecordBatchOptions::new().with_row_count(Some(self.rows()));
+        RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), arrays, &options)
+            .map_err(|_| DataError::InvalidStateError)
+    }
+
+    /// Constructs a table from an Arrow `RecordBatch`.
+    ///
+    /// Each column takes the name of its field. `Utf8` values are copied as
+    /// they are, and values of other types are formatted as Arrow displays
+    /// them. Nulls become missing values. `Boolean`, `Int64` and `Float64`
+    /// columns have their type recorded, see `DataColumn::dtype`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A column has a type which Arrow cannot display.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<DataTable, DataError> {
+        let schema = batch.schema();
+        let options = FormatOptions::default();
+        let mut table = DataTable::empty();
+
+        for (field, array) in schema.fields().iter().zip(batch.columns()) {
+            let mut column = DataColumn::empty();
+            column.name = Some(field.name().clone());
+
+            if let Some(strings) = array.as_any().downcast_ref::<StringArray>() {
+                for val in strings.iter() {
+                    match val {
+                        Some(val) => column.push(val),
+                        None => column.push_missing(""),
+                    }
+                }
+            } else {
+                let formatter = ArrayFormatter::try_new(array.as_ref(), &options)
+                    .map_err(|_| DataError::InvalidStateError)?;
+
+                for row in 0..array.len() {
+                    if array.is_null(row) {
+                        column.push_missing("");
+                    } else {
+                        column.push(formatter.value(row).to_string());
+                    }
+                }
+            }
+
+            match *field.data_type() {
+                DataType::Boolean => column.set_dtype(DType::Bool),
+                DataType::Int64 => column.set_dtype(DType::I64),
+                DataType::Float64 => column.set_dtype(DType::F64),
+                _ => {}
+            }
+            table.data_cols.push(column);
+        }
+
+        Ok(table)
+    }
+}
+
+/// Converts the column at index `col` to an Arrow array of its recorded type.
+#[cfg(feature = "arrow")]
+fn arrow_array(column: &DataColumn, col: usize) -> Result<ArrayRef, DataError> {
+    let array: ArrayRef = match column.dtype() {
+        Some(DType::Bool) => Arc::new(BooleanArray::from(present_values::<bool>(column, col)?)),
+        Some(DType::I64) => Arc::new(Int64Array::from(present_values::<i64>(column, col)?)),
+        Some(DType::F64) => Arc::new(Float64Array::from(present_values::<f64>(column, col)?)),
+        Some(DType::String) | None => {
+            let values = (0..column.len()).map(|row| {
+                if column.is_missing(row) { None } else { Some(&column[row]) }
+            });
+            Arc::new(values.collect::<StringArray>())
+        }
+    };
+
+    Ok(array)
+}
+
+/// Parses the present values of the column, giving `None` for missing values.
+#[cfg(feature = "arrow")]
+fn present_values<T>(column: &DataColumn, col: usize) -> Result<Vec<Option<T>>, DataError>
+    where T: FromStr,
+          T::Err: Error + Send + Sync + 'static
+{
+    (0..column.len())
+        .map(|row| {
+            if column.is_missing(row) {
+                return Ok(None);
+            }
+
+            cast_value(&column[row], || {
+                    CellLocation {
+                        row: Some(row),
+                        column: Some(col),
+                        column_name: column.name.clone(),
+                    }
+                })
+                .map(Some)
+        })
+        .collect()
+}
+
+#[cfg(feature = "parquet")]
+impl DataTable {
+    /// Writes the table to a Parquet file.
+    ///
+    /// The columns are those given by `to_record_batch`, so columns with a
+    /// recorded type keep it. The file is written as a single row group
+    /// compressed with Snappy.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    /// - Fails in the same way as `to_record_batch`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    /// use rusty_data::schema::DType;
+    ///
+    /// let options = LoaderOptions {
+    ///     has_header: true,
+    ///     na_tokens: vec!["NA".to_string()],
+    ///     ..LoaderOptions::default()
+    /// };
+    /// let loader = Loader::from_str("name,age\nann,31\nbob,NA\n", options);
+    /// let mut table = loader.load_file().unwrap();
+    /// table.convert_columns(&[("age", DType::I64)]).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("rusty_data_doc_parquet.parquet");
+    /// table.save_parquet(&path).unwrap();
+    ///
+    /// let loaded = DataTable::load_parquet(&path).unwrap();
+    /// assert_eq!(loaded.headers(), vec!["name", "age"]);
+    /// assert_eq!(loaded[1].dtype(), Some(DType::I64));
+    /// assert!(loaded[1].is_missing(1));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save_parquet<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        let batch = self.to_record_batch()?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+
+        let file = File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))
+            .map_err(parquet_error)?;
+        writer.write(&batch).map_err(parquet_error)?;
+        writer.close().map_err(parquet_error)?;
+
+        Ok(())
+    }
+
+    /// Reads a table from a Parquet file.
+    ///
+    /// Every row group is read and the columns are converted as in
+    /// `from_record_batch`. Files compressed with Snappy, or not
+    /// compressed, can be read.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read, or is not a Parquet file which can be read.
+    /// - InvalidStateError : A column has a type which Arrow cannot display.
+    pub fn load_parquet<P: AsRef<Path>>(path: P) -> Result<DataTable, DataError> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+            .and_then(|builder| builder.build())
+            .map_err(parquet_error)?;
+        let schema = reader.schema();
+
+        let batches = reader.collect::<Result<Vec<RecordBatch>, _>>()
+            .map_err(|e| DataError::Io(io::Error::other(e)))?;
+        let batch = concat_batches(&schema, &batches)
+            .map_err(|e| DataError::Io(io::Error::other(e)))?;
+
+        DataTable::from_record_batch(&batch)
+    }
+}
+
+/// Wraps an error from the parquet crate.
+#[cfg(feature = "parquet")]
+fn parquet_error(err: ParquetError) -> DataError {
+    DataError::Io(io::Error::other(err))
+}
+
+impl DataTable {
+    /// Constructs a table from a stream of records.
+    ///
+    /// A record is anything which iterates over string values, such as a
+    /// `Vec<String>` or a `&csv::StringRecord`. This allows a table to be
+    /// built on top of another parser. See `RecordSink` to push records
+    /// one at a time instead.
+    ///
+    /// # Failures
+    ///
+    /// - Malformed : A record has a different number of values than the first.
+    ///   The `line` of the error is the position of the record in the stream,
+    ///   counting the headers if present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "2"], vec!["3", "4"]];
+    /// let table = DataTable::from_records(Some(vec!["x", "y"]), records).unwrap();
+    ///
+    /// assert_eq!(table.headers(), vec!["x", "y"]);
+    /// assert_eq!(&table[1][1], "4");
+    ///
+    /// // Rows can be passed back out as records, for example to `csv::Writer::write_record`.
+    /// let rows: Vec<Vec<&str>> = table.iter_rows().map(|row| row.into_iter().collect()).collect();
+    /// assert_eq!(rows, vec![vec!["1", "2"], vec!["3", "4"]]);
+    /// ```
+    pub fn from_records<H, I, R>(headers: Option<H>, records: I) -> Result<DataTable, DataError>
+        where H: IntoIterator,
+              H::Item: AsRef<str>,
+              I: IntoIterator<Item = R>,
+              R: IntoIterator,
+              R::Item: AsRef<str>
+    {
+        let mut sink = match headers {
+            Some(headers) => RecordSink::with_headers(headers),
+            None => RecordSink::new(),
+        };
+
+        for record in records {
+            sink.push_record(record)?;
+        }
+
+        Ok(sink.finish())
+    }
+
+    /// The names of the columns, using an empty name for unnamed columns.
+    ///
+    /// Suitable for writing as the header record of another format.
+    pub fn headers(&self) -> Vec<&str> {
+        self.data_cols
+            .iter()
+            .map(|c| c.name.as_ref().map(|s| &s[..]).unwrap_or(""))
+            .collect()
+    }
+}
+
+/// Builds a DataTable from records pushed one at a time.
+///
+/// Useful when records come from a fallible reader, such as the
+/// `csv` crate's `Reader::records`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::interop::RecordSink;
+///
+/// let mut sink = RecordSink::with_headers(vec!["a", "b"]);
+/// sink.push_record(vec!["1", "2"]).unwrap();
+///
+/// // Records must match the number of columns.
+/// assert!(sink.push_record(vec!["3"]).is_err());
+///
+/// let table = sink.finish();
+/// assert_eq!(table.rows(), 1);
+/// ```
+pub struct RecordSink {
+    table: DataTable,
+    position: usize,
+}
+
+impl RecordSink {
+    /// Constructs a sink whose columns are created from the first record.
+    pub fn new() -> RecordSink {
+        RecordSink {
+            table: DataTable::empty(),
+            position: 0,
+        }
+    }
+
+    /// Constructs a sink with a named column for each header.
+    pub fn with_headers<H>(headers: H) -> RecordSink
+        where H: IntoIterator,
+              H::Item: AsRef<str>
+    {
+        let mut table = DataTable::empty();
+
+        for name in headers {
+            let mut column = DataColumn::empty();
+            column.name = Some(name.as_ref().to_string());
+            table.data_cols.push(column);
+        }
+
+        RecordSink { table, position: 1 }
+    }
+
+    /// Appends a record to the table.
+    ///
+    /// The record is not added if it has the wrong number of values.
+    ///
+    /// # Failures
+    ///
+    /// - Malformed : The record has a different number of values than the table has columns.
+    pub fn push_record<R>(&mut self, record: R) -> Result<(), DataError>
+        where R: IntoIterator,
+              R::Item: AsRef<str>
+    {
+        self.position += 1;
+
+        let fields: Vec<R::Item> = record.into_iter().collect();
+
+        if self.table.data_cols.is_empty() {
+            for _ in 0..fields.len() {
+                self.table.data_cols.push(DataColumn::empty());
+            }
+        }
+
+        let expected = self.table.cols();
+        let found = fields.len();
+
+        if found != expected {
+            let (kind, column) = if found > expected {
+                (LoaderErrorKind::TooManyFields { expected, found }, expected.saturating_sub(1))
+            } else {
+                (LoaderErrorKind::TooFewFields { expected, found }, found)
+            };
+
+            return Err(DataError::Malformed(LoaderError {
+                kind,
+                line: self.position,
+                column,
+                column_name: self.table.data_cols.get(column).and_then(|c| c.name.clone()),
+                text: fields.iter().map(|f| f.as_ref()).collect::<Vec<_>>().join(","),
+            }));
+        }
+
+        for (column, field) in self.table.data_cols.iter_mut().zip(fields.iter()) {
+            column.push(field.as_ref());
+        }
+
+        Ok(())
+    }
+
+    /// Consumes the sink and returns the table.
+    pub fn finish(self) -> DataTable {
+        self.table
+    }
+}
+
+impl Default for RecordSink {
+    fn default() -> RecordSink {
+        RecordSink::new()
+    }
+}
+
+#[cfg(feature = "csv")]
+impl DataTable {
+    /// Reads every record from a `csv::Reader` into a table.
+    ///
+    /// The reader's headers name the columns if it has headers. Parsing is
+    /// left to the csv crate, so its quoting and escaping options apply.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The reader failed, for example on invalid UTF-8 or records of
+    ///   different lengths. The error wraps the `csv::Error`.
+    /// - Malformed : A record has a different number of values than the headers.
+    ///   Only reported when the reader is `flexible`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate csv;
+    /// extern crate rusty_data;
+    ///
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// # fn main() {
+    /// let mut reader = csv::Reader::from_reader(&b"x,y\n1,\"a,b\"\n"[..]);
+    /// let table = DataTable::from_csv_reader(&mut reader).unwrap();
+    ///
+    /// assert_eq!(table.headers(), vec!["x", "y"]);
+    /// assert_eq!(&table[1][0], "a,b");
+    ///
+    /// let mut writer = csv::Writer::from_writer(Vec::new());
+    /// table.write_csv_records(&mut writer, true).unwrap();
+    /// assert_eq!(writer.into_inner().unwrap(), b"x,y\n1,\"a,b\"\n".to_vec());
+    /// # }
+    /// ```
+    pub fn from_csv_reader<R>(reader: &mut csv::Reader<R>) -> Result<DataTable, DataError>
+        where R: io::Read
+    {
+        let mut sink = if reader.has_headers() {
+            RecordSink::with_headers(reader.headers().map_err(csv_error)?)
+        } else {
+            RecordSink::new()
+        };
+
+        for record in reader.records() {
+            sink.push_record(&record.map_err(csv_error)?)?;
+        }
+
+        Ok(sink.finish())
+    }
+
+    /// Returns an iterator over each row of the table as a `csv::StringRecord`.
+    pub fn string_records(&self) -> impl Iterator<Item = csv::StringRecord> + '_ {
+        self.iter_rows().map(|row| row.into_iter().collect())
+    }
+
+    /// Writes every row of the table to a `csv::Writer`.
+    ///
+    /// The names of the columns are written first if `has_header` is true,
+    /// using an empty name for unnamed columns.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The writer failed. The error wraps the `csv::Error`.
+    pub fn write_csv_records<W: io::Write>(&self,
+                                          writer: &mut csv::Writer<W>,
+                                          has_header: bool)
+                                          -> Result<(), DataError> {
+        if has_header {
+            writer.write_record(self.headers()).map_err(csv_error)?;
+        }
+
+        for row in self.iter_rows() {
+            writer.write_record(row).map_err(csv_error)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Wraps an error from the csv crate.
+#[cfg(feature = "csv")]
+fn csv_error(err: csv::Error) -> DataError {
+    DataError::Io(io::Error::from(err))
+}
+
+impl DataTable {
+    /// Converts each row into a map from column name to value.
+    ///
+    /// This is the shape of a list of dicts in Python or an array of
+    /// objects in JSON. Unnamed columns are keyed by their index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let table = DataTable::from_records(Some(vec!["x", "y"]), vec![vec!["1", "2"]]).unwrap();
+    /// let maps = table.to_row_maps();
+    ///
+    /// assert_eq!(maps[0]["y"], "2");
+    ///
+    /// let round_trip = DataTable::from_row_maps(&["x", "y"], &maps).unwrap();
+    /// assert_eq!(round_trip[0].data(), table[0].data());
+    /// ```
+    pub fn to_row_maps(&self) -> Vec<HashMap<String, String>> {
+        let names: Vec<String> = self.data_cols
+            .iter()
+            .enumerate()
+            .map(|(i, c)| c.name.clone().unwrap_or_else(|| i.to_string()))
+            .collect();
+
+        self.iter_rows()
+            .map(|row| names.iter().cloned().zip(row.into_iter().map(String::from)).collect())
+            .collect()
+    }
+
+    /// Constructs a table with the given columns from maps of column name to value.
+    ///
+    /// Keys which are not among `columns` are ignored.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A map has no value for one of the columns.
+    pub fn from_row_maps<S: AsRef<str>>(columns: &[S],
+                                        rows: &[HashMap<String, String>])
+                                        -> Result<DataTable, DataError> {
+        let mut sink = RecordSink::with_headers(columns);
+
+        for row in rows {
+            let mut record = Vec::with_capacity(columns.len());
+
+            for name in columns {
+                match row.get(name.as_ref()) {
+                    Some(val) => record.push(&val[..]),
+                    None => return Err(DataError::InvalidStateError),
+                }
+            }
+
+            sink.push_record(record)?;
+        }
+
+        Ok(sink.finish())
+    }
+}
+
+impl<'a> Row<'a> {
+    /// Returns an iterator over the values in the row.
+    pub fn iter(&self) -> RowValues<'a> {
+        RowValues {
+            row: *self,
+            col: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for Row<'a> {
+    type Item = &'a str;
+    type IntoIter = RowValues<'a>;
+
+    fn into_iter(self) -> RowValues<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the values in a Row.
+pub struct RowValues<'a> {
+    row: Row<'a>,
+    col: usize,
+}
+
+impl<'a> Iterator for RowValues<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let val = self.row.get(self.col);
+        if val.is_some() {
+            self.col += 1;
+        }
+        val
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.row.len() - self.col;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Collects records of named fields into columns, in the order the names
+/// are first seen, marking fields a record lacks as missing.
+#[derive(Default)]
+pub(crate) struct FieldRecords {
+    columns: Vec<DataColumn>,
+    index: HashMap<String, usize>,
+    rows: usize,
+}
+
+impl FieldRecords {
+    pub(crate) fn push(&mut self, fields: Vec<(String, String)>) {
+        let mut values: Vec<Option<String>> = vec![None; self.columns.len()];
+
+        for (name, value) in fields {
+            let rows = self.rows;
+            let columns = &mut self.columns;
+            let col = *self.index.entry(name.clone()).or_insert_with(|| {
+                let mut column = DataColumn::empty();
+                column.name = Some(name);
+                for _ in 0..rows {
+                    column.push_missing("");
+                }
+                columns.push(column);
+                columns.len() - 1
+            });
+
+            if col >= values.len() {
+                values.resize(col + 1, None);
+            }
+            values[col] = Some(value);
+        }
+
+        for (column, value) in self.columns.iter_mut().zip(values) {
+            match value {
+                Some(value) => column.push(value),
+                None => column.push_missing(""),
+            }
+        }
+        self.rows += 1;
+    }
+
+    pub(crate) fn finish(self) -> DataTable {
+        DataTable { data_cols: self.columns }
+    }
+}
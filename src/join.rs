@@ -0,0 +1,255 @@
+//! The join module.
+//!
+//! Provides joins which combine or filter the rows of one DataTable
+//! using the rows of another.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::time::Instant;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+
+/// Which row of the other table `DataTable::asof_join` matches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AsofDirection {
+    /// The row with the largest key which is not after the key.
+    #[default]
+    Backward,
+    /// The row with the smallest key which is not before the key.
+    Forward,
+    /// The row with the closest key, preferring the earlier row on a tie.
+    Nearest,
+}
+
+impl DataTable {
+    /// Keeps the rows which have a matching row in `other`.
+    ///
+    /// Rows match when their values in the `on` columns are equal. Each
+    /// row is kept at most once however many rows it matches, and rows
+    /// with a missing key never match.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A key column is missing from either table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "tea"], vec!["2", "jam"], vec!["3", "pie"]];
+    /// let orders = DataTable::from_records(Some(vec!["customer", "item"]), records).unwrap();
+    ///
+    /// let records = vec![vec!["2"], vec!["2"]];
+    /// let banned = DataTable::from_records(Some(vec!["customer"]), records).unwrap();
+    ///
+    /// let flagged = orders.semi_join(&banned, &["customer"]).unwrap();
+    /// assert_eq!(flagged[1].data(), vec!["jam"]);
+    ///
+    /// let allowed = orders.anti_join(&banned, &["customer"]).unwrap();
+    /// assert_eq!(allowed[1].data(), vec!["tea", "pie"]);
+    /// ```
+    pub fn semi_join(&self, other: &DataTable, on: &[&str]) -> Result<DataTable, DataError> {
+        self.filter_join(other, on, true)
+    }
+
+    /// Keeps the rows which have no matching row in `other`.
+    ///
+    /// Rows match as in `semi_join`, so rows with a missing key are kept.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A key column is missing from either table.
+    pub fn anti_join(&self, other: &DataTable, on: &[&str]) -> Result<DataTable, DataError> {
+        self.filter_join(other, on, false)
+    }
+
+    /// Pairs every row of this table with every row of `other`.
+    ///
+    /// The result holds the columns of this table followed by the columns
+    /// of `other`, with their names unchanged, and has one row for each
+    /// pair of rows in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let rates = vec![vec!["0.1"], vec!["0.01"]];
+    /// let rates = DataTable::from_records(Some(vec!["rate"]), rates).unwrap();
+    /// let depths = vec![vec!["2"], vec!["4"]];
+    /// let depths = DataTable::from_records(Some(vec!["depth"]), depths).unwrap();
+    ///
+    /// let grid = rates.cross_join(&depths);
+    ///
+    /// assert_eq!(grid.rows(), 4);
+    /// assert_eq!(grid[0].data(), vec!["0.1", "0.1", "0.01", "0.01"]);
+    /// assert_eq!(grid[1].data(), vec!["2", "4", "2", "4"]);
+    /// ```
+    pub fn cross_join(&self, other: &DataTable) -> DataTable {
+        let start = Instant::now();
+        let (rows, other_rows) = (self.rows(), other.rows());
+
+        let left: Vec<usize> = (0..rows).flat_map(|row| vec![row; other_rows]).collect();
+        let right: Vec<usize> = (0..rows).flat_map(|_| 0..other_rows).collect();
+
+        let mut table = self.take_rows(&left);
+        table.data_cols.extend(other.take_rows(&right).data_cols);
+
+        log_info!("cross joined {} rows with {} rows in {:?}", rows, other_rows, start.elapsed());
+        table
+    }
+
+    /// Matches each row to the row of `other` whose `on` value is closest in `direction`.
+    ///
+    /// The `on` values of both tables are parsed as floats, such as Unix
+    /// timestamps, and `other` does not need to be sorted. The result holds
+    /// every row of this table in order, followed by the columns of `other`
+    /// without its `on` column. Rows with no match, or with a missing key,
+    /// get missing values in those columns.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The `on` column is missing from either table.
+    /// - DataCastError : A present `on` value could not be parsed as a float.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::join::AsofDirection;
+    ///
+    /// let readings = vec![vec!["1", "20.5"], vec!["5", "21.0"], vec!["12", "22.3"]];
+    /// let readings = DataTable::from_records(Some(vec!["time", "temp"]), readings).unwrap();
+    ///
+    /// let settings = vec![vec!["10", "high"], vec!["0", "low"]];
+    /// let settings = DataTable::from_records(Some(vec!["time", "fan"]), settings).unwrap();
+    ///
+    /// let aligned = readings.asof_join(&settings, "time", AsofDirection::Backward).unwrap();
+    /// assert_eq!(aligned[2].data(), vec!["low", "low", "high"]);
+    ///
+    /// let ahead = readings.asof_join(&settings, "time", AsofDirection::Forward).unwrap();
+    /// assert_eq!(ahead[2].data(), vec!["high", "high", ""]);
+    /// assert!(ahead[2].is_missing(2));
+    /// ```
+    pub fn asof_join(&self,
+                     other: &DataTable,
+                     on: &str,
+                     direction: AsofDirection)
+                     -> Result<DataTable, DataError> {
+        let start = Instant::now();
+        let left = self.column(on).ok_or(DataError::InvalidStateError)?;
+        let right_on = other.column_index(on).ok_or(DataError::InvalidStateError)?;
+        let right = &other.data_cols[right_on];
+
+        let mut keys = Vec::with_capacity(right.len());
+        for row in 0..right.len() {
+            if !right.is_missing(row) {
+                keys.push((right.get_as::<f64>(row)?, row));
+            }
+        }
+        keys.retain(|k| !k.0.is_nan());
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        let mut matches = Vec::with_capacity(left.len());
+        for row in 0..left.len() {
+            if left.is_missing(row) {
+                matches.push(None);
+                continue;
+            }
+
+            let key: f64 = left.get_as(row)?;
+            matches.push(if key.is_nan() { None } else { asof_match(&keys, key, direction) });
+        }
+
+        let mut table = self.take_rows(&(0..self.rows()).collect::<Vec<_>>());
+
+        for (col, from) in other.data_cols.iter().enumerate() {
+            if col == right_on {
+                continue;
+            }
+
+            let mut column = from.empty_like();
+            for m in matches.iter() {
+                match *m {
+                    Some(row) => column.push_from(from, row),
+                    None => column.push_missing(""),
+                }
+            }
+            table.data_cols.push(column);
+        }
+
+        log_info!("asof joined {} rows on {:?} with {} rows in {:?}",
+                  self.rows(),
+                  on,
+                  other.rows(),
+                  start.elapsed());
+        Ok(table)
+    }
+
+    /// Keeps the rows whose key is found in `other` when `matching` is true,
+    /// otherwise the rows whose key is not found.
+    fn filter_join(&self,
+                   other: &DataTable,
+                   on: &[&str],
+                   matching: bool)
+                   -> Result<DataTable, DataError> {
+        let start = Instant::now();
+        let left = key_columns(self, on)?;
+        let right = key_columns(other, on)?;
+
+        let keys: HashSet<Vec<&str>> = (0..other.rows())
+            .filter_map(|row| row_key(&right, row))
+            .collect();
+
+        let rows: Vec<usize> = (0..self.rows())
+            .filter(|&row| {
+                let found = row_key(&left, row).is_some_and(|key| keys.contains(&key));
+                found == matching
+            })
+            .collect();
+
+        log_info!("kept {} of {} rows joining on {:?} in {:?}",
+                  rows.len(),
+                  self.rows(),
+                  on,
+                  start.elapsed());
+        Ok(self.take_rows(&rows))
+    }
+}
+
+/// Finds the row matching `key` among keys sorted in ascending order.
+fn asof_match(keys: &[(f64, usize)], key: f64, direction: AsofDirection) -> Option<usize> {
+    // The last key which is not after `key`, taking the last of equal keys.
+    let not_after = keys.partition_point(|k| k.0 <= key);
+    let backward = not_after.checked_sub(1).map(|i| keys[i]);
+
+    // The first key which is not before `key`, taking the first of equal keys.
+    let forward = keys.get(keys.partition_point(|k| k.0 < key)).cloned();
+
+    match direction {
+        AsofDirection::Backward => backward.map(|k| k.1),
+        AsofDirection::Forward => forward.map(|k| k.1),
+        AsofDirection::Nearest => {
+            match (backward, forward) {
+                (Some(b), Some(f)) => Some(if f.0 - key < key - b.0 { f.1 } else { b.1 }),
+                (b, f) => b.or(f).map(|k| k.1),
+            }
+        }
+    }
+}
+
+/// Looks up the named key columns of the table.
+fn key_columns<'a>(table: &'a DataTable, on: &[&str]) -> Result<Vec<&'a DataColumn>, DataError> {
+    on.iter()
+        .map(|name| table.column(name).ok_or(DataError::InvalidStateError))
+        .collect()
+}
+
+/// The key of the row, or `None` if any part of it is missing.
+fn row_key<'a>(columns: &[&'a DataColumn], row: usize) -> Option<Vec<&'a str>> {
+    columns.iter()
+        .map(|c| if c.is_missing(row) { None } else { c.get(row) })
+        .collect()
+}
@@ -0,0 +1,351 @@
+//! The lazy module.
+//!
+//! Provides the LazyTable which reads each column from its file
+//! only when the column is first used.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufReader, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str;
+
+use datatable::{DataColumn, DataTable};
+use error::{DataError, Warnings};
+use loader::{field_missing, fit_fields, header_name, line_splitter, split_line, split_record,
+             warn_padded, ColumnRules, LoaderOptions};
+
+/// A table whose columns are read from a file on demand.
+///
+/// Opening the table reads the file once to find where each field of
+/// each row starts. A column is then read from the bytes of its own fields
+/// the first time it is accessed and kept for later use, so wide files
+/// open quickly and only the columns used are parsed and stored.
+///
+/// Values are split and marked as missing using the loader options.
+/// Records split over several lines, and footers, are not supported.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::lazy::LazyTable;
+/// use rusty_data::loader::LoaderOptions;
+///
+/// let path = std::env::temp_dir().join("rusty-data-lazy-doctest.csv");
+/// std::fs::write(&path, "id,name,score\n1,ann,71\n2,bob,93\n").unwrap();
+///
+/// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+/// let mut table = LazyTable::open(&path, options).unwrap();
+///
+/// assert_eq!(table.rows(), 2);
+/// assert_eq!(table.column("score").unwrap().data(), vec!["71", "93"]);
+/// assert_eq!(table.loaded(), 1);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct LazyTable {
+    path: PathBuf,
+    options: LoaderOptions,
+    names: Vec<Option<String>>,
+    rules: Vec<ColumnRules>,
+    // The start of each row followed by the end of the last.
+    offsets: Vec<u64>,
+    // The index within `fields` of the first field of each row, followed by the end of the last.
+    row_fields: Vec<usize>,
+    // The start of each field relative to the start of its row, and its length.
+    fields: Vec<(u32, u32)>,
+    // The line number of the first row.
+    first_line: usize,
+    columns: Vec<Option<DataColumn>>,
+    warnings: Warnings,
+}
+
+impl LazyTable {
+    /// Opens the file and indexes the bytes of each field.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read or was not valid UTF-8.
+    /// - Parse : A line contained an unterminated quote.
+    /// - InvalidStateError : The options ask for `multiline_quotes` or `skip_footer`,
+    ///   or a line is longer than 4 GiB.
+    pub fn open<P: AsRef<Path>>(path: P,
+                                mut options: LoaderOptions)
+                                -> Result<LazyTable, DataError> {
+        if options.multiline_quotes || options.skip_footer > 0 {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut line = String::new();
+        let mut position = 0u64;
+        let mut offsets = Vec::new();
+        let mut row_fields = Vec::new();
+        let mut fields = Vec::new();
+        let mut warnings = Warnings::new();
+
+        let mut headers: Vec<Option<String>> = Vec::new();
+        let mut first_line = 1;
+
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            let text = trim_line_end(&line);
+            let line_number = first_line + offsets.len();
+
+            // The first line gives the columns, and is skipped if it is a header.
+            if position == 0 {
+                let (values, unnamed) = split_record(&options, text, 1)?;
+
+                if options.has_header {
+                    headers = values.into_iter()
+                        .zip(unnamed)
+                        .map(|(f, unnamed)| Some(f.into_owned()).filter(|_| !unnamed))
+                        .collect();
+                    position += read as u64;
+                    first_line = 2;
+                    continue;
+                }
+                headers = vec![None; values.len()];
+            }
+
+            if read > u32::MAX as usize {
+                return Err(DataError::InvalidStateError);
+            }
+
+            offsets.push(position);
+            row_fields.push(fields.len());
+
+            let mut splitter = split_line(&options, text, line_number)?;
+            while let Some(raw) = splitter.next_raw() {
+                let start = raw.as_ptr() as usize - text.as_ptr() as usize;
+                fields.push((start as u32, raw.len() as u32));
+            }
+
+            let found = fields.len() - row_fields[row_fields.len() - 1];
+            if found < headers.len() && options.pad_short_rows {
+                warn_padded(&mut warnings, line_number, found, headers.len());
+            }
+
+            position += read as u64;
+        }
+        offsets.push(position);
+        row_fields.push(fields.len());
+
+        let names: Vec<Option<String>> = headers.iter()
+            .map(|h| h.as_ref().map(|h| header_name(&options, Cow::Borrowed(h))))
+            .collect();
+        let headers: Vec<Option<&str>> = headers.iter().map(|h| h.as_deref()).collect();
+        let rules = ColumnRules::for_columns(&mut options, &headers)?;
+        let columns = names.iter().map(|_| None).collect();
+
+        Ok(LazyTable {
+            path,
+            options,
+            names,
+            rules,
+            offsets,
+            row_fields,
+            fields,
+            first_line,
+            columns,
+            warnings,
+        })
+    }
+
+    /// The number of columns in the table.
+    pub fn cols(&self) -> usize {
+        self.names.len()
+    }
+
+    /// The number of rows in the table.
+    pub fn rows(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// The names of the columns, if the file has a header.
+    pub fn names(&self) -> &[Option<String>] {
+        &self.names
+    }
+
+    /// The warnings produced so far.
+    ///
+    /// Padded lines are recorded when the table is opened. Values matching
+    /// the missing value markers are recorded for each column once it is read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::lazy::LazyTable;
+    /// use rusty_data::loader::LoaderOptions;
+    ///
+    /// let path = std::env::temp_dir().join("rusty-data-lazy-warnings-doctest.csv");
+    /// std::fs::write(&path, "x,y\n1,NA\n2\n").unwrap();
+    ///
+    /// let options = LoaderOptions {
+    ///     has_header: true,
+    ///     pad_short_rows: true,
+    ///     na_tokens: vec!["NA".to_string()],
+    ///     ..LoaderOptions::default()
+    /// };
+    /// let mut table = LazyTable::open(&path, options).unwrap();
+    /// assert_eq!(table.warnings().warnings()[0].to_string(),
+    ///            "line 3: column 1: padded from 1 to 2 fields");
+    ///
+    /// table.column("y").unwrap();
+    /// assert_eq!(table.warnings().warnings()[1].to_string(),
+    ///            "column 1: 1 values matched the missing value markers");
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// The number of columns which have been read from the file.
+    pub fn loaded(&self) -> usize {
+        self.columns.iter().filter(|c| c.is_some()).count()
+    }
+
+    /// Gets the column with the given name, reading it if needed.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    /// - Fails in the same way as `column_at`.
+    pub fn column(&mut self, name: &str) -> Result<&DataColumn, DataError> {
+        let idx = self.names
+            .iter()
+            .position(|n| n.as_ref().is_some_and(|n| self.options.header_match.matches(n, name)))
+            .ok_or(DataError::InvalidStateError)?;
+
+        self.column_at(idx)
+    }
+
+    /// Gets the column at the index, reading it if needed.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The index is out of range.
+    /// - Io : The file could not be read, or has changed since it was opened.
+    /// - Malformed : A line did not have one field per column.
+    pub fn column_at(&mut self, idx: usize) -> Result<&DataColumn, DataError> {
+        if idx >= self.cols() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        if self.columns[idx].is_none() {
+            let column = self.read_column(idx)?;
+            self.rules[idx].warn_na_matches(idx, &mut self.warnings);
+            self.columns[idx] = Some(column);
+        }
+
+        Ok(self.columns[idx].as_ref().expect("column was just read"))
+    }
+
+    /// Reads every column not yet read and returns the full table.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `column_at`.
+    pub fn into_table(mut self) -> Result<DataTable, DataError> {
+        for idx in 0..self.cols() {
+            self.column_at(idx)?;
+        }
+
+        Ok(DataTable { data_cols: self.columns.into_iter().flatten().collect() })
+    }
+
+    /// Reads the column at the index from the bytes of its fields.
+    fn read_column(&self, idx: usize) -> Result<DataColumn, DataError> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut position = 0u64;
+
+        let mut column = if self.options.arena_storage {
+            DataColumn::empty_arena()
+        } else {
+            DataColumn::empty()
+        };
+        column.name = self.names[idx].clone();
+
+        // Only used for the options it splits with.
+        let splitter = line_splitter(&self.options, "");
+        let mut bytes = Vec::new();
+
+        for row in 0..self.rows() {
+            let first = self.row_fields[row];
+            let found = self.row_fields[row + 1] - first;
+
+            if found > self.cols() || (found < self.cols() && !self.options.pad_short_rows) {
+                return Err(self.line_error(&mut reader, row));
+            }
+
+            // A padded field is empty, and is not read as missing.
+            let (mut val, missing) = if idx < found {
+                let (start, len) = self.fields[first + idx];
+                let target = self.offsets[row] + start as u64;
+
+                reader.seek_relative(target as i64 - position as i64)?;
+                bytes.resize(len as usize, 0);
+                reader.read_exact(&mut bytes)?;
+                position = target + len as u64;
+
+                let text = str::from_utf8(&bytes).map_err(invalid_data)?;
+                let (val, quoted) = splitter.parse_field(text);
+                let missing = field_missing(&self.options, &val, quoted);
+                (Cow::Owned(val.into_owned()), missing)
+            } else {
+                (Cow::Borrowed(""), false)
+            };
+
+            self.rules[idx].parse(&mut val, missing)?;
+            self.rules[idx].push(&mut column, val, missing);
+        }
+
+        column.shrink_to_fit();
+        Ok(column)
+    }
+
+    /// Reads the whole of a row which does not have one field per column,
+    /// and returns the error describing it.
+    fn line_error(&self, reader: &mut BufReader<File>, row: usize) -> DataError {
+        let result = reader.seek(SeekFrom::Start(self.offsets[row]))
+            .map_err(DataError::from)
+            .and_then(|_| {
+                let mut bytes = vec![0; (self.offsets[row + 1] - self.offsets[row]) as usize];
+                reader.read_exact(&mut bytes)?;
+
+                let line = str::from_utf8(&bytes).map_err(invalid_data)?;
+                let line = trim_line_end(line);
+                let line_number = self.first_line + row;
+
+                let (mut values, _) = split_record(&self.options, line, line_number)?;
+                fit_fields(&self.options,
+                           &mut values,
+                           self.cols(),
+                           |i| self.names[i].clone(),
+                           line,
+                           line_number,
+                           &mut Warnings::new())
+            });
+
+        // The line did not fit when it was indexed, so if it fits now the file has changed.
+        result.err().unwrap_or_else(|| invalid_data("the file has changed since it was opened"))
+    }
+}
+
+/// An error for bytes which could not be read as expected.
+fn invalid_data<E>(err: E) -> DataError
+    where E: Into<Box<dyn Error + Send + Sync>>
+{
+    DataError::Io(io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// The line without its line ending.
+fn trim_line_end(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
@@ -8,9 +8,93 @@
 //!
 //! In addition to the DataTable there is a Loader which is used to
 //! read in data from file to tables.
+//!
+//! With the `log` feature the loader and the heavy table operations,
+//! such as sorts, groups and joins, report what they did and how long
+//! it took through the `log` crate.
 
 extern crate num;
+#[cfg(feature = "arrow")]
+extern crate arrow;
+extern crate crc32fast;
+#[cfg(feature = "csv")]
+extern crate csv;
+#[cfg(any(feature = "avro", feature = "gzip"))]
+extern crate flate2;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "mysql")]
+extern crate mysql_client;
+#[cfg(feature = "parquet")]
+extern crate parquet;
+#[cfg(feature = "plot")]
+extern crate plotters;
+#[cfg(feature = "postgres")]
+extern crate postgres_client;
+#[cfg(feature = "python")]
+extern crate pyo3;
+// The PyO3 macros refer to `::core`, which this edition resolves from the crate root.
+#[cfg(feature = "python")]
+extern crate core;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "avro")]
+extern crate snap;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "zstd")]
+extern crate zstd;
+
+// Declared first so that its macros are visible to the modules below.
+#[macro_use]
+mod logging;
 
 pub mod loader;
 pub mod datatable;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod storage;
+pub mod disk;
+pub mod parallel;
+pub mod interop;
+pub mod parse;
+pub mod schema;
+pub mod stats;
+pub mod online;
+pub mod profile;
+pub mod category;
+pub mod transform;
+pub mod filter;
+pub mod join;
+pub mod group;
+pub mod sort;
+pub mod compare;
+pub mod fingerprint;
+pub mod snapshot;
+pub mod lazy;
+pub mod history;
+pub mod patch;
+pub mod generate;
+pub mod writer;
+pub mod xml;
+pub mod html;
+pub mod toml;
+pub mod yaml;
+pub mod msgpack;
+pub mod npy;
+pub mod sql;
+#[cfg(feature = "plot")]
+pub mod plot;
+#[cfg(feature = "avro")]
+pub mod avro;
+#[cfg(feature = "mat")]
+pub mod mat;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+#[cfg(feature = "tokio")]
+pub mod async_loader;
+#[cfg(feature = "python")]
+pub mod python;
+
+mod scan;
\ No newline at end of file
@@ -3,12 +3,27 @@
 //! Provides the Loader struct which is used to read data into
 //! DataTables.
 
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
 use std::io;
 use std::io::prelude::*;
-use std::io::{BufReader, Error, ErrorKind};
+use std::io::BufReader;
+use std::mem;
 use std::fs::File;
+use std::str;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use datatable::*;
+use datatable::cast_value;
+use error::{CellLocation, DataError, ErrorReport, LoaderError, LoaderErrorKind, Warnings};
+use scan;
+use schema::{self, ColumnInference, DType};
+use storage::SmallString;
 
 /// Options used to fine tune the file loading
 pub struct LoaderOptions {
@@ -18,37 +33,230 @@ pub struct LoaderOptions {
     pub delimiter: char,
     /// The quote character
     pub quote_marker: Option<char>,
+    /// True if lines with too few fields should be padded with empty values
+    ///
+    /// Each padded line is recorded as a warning.
+    pub pad_short_rows: bool,
+    /// True if each column should store its values in a single `StringArena`
+    pub arena_storage: bool,
+    /// The escape character
+    ///
+    /// The character following an escape is taken literally, so `a\,b`
+    /// is read as the single value `a,b` when the escape is a backslash.
+    pub escape_char: Option<char>,
+    /// True if leading and trailing whitespace should be removed from each value and header
+    ///
+    /// Whitespace within quotes is kept.
+    pub trim_fields: bool,
+    /// How names are matched when records are accessed by column name
+    pub header_match: HeaderMatch,
+    /// True if headers should be passed through `normalize_header` when loaded
+    pub normalize_headers: bool,
+    /// The number of lines at the end of the input to ignore
+    ///
+    /// Used to skip summary or total lines appended by export tools.
+    /// These lines are never parsed so may have any format.
+    pub skip_footer: usize,
+    /// True if a quoted field may contain line breaks
+    ///
+    /// Lines are joined into one record until every quote is closed. Requires
+    /// a `quote_marker`. Line numbers in errors refer to the first line of the record.
+    pub multiline_quotes: bool,
+    /// Values which mark a missing value in every column
+    pub na_tokens: Vec<String>,
+    /// Further missing value markers for the columns with the given names
+    ///
+    /// These are used in addition to `na_tokens`. Names are matched as for
+    /// `column_parsers`, and loading fails if a name matches no column.
+    pub column_na_tokens: HashMap<String, Vec<String>>,
+    /// Parsers applied to the values of the columns with the given names
+    ///
+    /// Each value, other than missing values, is replaced by the result of
+    /// its column's parser as it is loaded. A parser returning an error fails
+    /// the line in the same way as a malformed line.
+    ///
+    /// Names are compared using `header_match` with both the header as it is
+    /// written and its normalized name. A column without a header is named by
+    /// its index, such as `"0"`. Loading fails if a parser names no column.
+    pub column_parsers: HashMap<String, FieldParser>,
+    /// Accumulators updated with the values of the columns with the given names
+    ///
+    /// Each value is passed to its column's accumulators as it is loaded,
+    /// after any parser, so that a summary such as `OnlineStats` is built
+    /// in the same pass over the file as the table. Names are matched as for
+    /// `column_parsers`, and loading fails if a name matches no column.
+    pub column_accumulators: HashMap<String, Vec<SharedAccumulator>>,
+    /// True if values written by `WriterOptions::round_trip` should load back unchanged
+    ///
+    /// Requires a `quote_marker`. A quoted field has only its enclosing quotes
+    /// removed and any doubled quote inside read as one quote. An empty field
+    /// which is not quoted is missing, and an empty header which is not quoted
+    /// gives a column without a name. A line ending with a delimiter ends with
+    /// an empty field.
+    pub round_trip: bool,
+}
+
+/// A parser applied to each value of a column as it is loaded.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::error::DataError;
+/// use rusty_data::loader::{FieldParser, Loader, LoaderOptions};
+///
+/// // Converts minutes to seconds.
+/// let parser: FieldParser = Box::new(|v| {
+///     let minutes: u32 = v.parse().map_err(|_| DataError::InvalidStateError)?;
+///     Ok((minutes * 60).to_string())
+/// });
+///
+/// let mut options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+/// options.column_parsers.insert("duration".to_string(), parser);
+///
+/// let table = Loader::from_str("id,duration\n1,2\n", options).load_file().unwrap();
+/// assert_eq!(&table[1][0], "120");
+/// ```
+pub type FieldParser = Box<dyn Fn(&str) -> Result<String, DataError> + Send + Sync>;
+
+/// A summary which is updated with each value of a column as it is loaded.
+pub trait Accumulator: Send {
+    /// Adds the next value of the column, or `None` if it is missing.
+    fn update(&mut self, value: Option<&str>);
 }
 
+/// An accumulator shared between a loader and the code which reads its summary.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use rusty_data::loader::{Accumulator, Loader, LoaderOptions, SharedAccumulator};
+///
+/// // Counts the values longer than 3 bytes.
+/// struct LongValues(usize);
+///
+/// impl Accumulator for LongValues {
+///     fn update(&mut self, value: Option<&str>) {
+///         if value.is_some_and(|v| v.len() > 3) {
+///             self.0 += 1;
+///         }
+///     }
+/// }
+///
+/// let long = Arc::new(Mutex::new(LongValues(0)));
+/// let mut options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+/// options.column_accumulators.insert("name".to_string(), vec![long.clone() as SharedAccumulator]);
+///
+/// Loader::from_str("id,name\n1,ada\n2,grace\n", options).load_file().unwrap();
+/// assert_eq!(long.lock().unwrap().0, 1);
+/// ```
+pub type SharedAccumulator = Arc<Mutex<dyn Accumulator>>;
+
 impl Default for LoaderOptions {
     fn default() -> LoaderOptions {
         LoaderOptions {
             has_header: false,
             delimiter: ',',
             quote_marker: None,
+            pad_short_rows: false,
+            arena_storage: false,
+            escape_char: None,
+            trim_fields: false,
+            header_match: HeaderMatch::Exact,
+            normalize_headers: false,
+            skip_footer: 0,
+            multiline_quotes: false,
+            na_tokens: Vec::new(),
+            column_na_tokens: HashMap::new(),
+            column_parsers: HashMap::new(),
+            column_accumulators: HashMap::new(),
+            round_trip: false,
+        }
+    }
+}
+
+impl LoaderOptions {
+    /// The options which read files written with `WriterOptions::round_trip`.
+    ///
+    /// Loading a table written with these options gives back the same
+    /// table, see `WriterOptions::round_trip`.
+    pub fn round_trip() -> LoaderOptions {
+        LoaderOptions {
+            has_header: true,
+            quote_marker: Some('"'),
+            multiline_quotes: true,
+            round_trip: true,
+            ..LoaderOptions::default()
+        }
+    }
+
+    /// Copies the options which are used by `RowStream`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The options hold column parsers or
+    ///   accumulators, which cannot be copied.
+    pub(crate) fn stream_copy(&self) -> Result<LoaderOptions, DataError> {
+        if !self.column_parsers.is_empty() || !self.column_accumulators.is_empty() {
+            return Err(DataError::InvalidStateError);
         }
+
+        Ok(LoaderOptions {
+            has_header: self.has_header,
+            delimiter: self.delimiter,
+            quote_marker: self.quote_marker,
+            pad_short_rows: self.pad_short_rows,
+            arena_storage: self.arena_storage,
+            escape_char: self.escape_char,
+            trim_fields: self.trim_fields,
+            header_match: self.header_match,
+            normalize_headers: self.normalize_headers,
+            skip_footer: self.skip_footer,
+            multiline_quotes: self.multiline_quotes,
+            na_tokens: self.na_tokens.clone(),
+            column_na_tokens: self.column_na_tokens.clone(),
+            column_parsers: HashMap::new(),
+            column_accumulators: HashMap::new(),
+            round_trip: self.round_trip,
+        })
     }
 }
+
 /// Loader struct
 ///
 /// Used to load and process data files into tables.
 pub struct Loader<'a> {
-    file: &'a str,
+    source: Source<'a>,
     options: LoaderOptions,
 }
 
+/// Where a Loader reads its input from.
+enum Source<'a> {
+    Path(&'a str),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> fmt::Display for Source<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Path(path) => write!(f, "{}", path),
+            Source::Bytes(bytes) => write!(f, "{} bytes in memory", bytes.len()),
+        }
+    }
+}
+
 impl<'a> Loader<'a> {
     /// Constructs a new Loader.
-    pub fn new(has_header: bool, file: &str, delimiter: char) -> Loader {
+    pub fn new(has_header: bool, file: &str, delimiter: char) -> Loader<'_> {
         let options = LoaderOptions {
-            has_header: has_header,
-            delimiter: delimiter,
-            quote_marker: None,
+            has_header,
+            delimiter,
+            ..LoaderOptions::default()
         };
 
         Loader {
-            file: file,
-            options: options,
+            source: Source::Path(file),
+            options,
         }
     }
 
@@ -58,148 +266,1786 @@ impl<'a> Loader<'a> {
     ///
     /// - has_header : false
     /// - delimiter : ','
-    pub fn from_file_string(file_string: &str) -> Loader {
+    pub fn from_file_string(file_string: &str) -> Loader<'_> {
         Loader {
-            file: file_string,
+            source: Source::Path(file_string),
             options: LoaderOptions::default(),
         }
     }
 
+    /// Creates a loader for the file using the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions {
+    ///     has_header: true,
+    ///     pad_short_rows: true,
+    ///     ..LoaderOptions::default()
+    /// };
+    ///
+    /// let loader = Loader::with_options("path/to/file.data", options);
+    /// let (table, warnings) = loader.load_file_with_warnings().unwrap();
+    ///
+    /// for warning in warnings.warnings() {
+    ///     println!("{}", warning);
+    /// }
+    /// ```
+    pub fn with_options(file: &str, options: LoaderOptions) -> Loader<'_> {
+        Loader {
+            source: Source::Path(file),
+            options,
+        }
+    }
+
+    /// Creates a loader which reads from a buffer in memory instead of a file.
+    ///
+    /// No filesystem access is needed, so this works within WebAssembly and tests.
+    /// Invalid UTF-8 in the buffer is reported as an `Io` error when loading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions {
+    ///     has_header: true,
+    ///     ..LoaderOptions::default()
+    /// };
+    ///
+    /// let table = Loader::from_bytes(b"x,y\n1,2\n3,4\n", options).load_file().unwrap();
+    ///
+    /// assert_eq!(table.rows(), 2);
+    /// assert_eq!(&table[1][1], "4");
+    /// ```
+    pub fn from_bytes(bytes: &[u8], options: LoaderOptions) -> Loader<'_> {
+        Loader {
+            source: Source::Bytes(bytes),
+            options,
+        }
+    }
+
+    /// Creates a loader which reads the data held in the string.
+    ///
+    /// The string is the contents to load, not a path. See `from_bytes`.
+    pub fn from_str(data: &str, options: LoaderOptions) -> Loader<'_> {
+        Loader::from_bytes(data.as_bytes(), options)
+    }
+
+    /// Opens the input of the loader.
+    fn open(&self) -> Result<Input<'a>, DataError> {
+        match self.source {
+            Source::Path(path) => Ok(Input::File(BufReader::new(File::open(path)?))),
+            Source::Bytes(bytes) => Ok(Input::Bytes(bytes)),
+        }
+    }
+
     /// Load the file from the loader with given delimiter.
     ///
     /// Pretty rudimentary with poor error handling.
     ///
-    /// # Panics
+    /// # Failures
+    ///
+    /// - Io : The file could not be opened or read, or was not valid UTF-8.
+    /// - Parse : A line contained an unterminated quote.
+    /// - Malformed : A line did not have one field per column.
+    /// - InvalidStateError : A column parser, missing value marker or
+    ///   accumulator names no column.
+    ///
+    /// The `Parse` and `Malformed` errors carry a `LoaderError` describing
+    /// the line, column and text at fault.
     ///
-    /// - The input data is not a float.
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::error::DataError;
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+    /// let loader = Loader::from_str("x,y\n1,2\n3,4,5\n", options);
+    ///
+    /// match loader.load_file() {
+    ///     Err(DataError::Malformed(e)) => {
+    ///         assert_eq!(e.line, 3);
+    ///         assert_eq!(e.column, 1);
+    ///         assert_eq!(e.column_name, Some("y".to_string()));
+    ///     }
+    ///     _ => panic!("expected a malformed line"),
+    /// }
+    /// ```
+    pub fn load_file(self) -> Result<DataTable, DataError> {
+        self.load(&mut Warnings::new(), Err)
+    }
+
+    /// Load the file, returning any warnings alongside the table.
+    ///
+    /// Warnings are produced by lenient options such as `pad_short_rows`,
+    /// and for each column holding values which matched the missing value
+    /// markers.
     ///
     /// # Failures
     ///
-    /// - The input data is malformed (missing data, non-uniform rows etc.)
-    pub fn load_file(self) -> Result<DataTable, io::Error> {
-        let f = try!(File::open(self.file));
-        let reader = BufReader::new(f);
+    /// - Fails in the same way as `load_file`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions {
+    ///     has_header: true,
+    ///     na_tokens: vec!["NA".to_string()],
+    ///     ..LoaderOptions::default()
+    /// };
+    ///
+    /// let loader = Loader::from_str("x,y\nNA,1\nNA,2\n", options);
+    /// let (_, warnings) = loader.load_file_with_warnings().unwrap();
+    ///
+    /// assert_eq!(warnings.warnings()[0].to_string(),
+    ///            "column 0: 2 values matched the missing value markers");
+    /// ```
+    pub fn load_file_with_warnings(self) -> Result<(DataTable, Warnings), DataError> {
+        let mut warnings = Warnings::new();
+        let table = self.load(&mut warnings, Err)?;
 
-        let mut table = DataTable::empty();
+        Ok((table, warnings))
+    }
 
-        let mut lines = reader.lines();
+    /// Load the file, collecting every malformed line into an `ErrorReport`.
+    ///
+    /// Unlike `load_file` this does not stop at the first bad line. Lines which
+    /// fail are skipped and the load continues so that all of the problems in
+    /// the file are reported together.
+    ///
+    /// # Failures
+    ///
+    /// - Returns a report containing every `Parse` and `Malformed` error found.
+    ///   An `Io` error stops the load and is returned as the last entry.
+    pub fn load_file_report(self) -> Result<DataTable, ErrorReport> {
+        let mut report = ErrorReport::new();
 
-        if self.options.has_header {
-            if let Some(line) = lines.next() {
-                let line = try!(line);
-                let values = LineSplitIter::new(line.to_string(),
-                                                self.options.quote_marker,
-                                                self.options.delimiter);
-
-                for val in values {
-                    let mut column = DataColumn::empty();
-                    column.name = Some(val);
-                    table.data_cols.push(column);
-                }
-            }
-        } else {
-            if let Some(line) = lines.next() {
-                let line = try!(line);
-                let values = LineSplitIter::new(line.to_string(),
-                                                self.options.quote_marker,
-                                                self.options.delimiter);
+        let table = self.load(&mut Warnings::new(), |e| {
+            let (row, column) = match e {
+                DataError::Parse(ref err) |
+                DataError::Malformed(ref err) => (err.line, Some(err.column)),
+                _ => return Err(e),
+            };
 
-                for val in values {
-                    let mut column = DataColumn::empty();
-                    column.push(val);
+            report.push(row, column, e);
+            Ok(())
+        });
 
-                    table.data_cols.push(column);
+        match table {
+            Ok(table) => {
+                if report.is_empty() {
+                    Ok(table)
+                } else {
+                    Err(report)
                 }
             }
+            Err(e) => {
+                report.push(0, None, e);
+                Err(report)
+            }
         }
+    }
 
-        for line in lines {
-            let line = try!(line);
-            let values = LineSplitIter::new(line.to_string(),
-                                                self.options.quote_marker,
-                                                self.options.delimiter);
+    /// Loads the file passing line errors to `on_error`.
+    ///
+    /// The line is skipped if `on_error` returns `Ok`, otherwise the load stops.
+    fn load<F>(self, warnings: &mut Warnings, mut on_error: F) -> Result<DataTable, DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        let start = Instant::now();
+        log_debug!("loading {} with delimiter {:?}, quote {:?}, header {}, NA tokens {:?}",
+                   self.source,
+                   self.options.delimiter,
+                   self.options.quote_marker,
+                   self.options.has_header,
+                   self.options.na_tokens);
 
+        let mut reader = self.open()?;
+        let mut builder = TableBuilder::new(self.options);
+        let skipped = warnings.len();
 
-            let mut idx = 0usize;
+        builder.read_all(&mut reader, warnings, &mut on_error)?;
+        let table = builder.finish(warnings, &mut on_error)?;
 
-            for (i, val) in values.enumerate() {
-                idx = i;
-                if idx > table.cols() {
-                    return Err(Error::new(ErrorKind::InvalidInput, "Malformed data format."));
-                }
+        for warning in &warnings.warnings()[skipped..] {
+            log_warn!("{}: {}", self.source, warning);
+        }
+        log_info!("loaded {} rows and {} columns from {} in {:?}",
+                  table.rows(),
+                  table.cols(),
+                  self.source,
+                  start.elapsed());
 
-                table.data_cols[idx].push(val);
-            }
+        Ok(table)
+    }
+
+    /// Checks the file can be loaded without building a table.
+    ///
+    /// The file is streamed a line at a time checking the quoting and the
+    /// number of fields on each line. Every problem found is returned in
+    /// the report rather than stopping at the first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+    /// let report = Loader::from_str("a,b\n1,2\n3\n4,5\n", options).validate();
+    ///
+    /// assert_eq!(report.rows, 2);
+    /// assert_eq!(report.errors.entries()[0].row, 3);
+    /// ```
+    pub fn validate(self) -> ValidationReport {
+        self.validate_with(&[])
+    }
+
+    /// Checks the file can be loaded and that its values match the schema.
+    ///
+    /// The schema gives the type of each column in order. Missing values
+    /// are not checked and columns beyond the end of the schema may hold any value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    /// use rusty_data::schema::DType;
+    ///
+    /// let report = Loader::from_str("1,x\n2.5,y\n", LoaderOptions::default())
+    ///     .validate_with(&[DType::I64, DType::String]);
+    ///
+    /// assert!(!report.is_valid());
+    /// assert_eq!(report.errors.entries()[0].row, 2);
+    /// ```
+    pub fn validate_with(self, schema: &[DType]) -> ValidationReport {
+        let mut warnings = Warnings::new();
+        let reader = self.open();
+        let mut builder = TableBuilder::new(self.options);
+        builder.validation = Some(Validation {
+            schema: schema.to_vec(),
+            report: ValidationReport {
+                rows: 0,
+                errors: ErrorReport::new(),
+                warnings: Warnings::new(),
+            },
+        });
 
-            if idx != table.cols() - 1 {
-                return Err(Error::new(ErrorKind::InvalidInput, "Malformed data format."));
+        let result = match reader {
+            Ok(mut reader) => {
+                builder.read_all(&mut reader, &mut warnings, &mut Err)
+                    .and_then(|_| builder.flush(&mut warnings, &mut Err))
             }
+            Err(e) => Err(e),
+        };
+
+        let mut report = builder.validation.take().expect("validation was set above").report;
+
+        if let Err(e) = result {
+            report.errors.push(0, None, e);
         }
 
-        table.shrink_to_fit();
-        Ok(table)
+        for warning in report.warnings.warnings() {
+            warnings.push(warning.line, warning.column, warning.message.clone());
+        }
+        report.warnings = warnings;
+
+        report
+    }
+
+    /// Infers the type of each column from the first `sample_rows` rows.
+    ///
+    /// The result can be reviewed, and the types pinned with `validate_with`,
+    /// before the whole file is loaded. Malformed lines within the sample are skipped.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be opened or read.
+    /// - Parse : The first line contained an unterminated quote.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    /// use rusty_data::schema::DType;
+    ///
+    /// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+    /// let schema = Loader::from_str("id,score\n1,0.5\n2,0.25\n3,x\n", options)
+    ///     .infer_schema(2)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(schema[0].dtype, DType::I64);
+    /// assert_eq!(schema[1].dtype, DType::F64);
+    /// assert_eq!(schema[1].sampled, 2);
+    /// ```
+    pub fn infer_schema(self, sample_rows: usize) -> Result<Vec<ColumnInference>, DataError> {
+        let mut reader = self.open()?;
+        let mut builder = TableBuilder::new(self.options);
+        let mut warnings = Warnings::new();
+        let mut skip = |_| Ok(());
+
+        let mut line = String::new();
+
+        while builder.table.rows() < sample_rows && read_line(&mut reader, &mut line)? {
+            builder.push_line(&line, &mut warnings, &mut skip)?;
+        }
+
+        let table = builder.finish(&mut warnings, &mut skip)?;
+        let inferred: Vec<_> = table.data_cols.iter().map(schema::infer_column).collect();
+
+        for (i, column) in inferred.iter().enumerate() {
+            log_debug!("inferred {:?} for column {} of {} from {} values",
+                       column.dtype,
+                       i,
+                       self.source,
+                       column.sampled);
+        }
+
+        Ok(inferred)
+    }
+
+    /// Opens the file and returns an iterator over its rows.
+    ///
+    /// The rows are parsed one at a time as the iterator advances so
+    /// single pass computations never need to build a `DataTable`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be opened, or the header could not be read.
+    /// - Parse : The header contained an unterminated quote.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rusty_data::loader::Loader;
+    ///
+    /// let loader = Loader::new(true, "path/to/file.data", ',');
+    /// let mut total = 0f64;
+    ///
+    /// for record in loader.stream_rows().unwrap() {
+    ///     let record = record.unwrap();
+    ///     total += record.get_as::<f64>(0).unwrap();
+    /// }
+    /// ```
+    pub fn stream_rows(self) -> Result<RowStream<Input<'a>>, DataError> {
+        let reader = self.open()?;
+        RowStream::new(reader, self.options)
     }
 }
 
-/// Iterator to parse a line in a data file.
-pub struct LineSplitIter {
+/// The input read by a Loader, either a file or a buffer in memory.
+pub enum Input<'a> {
+    /// A file opened from a path.
+    File(BufReader<File>),
+    /// A buffer passed to `Loader::from_bytes`.
+    Bytes(&'a [u8]),
+}
+
+impl<'a> Read for Input<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Input::File(ref mut reader) => reader.read(buf),
+            Input::Bytes(ref mut bytes) => bytes.read(buf),
+        }
+    }
+}
+
+impl<'a> BufRead for Input<'a> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match *self {
+            Input::File(ref mut reader) => reader.fill_buf(),
+            Input::Bytes(ref mut bytes) => bytes.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match *self {
+            Input::File(ref mut reader) => reader.consume(amt),
+            Input::Bytes(ref mut bytes) => bytes.consume(amt),
+        }
+    }
+}
+
+/// Iterator over the rows of a data file.
+///
+/// Created by `Loader::stream_rows`. Each row is checked against the
+/// number of columns in the first line of the file.
+pub struct RowStream<R> {
+    reader: R,
+    pub(crate) options: LoaderOptions,
+    pub(crate) headers: Option<Arc<Vec<String>>>,
+    cols: usize,
     line: String,
-    quote_char: Option<char>,
-    delimiter: char,
+    line_number: usize,
+    lines_read: usize,
+    row: usize,
+    pending: Option<Vec<SmallString>>,
+    footer: FooterBuffer,
+    joiner: RecordJoiner,
+    warnings: Warnings,
 }
 
-impl LineSplitIter {
-    /// Construct a new LineSplitIter over the specified line using
-    /// the given quote character and delimiter.
-    pub fn new(line: String, quote_char: Option<char>, delimiter: char) -> LineSplitIter {
-        LineSplitIter {
-            line: line,
-            quote_char: quote_char,
-            delimiter: delimiter,
+impl<R: BufRead> RowStream<R> {
+    pub(crate) fn new(reader: R, options: LoaderOptions) -> Result<RowStream<R>, DataError> {
+        let mut stream = RowStream {
+            reader,
+            footer: FooterBuffer::new(options.skip_footer),
+            options,
+            headers: None,
+            cols: 0,
+            line: String::new(),
+            line_number: 0,
+            lines_read: 0,
+            row: 0,
+            pending: None,
+            joiner: RecordJoiner::new(),
+            warnings: Warnings::new(),
+        };
+
+        if let Some(line_number) = stream.read_record()? {
+            stream.line_number = line_number;
+
+            let values: Vec<Cow<str>> = split_line(&stream.options, &stream.line, line_number)?
+                .collect();
+            stream.cols = values.len();
+
+            if stream.options.has_header {
+                let names = values.into_iter().map(|v| header_name(&stream.options, v)).collect();
+                stream.headers = Some(Arc::new(names));
+            } else {
+                stream.pending = Some(values.into_iter().map(SmallString::from).collect());
+            }
+        }
+
+        Ok(stream)
+    }
+
+    /// Reads the next record into `line`, returning the line it started on.
+    fn read_record(&mut self) -> Result<Option<usize>, DataError> {
+        loop {
+            if !self.footer.read_line(&mut self.reader, &mut self.line)? {
+                return Ok(self.joiner.finish(&mut self.line));
+            }
+
+            self.lines_read += 1;
+
+            if let Some(start) = self.joiner.push(&self.options, &mut self.line, self.lines_read) {
+                return Ok(Some(start));
+            }
+        }
+    }
+
+    /// The names of the columns, if the file has a header.
+    pub fn headers(&self) -> Option<&[String]> {
+        self.headers.as_ref().map(|h| &h[..])
+    }
+
+    /// The warnings produced from the rows read so far.
+    ///
+    /// Warnings are produced by lenient options such as `pad_short_rows`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions { pad_short_rows: true, ..LoaderOptions::default() };
+    /// let mut rows = Loader::from_str("1,2\n3\n", options).stream_rows().unwrap();
+    ///
+    /// assert_eq!(rows.by_ref().count(), 2);
+    /// assert_eq!(rows.warnings().len(), 1);
+    /// ```
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// Reads and checks the next line of the file.
+    fn next_record(&mut self) -> Result<Option<Record>, DataError> {
+        let row = self.row;
+
+        if let Some(fields) = self.pending.take() {
+            self.row += 1;
+            return Ok(Some(self.record(fields, row)));
+        }
+
+        self.line_number = match self.read_record()? {
+            Some(line_number) => line_number,
+            None => return Ok(None),
+        };
+
+        let fields: Vec<SmallString> = {
+            let mut values: Vec<Cow<str>> = split_line(&self.options, &self.line, self.line_number)?
+                .collect();
+            let headers = &self.headers;
+
+            fit_fields(&self.options,
+                       &mut values,
+                       self.cols,
+                       |i| headers.as_ref().map(|h| h[i].clone()),
+                       &self.line,
+                       self.line_number,
+                       &mut self.warnings)?;
+
+            values.into_iter().map(SmallString::from).collect()
+        };
+
+        self.row += 1;
+        Ok(Some(self.record(fields, row)))
+    }
+
+    fn record(&self, fields: Vec<SmallString>, row: usize) -> Record {
+        Record {
+            fields,
+            row,
+            line: self.line_number,
+            headers: self.headers.clone(),
+            header_match: self.options.header_match,
         }
     }
 }
 
-impl Iterator for LineSplitIter {
-    type Item = String;
+impl<R: BufRead> Iterator for RowStream<R> {
+    type Item = Result<Record, DataError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.line.len() == 0 {
-            return None;
+        match self.next_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
         }
+    }
+}
 
-        let drain_offset: Option<usize>;
-        if let Some(quote_char) = self.quote_char {
-            let mut in_quotes = false;
+/// A single row read from a data file.
+pub struct Record {
+    pub(crate) fields: Vec<SmallString>,
+    pub(crate) row: usize,
+    pub(crate) line: usize,
+    pub(crate) headers: Option<Arc<Vec<String>>>,
+    pub(crate) header_match: HeaderMatch,
+}
 
-            drain_offset = self.line
-                               .find(|c| {
-                                   if c == quote_char {
-                                       in_quotes = !in_quotes;
-                                       false
-                                   } else if c == self.delimiter && !in_quotes {
-                                       true
-                                   } else {
-                                       false
-                                   }
-                               });
+impl Record {
+    /// The number of fields in the record.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
 
-        } else {
-            drain_offset = self.line.find(self.delimiter);
-        }
+    /// Returns true if the record has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The index of the record among the data rows of the file.
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// The line number the record was read from.
+    pub fn line(&self) -> usize {
+        self.line
+    }
 
-        if let Some(offset) = drain_offset {
-            let t: String = self.line.drain(..offset).collect();
-            self.line = self.line[1..].to_string();
+    /// Gets the field at the index, if it exists.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.fields.get(idx).map(|s| s.as_str())
+    }
 
-            match self.quote_char {
-                None => Some(t),
-                Some(quote_char) => Some(t.trim_matches(quote_char).to_string()),
+    /// Gets the field in the column with the given name, if it exists.
+    ///
+    /// Names are compared using the `header_match` option of the loader.
+    pub fn get_by_name(&self, name: &str) -> Option<&str> {
+        match self.headers {
+            Some(ref headers) => {
+                headers.iter()
+                    .position(|h| self.header_match.matches(h, name))
+                    .and_then(|i| self.get(i))
             }
-        } else {
-            Some(self.line.drain(..).collect())
+            None => None,
+        }
+    }
+
+    /// Try to get the field at the index as the requested type.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : The field could not be parsed to this type.
+    /// - InvalidStateError : The record has no field at the index.
+    pub fn get_as<T>(&self, idx: usize) -> Result<T, DataError>
+        where T: FromStr,
+              T::Err: Error + Send + Sync + 'static
+    {
+        match self.get(idx) {
+            Some(val) => {
+                cast_value(val, || {
+                    CellLocation {
+                        row: Some(self.row),
+                        column: Some(idx),
+                        column_name: self.headers.as_ref().map(|h| h[idx].clone()),
+                    }
+                })
+            }
+            None => Err(DataError::InvalidStateError),
+        }
+    }
+}
+
+/// Loader which is fed its input a chunk at a time.
+///
+/// The loader never performs any IO itself. This makes it suitable for
+/// use within services which receive data in pieces, for example an
+/// async task forwarding the chunks of an upload, as feeding the loader
+/// never blocks while waiting for more input.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::loader::{IncrementalLoader, LoaderOptions};
+///
+/// let options = LoaderOptions {
+///     has_header: true,
+///     ..LoaderOptions::default()
+/// };
+///
+/// let mut loader = IncrementalLoader::new(options);
+/// loader.feed(b"x,y\n1.0,2").unwrap();
+/// loader.feed(b".0\n3.0,4.0\n").unwrap();
+///
+/// let table = loader.finish().unwrap();
+///
+/// assert_eq!(table.rows(), 2);
+/// assert_eq!(&table[1][0], "2.0");
+/// ```
+pub struct IncrementalLoader {
+    builder: TableBuilder,
+    partial: Vec<u8>,
+    warnings: Warnings,
+}
+
+impl IncrementalLoader {
+    /// Constructs a new IncrementalLoader using the given options.
+    pub fn new(options: LoaderOptions) -> IncrementalLoader {
+        IncrementalLoader {
+            builder: TableBuilder::new(options),
+            partial: Vec::new(),
+            warnings: Warnings::new(),
         }
     }
+
+    /// Feeds the next chunk of input to the loader.
+    ///
+    /// Chunks may split lines, or characters, at any point.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `Loader::load_file` for each complete line.
+    ///   The lines before the failing line are kept, and the failing line is
+    ///   dropped, so no line is pushed twice if feeding continues.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), DataError> {
+        self.partial.extend_from_slice(chunk);
+
+        let mut start = 0;
+        let mut result = Ok(());
+        while let Some(end) = scan::find_byte(&self.partial[start..], b'\n') {
+            let end = start + end;
+            result = self.push_bytes(start, end);
+            start = end + 1;
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // The consumed lines are removed even on failure.
+        self.partial.drain(..start);
+        result
+    }
+
+    /// The warnings produced from the input so far.
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    /// Completes the load, returning the table.
+    ///
+    /// Any input after the final line ending is treated as the last line.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `feed` for the last line.
+    pub fn finish(self) -> Result<DataTable, DataError> {
+        self.finish_with_warnings().map(|(table, _)| table)
+    }
+
+    /// Completes the load, returning any warnings alongside the table.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as `finish`.
+    pub fn finish_with_warnings(mut self) -> Result<(DataTable, Warnings), DataError> {
+        if !self.partial.is_empty() {
+            let end = self.partial.len();
+            self.push_bytes(0, end)?;
+        }
+
+        let table = self.builder.finish(&mut self.warnings, &mut Err)?;
+        Ok((table, self.warnings))
+    }
+
+    /// Passes the line held in `partial[start..end]` to the builder.
+    fn push_bytes(&mut self, start: usize, end: usize) -> Result<(), DataError> {
+        let mut bytes = &self.partial[start..end];
+        if bytes.last() == Some(&b'\r') {
+            bytes = &bytes[..bytes.len() - 1];
+        }
+
+        let line = match str::from_utf8(bytes) {
+            Ok(line) => line,
+            Err(e) => return Err(DataError::Io(io::Error::new(io::ErrorKind::InvalidData, e))),
+        };
+
+        self.builder.push_line(line, &mut self.warnings, &mut Err)
+    }
+}
+
+/// Builds a table from the lines of the input.
+struct TableBuilder {
+    options: LoaderOptions,
+    table: DataTable,
+    line_number: usize,
+    records: usize,
+    footer: FooterBuffer,
+    joiner: RecordJoiner,
+    buf: String,
+    rules: Vec<ColumnRules>,
+    validation: Option<Validation>,
+}
+
+impl TableBuilder {
+    fn new(options: LoaderOptions) -> TableBuilder {
+        TableBuilder {
+            footer: FooterBuffer::new(options.skip_footer),
+            options,
+            table: DataTable::empty(),
+            line_number: 0,
+            records: 0,
+            joiner: RecordJoiner::new(),
+            buf: String::new(),
+            rules: Vec::new(),
+            validation: None,
+        }
+    }
+
+    /// Adds the next line of the input to the table.
+    ///
+    /// Errors in the line are passed to `on_error`. The line is skipped
+    /// if `on_error` returns `Ok`, otherwise the error is returned.
+    fn push_line<F>(&mut self,
+                    line: &str,
+                    warnings: &mut Warnings,
+                    on_error: &mut F)
+                    -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        if self.footer.size == 0 {
+            return self.parse_line(line, warnings, on_error);
+        }
+
+        match self.footer.push(line) {
+            Some(line) => self.parse_line(&line, warnings, on_error),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses a line known not to be part of the footer.
+    fn parse_line<F>(&mut self,
+                     line: &str,
+                     warnings: &mut Warnings,
+                     on_error: &mut F)
+                     -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        self.line_number += 1;
+
+        if !self.options.multiline_quotes {
+            let line_number = self.line_number;
+            return self.parse_record(line, line_number, warnings, on_error);
+        }
+
+        let mut buf = mem::take(&mut self.buf);
+        buf.clear();
+        buf.push_str(line);
+
+        let result = match self.joiner.push(&self.options, &mut buf, self.line_number) {
+            Some(start) => self.parse_record(&buf, start, warnings, on_error),
+            None => Ok(()),
+        };
+
+        self.buf = buf;
+        result
+    }
+
+    /// Parses a complete record which started on line `line_number`.
+    fn parse_record<F>(&mut self,
+                       line: &str,
+                       line_number: usize,
+                       warnings: &mut Warnings,
+                       on_error: &mut F)
+                       -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        self.records += 1;
+
+        if self.records == 1 {
+            return self.push_first_line(line, line_number, on_error);
+        }
+
+        let table = &mut self.table;
+
+        let (mut values, mut missing) = match split_record(&self.options, line, line_number) {
+            Ok(record) => record,
+            Err(e) => return self.fail(e, line_number, on_error),
+        };
+
+        if let Err(e) = fit_fields(&self.options,
+                                   &mut values,
+                                   table.cols(),
+                                   |i| table.data_cols[i].name.clone(),
+                                   line,
+                                   line_number,
+                                   warnings) {
+            return self.fail(e, line_number, on_error);
+        }
+        missing.resize(values.len(), false);
+
+        self.store(values, &missing, line_number, on_error)
+    }
+
+    /// Parses the values of a record and adds them to the table.
+    ///
+    /// Values whose position in `missing` is true are missing. When
+    /// validating the values are checked against the schema instead.
+    fn store<F>(&mut self,
+                mut values: Vec<Cow<str>>,
+                missing: &[bool],
+                line_number: usize,
+                on_error: &mut F)
+                -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        // Every value is parsed before any are pushed so a failure leaves no partial row.
+        for ((val, rules), &missing) in values.iter_mut().zip(&self.rules).zip(missing) {
+            if let Err(e) = rules.parse(val, missing) {
+                return self.fail(e, line_number, on_error);
+            }
+        }
+
+        if let Some(ref mut validation) = self.validation {
+            validation.check(&self.table, &self.rules, &values, missing, line_number);
+            return Ok(());
+        }
+
+        let fields = self.table.data_cols.iter_mut().zip(values).zip(&self.rules).zip(missing);
+        for (((column, val), rules), &missing) in fields {
+            rules.push(column, val, missing);
+        }
+
+        Ok(())
+    }
+
+    /// Passes an error in line `line_number` to `on_error`, or records it when validating.
+    fn fail<F>(&mut self, e: DataError, line_number: usize, on_error: &mut F) -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        match self.validation {
+            Some(ref mut validation) => {
+                let column = match e {
+                    DataError::Parse(ref err) |
+                    DataError::Malformed(ref err) => Some(err.column),
+                    _ => None,
+                };
+
+                validation.report.errors.push(line_number, column, e);
+                Ok(())
+            }
+            None => on_error(e),
+        }
+    }
+
+    /// Reads every line from the reader into the builder.
+    fn read_all<R, F>(&mut self,
+                      reader: &mut R,
+                      warnings: &mut Warnings,
+                      on_error: &mut F)
+                      -> Result<(), DataError>
+        where R: BufRead,
+              F: FnMut(DataError) -> Result<(), DataError>
+    {
+        // A single buffer is reused for every line of the file.
+        let mut line = String::new();
+
+        while read_line(reader, &mut line)? {
+            self.push_line(&line, warnings, on_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the columns from the first line, which may be a header.
+    fn push_first_line<F>(&mut self,
+                          line: &str,
+                          line_number: usize,
+                          on_error: &mut F)
+                          -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        let (values, missing) = split_record(&self.options, line, line_number)?;
+
+        let has_header = self.options.has_header;
+        let headers: Vec<Option<&str>> = values.iter()
+            .zip(&missing)
+            .map(|(val, &unnamed)| Some(&val[..]).filter(|_| has_header && !unnamed))
+            .collect();
+        self.rules = ColumnRules::for_columns(&mut self.options, &headers)?;
+
+        for header in headers {
+            let mut column = if self.options.arena_storage {
+                DataColumn::empty_arena()
+            } else {
+                DataColumn::empty()
+            };
+
+            column.name = header.map(|h| header_name(&self.options, Cow::Borrowed(h)));
+            self.table.data_cols.push(column);
+        }
+
+        if let Some(ref mut validation) = self.validation {
+            validation.check_first_line(&self.options, &self.table, line, line_number);
+        }
+
+        if self.options.has_header {
+            Ok(())
+        } else {
+            self.store(values, &missing, line_number, on_error)
+        }
+    }
+
+    /// Completes the table, parsing any record left open by a quote.
+    fn finish<F>(mut self, warnings: &mut Warnings, on_error: &mut F) -> Result<DataTable, DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        self.flush(warnings, on_error)?;
+        self.table.shrink_to_fit();
+        Ok(self.table)
+    }
+
+    /// Parses any record left open by a quote at the end of the input.
+    fn flush<F>(&mut self, warnings: &mut Warnings, on_error: &mut F) -> Result<(), DataError>
+        where F: FnMut(DataError) -> Result<(), DataError>
+    {
+        let mut buf = String::new();
+
+        if let Some(start) = self.joiner.finish(&mut buf) {
+            self.parse_record(&buf, start, warnings, on_error)?;
+        }
+
+        warn_na_matches(&self.rules, warnings);
+        Ok(())
+    }
+}
+
+/// Joins lines into records when quoted fields contain line breaks.
+struct RecordJoiner {
+    pending: Option<(String, usize)>,
+}
+
+impl RecordJoiner {
+    fn new() -> RecordJoiner {
+        RecordJoiner { pending: None }
+    }
+
+    /// Adds line `line_number`, held in `buf`.
+    ///
+    /// Once a record is complete it is left in `buf` and the line it
+    /// started on is returned.
+    fn push(&mut self, options: &LoaderOptions, buf: &mut String, line_number: usize) -> Option<usize> {
+        let quote_char = match options.quote_marker {
+            Some(quote_char) if options.multiline_quotes => quote_char,
+            _ => return Some(line_number),
+        };
+
+        let open = |text: &str| {
+            unterminated_quote(text, quote_char, options.delimiter, options.escape_char).is_some()
+        };
+
+        match self.pending.take() {
+            Some((mut record, start)) => {
+                record.push('\n');
+                record.push_str(buf);
+
+                if open(&record) {
+                    self.pending = Some((record, start));
+                    None
+                } else {
+                    *buf = record;
+                    Some(start)
+                }
+            }
+            None if open(buf) => {
+                self.pending = Some((mem::take(buf), line_number));
+                None
+            }
+            None => Some(line_number),
+        }
+    }
+
+    /// Moves a record left open at the end of the input into `buf`.
+    fn finish(&mut self, buf: &mut String) -> Option<usize> {
+        self.pending.take().map(|(record, start)| {
+            *buf = record;
+            start
+        })
+    }
+}
+
+/// The state of a `Loader::validate` pass.
+struct Validation {
+    schema: Vec<DType>,
+    report: ValidationReport,
+}
+
+impl Validation {
+    /// Checks the line used to create the columns.
+    fn check_first_line(&mut self,
+                        options: &LoaderOptions,
+                        table: &DataTable,
+                        line: &str,
+                        line_number: usize) {
+        let warnings = &mut self.report.warnings;
+
+        if table.cols() == 1 {
+            for &other in &[',', ';', '\t', '|'] {
+                if other != options.delimiter && line.contains(other) {
+                    warnings.push(Some(line_number),
+                                  None,
+                                  format!("only one field found but the line contains {:?}, \
+                                           which may be the delimiter",
+                                          other));
+                    break;
+                }
+            }
+        }
+
+        if !self.schema.is_empty() && self.schema.len() != table.cols() {
+            warnings.push(Some(line_number),
+                          None,
+                          format!("the schema has {} types but there are {} columns",
+                                  self.schema.len(),
+                                  table.cols()));
+        }
+    }
+
+    /// Checks the values of a data row against the schema.
+    fn check(&mut self,
+             table: &DataTable,
+             rules: &[ColumnRules],
+             values: &[Cow<str>],
+             missing: &[bool],
+             line_number: usize) {
+        let row = self.report.rows;
+        self.report.rows += 1;
+
+        for (idx, (val, dtype)) in values.iter().zip(&self.schema).enumerate() {
+            if missing[idx] || rules[idx].is_missing(val) {
+                continue;
+            }
+
+            let location = || {
+                CellLocation {
+                    row: Some(row),
+                    column: Some(idx),
+                    column_name: table.data_cols[idx].name.clone(),
+                }
+            };
+
+            if let Err(e) = dtype.check(val, location) {
+                self.report.errors.push(line_number, Some(idx), e);
+            }
+        }
+    }
+}
+
+/// The result of `Loader::validate`.
+#[derive(Debug)]
+pub struct ValidationReport {
+    /// The number of data rows which were well formed.
+    pub rows: usize,
+    /// Every malformed line and value which did not match the schema.
+    pub errors: ErrorReport,
+    /// Possible problems, such as a delimiter which looks wrong.
+    pub warnings: Warnings,
+}
+
+impl ValidationReport {
+    /// Returns true if no errors were found.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// The missing value markers, parser and accumulators which apply to a column.
+pub(crate) struct ColumnRules {
+    na_tokens: Vec<String>,
+    parser: Option<FieldParser>,
+    accumulators: Vec<SharedAccumulator>,
+    // The number of values marked as missing because they matched a token.
+    na_matches: Cell<usize>,
+}
+
+impl ColumnRules {
+    /// Collects the rules for each column, taking the parsers and accumulators from the options.
+    ///
+    /// `headers` holds the header of each column as it appears in the file,
+    /// or `None` for columns without one.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : A column parser, missing value marker or
+    ///   accumulator names no column.
+    pub(crate) fn for_columns(options: &mut LoaderOptions,
+                              headers: &[Option<&str>])
+                              -> Result<Vec<ColumnRules>, DataError> {
+        let mut rules = Vec::with_capacity(headers.len());
+        let mut na_keys = HashSet::new();
+
+        for (index, &header) in headers.iter().enumerate() {
+            let mut na_tokens = options.na_tokens.clone();
+
+            if let Some(key) = column_key(options.column_na_tokens.keys(), options, index, header) {
+                na_tokens.extend(options.column_na_tokens[&key].iter().cloned());
+                na_keys.insert(key);
+            }
+
+            let parser = column_key(options.column_parsers.keys(), options, index, header)
+                .and_then(|key| options.column_parsers.remove(&key));
+
+            let accumulators =
+                column_key(options.column_accumulators.keys(), options, index, header)
+                    .and_then(|key| options.column_accumulators.remove(&key))
+                    .unwrap_or_default();
+
+            rules.push(ColumnRules {
+                na_tokens,
+                parser,
+                accumulators,
+                na_matches: Cell::new(0),
+            });
+        }
+
+        let unmatched = !options.column_parsers.is_empty() ||
+                        !options.column_accumulators.is_empty() ||
+                        na_keys.len() != options.column_na_tokens.len();
+        if unmatched {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(rules)
+    }
+
+    fn is_missing(&self, val: &str) -> bool {
+        self.na_tokens.iter().any(|t| t == val)
+    }
+
+    /// Replaces the value with the result of the parser, if there is one.
+    ///
+    /// Missing values are not parsed.
+    pub(crate) fn parse(&self, val: &mut Cow<str>, missing: bool) -> Result<(), DataError> {
+        if let Some(ref parser) = self.parser {
+            if !missing && !self.is_missing(val) {
+                *val = Cow::Owned(parser(val)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a warning if values of the column at `idx` matched its missing value markers.
+    pub(crate) fn warn_na_matches(&self, idx: usize, warnings: &mut Warnings) {
+        let matches = self.na_matches.get();
+        if matches > 0 {
+            warnings.push(None,
+                          Some(idx),
+                          format!("{} values matched the missing value markers", matches));
+        }
+    }
+
+    /// Pushes the value, marking it as missing if it is one of the tokens.
+    ///
+    /// The value is also passed to the accumulators.
+    pub(crate) fn push(&self, column: &mut DataColumn, val: Cow<str>, missing: bool) {
+        let matched = !missing && self.is_missing(&val);
+        if matched {
+            self.na_matches.set(self.na_matches.get() + 1);
+        }
+        let missing = missing || matched;
+
+        for accumulator in &self.accumulators {
+            // An accumulator which panicked is left as it was.
+            if let Ok(mut accumulator) = accumulator.lock() {
+                accumulator.update(if missing { None } else { Some(&val) });
+            }
+        }
+
+        if missing {
+            column.push_missing(val);
+        } else {
+            column.push(val);
+        }
+    }
+}
+
+/// Records a warning for each column with values marked as missing by its missing value markers.
+pub(crate) fn warn_na_matches(rules: &[ColumnRules], warnings: &mut Warnings) {
+    for (idx, rules) in rules.iter().enumerate() {
+        rules.warn_na_matches(idx, warnings);
+    }
+}
+
+/// Finds the key of a per-column option which refers to the column.
+///
+/// Keys are compared using `header_match` with both the header as it is
+/// written and the column name, which may be normalized. A column without
+/// a header is referred to by its index.
+fn column_key<'a, K>(keys: K,
+                     options: &LoaderOptions,
+                     index: usize,
+                     header: Option<&str>)
+                     -> Option<String>
+    where K: Iterator<Item = &'a String>
+{
+    let name = header.map(|h| header_name(options, Cow::Borrowed(h)));
+
+    keys.filter(|key| match (header, name.as_ref()) {
+            (Some(header), Some(name)) => {
+                options.header_match.matches(header, key) ||
+                options.header_match.matches(name, key)
+            }
+            _ => **key == index.to_string(),
+        })
+        .min()
+        .cloned()
+}
+
+/// Converts a header field to a column name, normalizing it if the options ask.
+pub(crate) fn header_name(options: &LoaderOptions, field: Cow<str>) -> String {
+    if options.normalize_headers {
+        normalize_header(&field)
+    } else {
+        field.into_owned()
+    }
+}
+
+/// Normalizes a header so that it is stable across slightly different files.
+///
+/// A leading byte order mark is removed, letters are lowercased, runs of
+/// whitespace become a single underscore and other punctuation is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::loader::normalize_header;
+///
+/// assert_eq!(normalize_header("\u{feff}Max Temp. (C)"), "max_temp_c");
+/// assert_eq!(normalize_header("  sensor_id "), "sensor_id");
+/// ```
+pub fn normalize_header(header: &str) -> String {
+    let header = header.trim_start_matches('\u{feff}').trim();
+    let mut name = String::with_capacity(header.len());
+    let mut gap = false;
+
+    for c in header.chars() {
+        if c.is_whitespace() {
+            gap = true;
+        } else if c.is_alphanumeric() || c == '_' {
+            if gap && !name.is_empty() {
+                name.push('_');
+            }
+            gap = false;
+            name.extend(c.to_lowercase());
+        }
+    }
+
+    name
+}
+
+/// Holds back the last lines of the input so that a footer can be skipped.
+struct FooterBuffer {
+    held: VecDeque<String>,
+    size: usize,
+}
+
+impl FooterBuffer {
+    fn new(size: usize) -> FooterBuffer {
+        FooterBuffer {
+            held: VecDeque::with_capacity(size + 1),
+            size,
+        }
+    }
+
+    /// Adds a line, returning the oldest held line once it cannot be part of the footer.
+    fn push(&mut self, line: &str) -> Option<String> {
+        self.held.push_back(line.to_string());
+
+        if self.held.len() > self.size {
+            self.held.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Reads the next line which is not part of the footer into `buf`.
+    ///
+    /// Returns false once only the footer remains.
+    fn read_line<R: BufRead>(&mut self, reader: &mut R, buf: &mut String) -> Result<bool, DataError> {
+        if self.size == 0 {
+            return read_line(reader, buf);
+        }
+
+        while read_line(reader, buf)? {
+            if let Some(line) = self.push(buf) {
+                *buf = line;
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Checks the fields of a line match the number of columns.
+///
+/// Short lines are padded with empty values if the options allow it.
+pub(crate) fn fit_fields<N>(options: &LoaderOptions,
+                            values: &mut Vec<Cow<str>>,
+                            cols: usize,
+                            column_name: N,
+                            line: &str,
+                            line_number: usize,
+                            warnings: &mut Warnings)
+                            -> Result<(), DataError>
+    where N: Fn(usize) -> Option<String>
+{
+    if values.len() > cols {
+        // The extra fields are reported against the last column they follow.
+        let last = cols.saturating_sub(1);
+
+        return Err(DataError::Malformed(LoaderError {
+            kind: LoaderErrorKind::TooManyFields {
+                expected: cols,
+                found: values.len(),
+            },
+            line: line_number,
+            column: last,
+            column_name: if cols > 0 { column_name(last) } else { None },
+            text: values.swap_remove(cols).into_owned(),
+        }));
+    }
+
+    if values.len() < cols && options.pad_short_rows {
+        warn_padded(warnings, line_number, values.len(), cols);
+
+        while values.len() < cols {
+            values.push(Cow::Borrowed(""));
+        }
+    }
+
+    if values.len() < cols {
+        return Err(DataError::Malformed(LoaderError {
+            kind: LoaderErrorKind::TooFewFields {
+                expected: cols,
+                found: values.len(),
+            },
+            line: line_number,
+            column: values.len(),
+            column_name: column_name(values.len()),
+            text: line.to_string(),
+        }));
+    }
+
+    Ok(())
+}
+
+/// Records a warning for a line padded from `found` to `cols` fields.
+pub(crate) fn warn_padded(warnings: &mut Warnings, line_number: usize, found: usize, cols: usize) {
+    warnings.push(Some(line_number),
+                  Some(found),
+                  format!("padded from {} to {} fields", found, cols));
+}
+
+/// Checks the line is well formed and splits it into fields.
+pub(crate) fn split_line<'l>(options: &LoaderOptions,
+                             line: &'l str,
+                             line_number: usize)
+                             -> Result<LineSplitIter<'l>, DataError> {
+    if let Some(quote_char) = options.quote_marker {
+        if let Some(column) = unterminated_quote(line,
+                                                 quote_char,
+                                                 options.delimiter,
+                                                 options.escape_char) {
+            return Err(DataError::Parse(LoaderError {
+                kind: LoaderErrorKind::UnterminatedQuote,
+                line: line_number,
+                column,
+                column_name: None,
+                text: line.to_string(),
+            }));
+        }
+    }
+
+    Ok(line_splitter(options, line))
+}
+
+/// Splits the line into fields using the options, without checking its quotes.
+pub(crate) fn line_splitter<'l>(options: &LoaderOptions, line: &'l str) -> LineSplitIter<'l> {
+    let mut fields = LineSplitIter::with_escape(line,
+                                                options.quote_marker,
+                                                options.delimiter,
+                                                options.escape_char);
+    fields.trim = options.trim_fields;
+    fields.round_trip = options.round_trip;
+    fields.pending = options.round_trip;
+
+    fields
+}
+
+/// Splits a record into its values, and whether each value is missing.
+///
+/// Only empty fields which are not quoted are missing, when the options
+/// ask for `round_trip`. Other missing values are found by `ColumnRules`.
+pub(crate) fn split_record<'l>(options: &LoaderOptions,
+                               line: &'l str,
+                               line_number: usize)
+                               -> Result<(Vec<Cow<'l, str>>, Vec<bool>), DataError> {
+    let mut fields = split_line(options, line, line_number)?;
+    let mut values = Vec::new();
+    let mut missing = Vec::new();
+
+    while let Some((val, quoted)) = fields.next_field() {
+        missing.push(field_missing(options, &val, quoted));
+        values.push(val);
+    }
+
+    Ok((values, missing))
+}
+
+/// Returns true if a field is read as missing before any missing value markers apply.
+pub(crate) fn field_missing(options: &LoaderOptions, val: &str, quoted: bool) -> bool {
+    options.round_trip && !quoted && val.is_empty()
+}
+
+/// Reads the next line into `buf` without the line ending.
+///
+/// Returns false once the end of the input is reached.
+fn read_line<R: BufRead>(reader: &mut R, buf: &mut String) -> Result<bool, DataError> {
+    buf.clear();
+
+    if reader.read_line(buf)? == 0 {
+        return Ok(false);
+    }
+
+    if buf.ends_with('\n') {
+        buf.pop();
+        if buf.ends_with('\r') {
+            buf.pop();
+        }
+    }
+
+    Ok(true)
+}
+
+/// Finds the index of the field containing an unclosed quote, if any.
+fn unterminated_quote(line: &str,
+                      quote_char: char,
+                      delimiter: char,
+                      escape_char: Option<char>)
+                      -> Option<usize> {
+    let escaped = escape_char.is_some_and(|e| line.contains(e));
+
+    if let Some(quote) = scan::ascii_byte(quote_char) {
+        if !escaped && scan::count_byte(line.as_bytes(), quote).is_multiple_of(2) {
+            return None;
+        }
+    }
+
+    let mut in_quotes = false;
+    let mut field = 0usize;
+    let mut quoted_field = 0usize;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if Some(c) == escape_char {
+            chars.next();
+        } else if c == quote_char {
+            in_quotes = !in_quotes;
+            quoted_field = field;
+        } else if c == delimiter && !in_quotes {
+            field += 1;
+        }
+    }
+
+    if in_quotes {
+        Some(quoted_field)
+    } else {
+        None
+    }
+}
+
+/// Iterator to parse a line in a data file.
+///
+/// Fields are borrowed from the line wherever possible so that no
+/// allocation happens until a column takes ownership of the value.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::loader::LineSplitIter;
+///
+/// let line = "1,\"a,b\",3";
+/// let fields: Vec<_> = LineSplitIter::new(line, Some('"'), ',').collect();
+///
+/// assert_eq!(fields, vec!["1", "a,b", "3"]);
+/// ```
+pub struct LineSplitIter<'a> {
+    line: &'a str,
+    quote_char: Option<char>,
+    delimiter: char,
+    escape_char: Option<char>,
+    trim: bool,
+    round_trip: bool,
+    // True if a field is still to come, even if the line is empty.
+    pending: bool,
+}
+
+impl<'a> LineSplitIter<'a> {
+    /// Construct a new LineSplitIter over the specified line using
+    /// the given quote character and delimiter.
+    pub fn new(line: &'a str, quote_char: Option<char>, delimiter: char) -> LineSplitIter<'a> {
+        LineSplitIter::with_escape(line, quote_char, delimiter, None)
+    }
+
+    /// Construct a new LineSplitIter which also treats the character
+    /// following `escape_char` literally.
+    ///
+    /// Fields containing an escape are unescaped into an owned value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::LineSplitIter;
+    ///
+    /// let line = r"a\,b,c";
+    /// let fields: Vec<_> = LineSplitIter::with_escape(line, None, ',', Some('\\')).collect();
+    ///
+    /// assert_eq!(fields, vec!["a,b", "c"]);
+    /// ```
+    pub fn with_escape(line: &'a str,
+                       quote_char: Option<char>,
+                       delimiter: char,
+                       escape_char: Option<char>)
+                       -> LineSplitIter<'a> {
+        LineSplitIter {
+            line,
+            quote_char,
+            delimiter,
+            escape_char,
+            trim: false,
+            round_trip: false,
+            pending: false,
+        }
+    }
+}
+
+impl<'a> LineSplitIter<'a> {
+    /// Finds the offset of the next delimiter which is not within quotes.
+    fn find_delimiter(&self) -> Option<usize> {
+        if let Some(escape) = self.escape_char {
+            if self.line.contains(escape) {
+                return self.find_delimiter_escaped(escape);
+            }
+        }
+
+        let bytes = self.line.as_bytes();
+
+        match (scan::ascii_byte(self.delimiter), self.quote_char.map(scan::ascii_byte)) {
+            (Some(delimiter), None) => scan::find_byte(bytes, delimiter),
+            (Some(delimiter), Some(Some(quote))) => {
+                let mut pos = 0;
+
+                loop {
+                    let idx = pos + scan::find_either(&bytes[pos..], delimiter, quote)?;
+
+                    if bytes[idx] == delimiter {
+                        return Some(idx);
+                    }
+
+                    // Skip past the closing quote.
+                    pos = idx + 1 + scan::find_byte(&bytes[idx + 1..], quote)? + 1;
+                }
+            }
+            _ => self.find_delimiter_chars(),
+        }
+    }
+
+    /// Finds the next delimiter a character at a time.
+    ///
+    /// Used when the delimiter or quote character is not ASCII.
+    fn find_delimiter_chars(&self) -> Option<usize> {
+        let delimiter = self.delimiter;
+
+        if let Some(quote_char) = self.quote_char {
+            let mut in_quotes = false;
+
+            self.line
+                .find(|c| {
+                    if c == quote_char {
+                        in_quotes = !in_quotes;
+                        false
+                    } else {
+                        c == delimiter && !in_quotes
+                    }
+                })
+        } else {
+            self.line.find(delimiter)
+        }
+    }
+
+    /// Finds the next delimiter a character at a time, skipping escaped characters.
+    fn find_delimiter_escaped(&self, escape: char) -> Option<usize> {
+        let mut in_quotes = false;
+        let mut chars = self.line.char_indices();
+
+        while let Some((idx, c)) = chars.next() {
+            if c == escape {
+                chars.next();
+            } else if Some(c) == self.quote_char {
+                in_quotes = !in_quotes;
+            } else if c == self.delimiter && !in_quotes {
+                return Some(idx);
+            }
+        }
+
+        None
+    }
+
+    /// Removes escapes and unescaped quotes from the field.
+    fn unescape(&self, field: &str, escape: char) -> String {
+        let mut value = String::with_capacity(field.len());
+        let mut chars = field.chars();
+
+        while let Some(c) = chars.next() {
+            if c == escape {
+                if let Some(next) = chars.next() {
+                    value.push(next);
+                }
+            } else if Some(c) != self.quote_char {
+                value.push(c);
+            }
+        }
+
+        value
+    }
+}
+
+impl<'a> LineSplitIter<'a> {
+    /// Gets the next field, and whether it was quoted.
+    pub(crate) fn next_field(&mut self) -> Option<(Cow<'a, str>, bool)> {
+        let field = self.next_raw()?;
+        Some(self.parse_field(field))
+    }
+
+    /// Gets the text of the next field as it is written, including any quotes.
+    ///
+    /// The text is a slice of the line, so its position within the line can
+    /// be found from its pointer.
+    pub(crate) fn next_raw(&mut self) -> Option<&'a str> {
+        if self.line.is_empty() {
+            if !self.pending {
+                return None;
+            }
+            self.pending = false;
+            return Some(self.line);
+        }
+
+        let drain_offset = self.find_delimiter();
+
+        let field = match drain_offset {
+            Some(offset) => {
+                let field = &self.line[..offset];
+                self.line = &self.line[offset + self.delimiter.len_utf8()..];
+                // When reading round trips the delimiter is always followed by a field.
+                self.pending = self.round_trip;
+                field
+            }
+            None => {
+                let field = self.line;
+                self.line = &self.line[self.line.len()..];
+                self.pending = false;
+                field
+            }
+        };
+
+        Some(field)
+    }
+
+    /// Reads the value of a field from its text as written, and whether it was quoted.
+    pub(crate) fn parse_field<'f>(&self, field: &'f str) -> (Cow<'f, str>, bool) {
+        let field = if self.trim { field.trim() } else { field };
+        let quoted = self.quote_char.is_some_and(|q| field.starts_with(q));
+
+        if let Some(escape) = self.escape_char {
+            if field.contains(escape) {
+                return (Cow::Owned(self.unescape(field, escape)), quoted);
+            }
+        }
+
+        match self.quote_char {
+            None => (Cow::Borrowed(field), false),
+            Some(quote_char) if self.round_trip => (unquote(field, quote_char), quoted),
+            Some(quote_char) => (Cow::Borrowed(field.trim_matches(quote_char)), quoted),
+        }
+    }
+}
+
+impl<'a> Iterator for LineSplitIter<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_field().map(|(field, _)| field)
+    }
+}
+
+/// Removes the enclosing quotes from the field and reads doubled quotes as one.
+fn unquote(field: &str, quote_char: char) -> Cow<'_, str> {
+    let quote_len = quote_char.len_utf8();
+    let enclosed = field.starts_with(quote_char) && field.ends_with(quote_char);
+    if field.len() < 2 * quote_len || !enclosed {
+        return Cow::Borrowed(field);
+    }
+
+    let inner = &field[quote_len..field.len() - quote_len];
+    if inner.contains(quote_char) {
+        let mut doubled = String::new();
+        doubled.push(quote_char);
+        doubled.push(quote_char);
+        Cow::Owned(inner.replace(&doubled, quote_char.encode_utf8(&mut [0; 4])))
+    } else {
+        Cow::Borrowed(inner)
+    }
 }
 
 /// Load the specified file to a DataTable.
@@ -0,0 +1,31 @@
+//! Logging through the `log` crate, enabled by the `log` feature.
+//!
+//! Without the feature the macros below expand to code which is never
+//! run, so their arguments are still checked but nothing is formatted.
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        { ::log::debug!($($arg)+); }
+        #[cfg(not(feature = "log"))]
+        { if false { let _ = format_args!($($arg)+); } }
+    };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        { ::log::info!($($arg)+); }
+        #[cfg(not(feature = "log"))]
+        { if false { let _ = format_args!($($arg)+); } }
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        { ::log::warn!($($arg)+); }
+        #[cfg(not(feature = "log"))]
+        { if false { let _ = format_args!($($arg)+); } }
+    };
+}
@@ -0,0 +1,193 @@
+//! The mat module.
+//!
+//! Provides writing of DataTables as MATLAB level 5 MAT-files, so that
+//! results can be opened with `load` in MATLAB or Octave. Built with the
+//! `mat` feature.
+//!
+//! A file holds two variables: the values of the table as a matrix of
+//! doubles with a row for each row of the table, and the column names as
+//! a cell array of character arrays.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+
+use datatable::DataTable;
+use error::DataError;
+use npy::cell_value;
+
+/// The descriptive text at the start of the file, padded to 116 bytes.
+const HEADER_TEXT: &str = "MATLAB 5.0 MAT-file, written by rusty-data";
+
+// The types of data elements.
+const MI_INT8: u32 = 1;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+
+// The classes of arrays.
+const MX_CELL_CLASS: u32 = 1;
+const MX_CHAR_CLASS: u32 = 4;
+const MX_DOUBLE_CLASS: u32 = 6;
+
+/// Options for writing MAT-files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatOptions {
+    /// The variable holding the matrix of values
+    pub matrix: String,
+    /// The variable holding the cell array of column names
+    pub names: String,
+}
+
+impl Default for MatOptions {
+    fn default() -> MatOptions {
+        MatOptions {
+            matrix: "data".to_string(),
+            names: "columns".to_string(),
+        }
+    }
+}
+
+impl DataTable {
+    /// Writes the table as a MAT-file with a matrix and a cell array of column names.
+    ///
+    /// Each value is parsed as an `f64` and missing values are written as
+    /// NaN. Columns without a name have an empty name in the cell array.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value does not parse as a number.
+    /// - InvalidStateError : A variable name is not a valid MATLAB name,
+    ///   both variables have the same name, or the matrix would be larger
+    ///   than 4GiB.
+    /// - Io : The writer failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::mat::MatOptions;
+    ///
+    /// let records = vec![vec!["1", "0.5"], vec!["2", "1.5"]];
+    /// let table = DataTable::from_records(Some(vec!["x", "y"]), records).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// table.write_mat(&mut bytes, &MatOptions::default()).unwrap();
+    ///
+    /// assert!(bytes.starts_with(b"MATLAB 5.0 MAT-file"));
+    /// assert_eq!(&bytes[126..128], b"IM");
+    /// ```
+    pub fn write_mat<W: Write>(&self,
+                               writer: &mut W,
+                               options: &MatOptions)
+                               -> Result<(), DataError> {
+        if !is_valid_name(&options.matrix) || !is_valid_name(&options.names) ||
+           options.matrix == options.names {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut values = Vec::with_capacity(self.rows() * self.cols() * 8);
+        for (col, column) in self.data_cols.iter().enumerate() {
+            for row in 0..column.len() {
+                let value: f64 = cell_value(column, row, col)?;
+                values.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let matrix = array(MX_DOUBLE_CLASS,
+                           &[self.rows(), self.cols()],
+                           &options.matrix,
+                           &element(MI_DOUBLE, &values)?)?;
+
+        let mut cells = Vec::new();
+        for column in &self.data_cols {
+            cells.extend(char_array(column.name.as_deref().unwrap_or(""))?);
+        }
+        let names = array(MX_CELL_CLASS, &[1, self.cols()], &options.names, &cells)?;
+
+        let mut header = HEADER_TEXT.as_bytes().to_vec();
+        header.resize(116, b' ');
+        // No subsystem data, version 1 and the written endian indicator.
+        header.extend_from_slice(&[0; 8]);
+        header.extend_from_slice(&0x0100u16.to_le_bytes());
+        header.extend_from_slice(b"IM");
+
+        writer.write_all(&header)?;
+        writer.write_all(&matrix)?;
+        writer.write_all(&names)?;
+        Ok(())
+    }
+
+    /// Writes the table to a MAT-file, see `write_mat`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value does not parse as a number.
+    /// - InvalidStateError : A variable name is not a valid MATLAB name,
+    ///   both variables have the same name, or the matrix would be larger
+    ///   than 4GiB.
+    /// - Io : The file could not be written.
+    pub fn save_mat<P: AsRef<Path>>(&self, path: P, options: &MatOptions) -> Result<(), DataError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_mat(&mut writer, options)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Returns true if the name can be used for a MATLAB variable.
+fn is_valid_name(name: &str) -> bool {
+    name.len() <= 63 && name.starts_with(|c: char| c.is_ascii_alphabetic()) &&
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A data element of the type, padded to a multiple of 8 bytes.
+fn element(ty: u32, data: &[u8]) -> Result<Vec<u8>, DataError> {
+    if data.len() > u32::MAX as usize - 8 {
+        return Err(DataError::InvalidStateError);
+    }
+
+    let mut out = Vec::with_capacity(data.len() + 15);
+    out.extend_from_slice(&ty.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    while out.len() % 8 != 0 {
+        out.push(0);
+    }
+    Ok(out)
+}
+
+/// A matrix element holding an array of the class, whose data is `body`.
+fn array(class: u32, dims: &[usize], name: &str, body: &[u8]) -> Result<Vec<u8>, DataError> {
+    let mut flags = Vec::with_capacity(8);
+    flags.extend_from_slice(&class.to_le_bytes());
+    flags.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut sizes = Vec::with_capacity(dims.len() * 4);
+    for &dim in dims {
+        if dim > i32::MAX as usize {
+            return Err(DataError::InvalidStateError);
+        }
+        sizes.extend_from_slice(&(dim as i32).to_le_bytes());
+    }
+
+    let mut data = element(MI_UINT32, &flags)?;
+    data.extend(element(MI_INT32, &sizes)?);
+    data.extend(element(MI_INT8, name.as_bytes())?);
+    data.extend_from_slice(body);
+    element(MI_MATRIX, &data)
+}
+
+/// An unnamed row of characters, as held by a cell array.
+fn char_array(text: &str) -> Result<Vec<u8>, DataError> {
+    let mut units = Vec::new();
+    for unit in text.encode_utf16() {
+        units.extend_from_slice(&unit.to_le_bytes());
+    }
+
+    let len = units.len() / 2;
+    let dims = if len == 0 { [0, 0] } else { [1, len] };
+    array(MX_CHAR_CLASS, &dims, "", &element(MI_UINT16, &units)?)
+}
@@ -0,0 +1,381 @@
+//! The msgpack module.
+//!
+//! Provides encoding of DataTables as MessagePack, for exchanging tables
+//! between services without parsing text.
+//!
+//! A table is a map holding `columns`, an array with a map for each
+//! column. A column map holds its `name` and recorded `dtype`, which may
+//! be nil, its `values` and, if it has categories, its `categories` as an
+//! array of labels in index order. Missing values are nil. Values of a
+//! column recorded as `bool`, `i64` or `f64` are written as MessagePack
+//! booleans, integers or floats where this keeps their text, and as
+//! strings otherwise.
+
+use std::collections::HashMap;
+use std::str;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use schema::DType;
+use snapshot::SnapshotReader;
+
+/// The deepest nesting of collections which is skipped over.
+const MAX_DEPTH: usize = 64;
+
+impl DataTable {
+    /// Encodes the table as MessagePack.
+    ///
+    /// The text of missing values is not kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "a"], vec!["2", "b"]];
+    /// let table = DataTable::from_records(Some(vec!["id", "label"]), records).unwrap();
+    ///
+    /// let bytes = table.to_msgpack();
+    /// let decoded = DataTable::from_msgpack(&bytes).unwrap();
+    ///
+    /// assert!(decoded.compare(&table).is_empty());
+    /// assert_eq!(decoded.headers(), vec!["id", "label"]);
+    /// ```
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_map_len(&mut out, 1);
+        write_str(&mut out, "columns");
+        write_array_len(&mut out, self.cols());
+
+        for column in self.data_cols.iter() {
+            let labels = column.category_labels();
+            write_map_len(&mut out, if labels.is_some() { 4 } else { 3 });
+
+            write_str(&mut out, "name");
+            match column.name {
+                Some(ref name) => write_str(&mut out, name),
+                None => out.push(0xc0),
+            }
+
+            write_str(&mut out, "dtype");
+            match column.dtype() {
+                Some(dtype) => write_str(&mut out, dtype.name()),
+                None => out.push(0xc0),
+            }
+
+            if let Some(labels) = labels {
+                write_str(&mut out, "categories");
+                write_array_len(&mut out, labels.len());
+                for label in labels {
+                    write_str(&mut out, label);
+                }
+            }
+
+            write_str(&mut out, "values");
+            write_array_len(&mut out, column.len());
+            for row in 0..column.len() {
+                if column.is_missing(row) {
+                    out.push(0xc0);
+                } else {
+                    write_value(&mut out, &column[row], column.dtype());
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Decodes a table written by `to_msgpack`.
+    ///
+    /// Values may be any MessagePack scalar, which are kept as their text,
+    /// and unknown keys are skipped. So tables built by other MessagePack
+    /// libraries in the same layout can be decoded.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The bytes are not MessagePack in the layout
+    ///   of a table, the columns have different lengths, or a column repeats
+    ///   a category label.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<DataTable, DataError> {
+        let mut reader = MsgpackReader { reader: SnapshotReader { bytes } };
+        let mut table = DataTable::empty();
+
+        for _ in 0..reader.read_map_len()? {
+            if reader.read_str()? != "columns" {
+                reader.skip(0)?;
+                continue;
+            }
+            for _ in 0..reader.read_array_len()? {
+                table.data_cols.push(reader.read_column()?);
+            }
+        }
+
+        let rows = table.data_cols.first().map_or(0, |c| c.len());
+        if !reader.reader.bytes.is_empty() || table.data_cols.iter().any(|c| c.len() != rows) {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(table)
+    }
+}
+
+/// Writes the value as the MessagePack type of its column, if this keeps its text.
+fn write_value(out: &mut Vec<u8>, value: &str, dtype: Option<DType>) {
+    match dtype {
+        Some(DType::Bool) if value == "true" => return out.push(0xc3),
+        Some(DType::Bool) if value == "false" => return out.push(0xc2),
+        Some(DType::I64) => {
+            if let Ok(v) = value.parse::<i64>() {
+                if v.to_string() == value {
+                    return write_int(out, v);
+                }
+            }
+        }
+        Some(DType::F64) => {
+            if let Ok(v) = value.parse::<f64>() {
+                if v.to_string() == value {
+                    out.push(0xcb);
+                    out.extend_from_slice(&v.to_bits().to_be_bytes());
+                    return;
+                }
+            }
+        }
+        _ => {}
+    }
+
+    write_str(out, value);
+}
+
+fn write_int(out: &mut Vec<u8>, v: i64) {
+    match v {
+        0..=0x7f => out.push(v as u8),
+        -32..=-1 => out.push(v as i8 as u8),
+        _ if v >= 0 && v <= u32::MAX as i64 => {
+            out.push(0xce);
+            out.extend_from_slice(&(v as u32).to_be_bytes());
+        }
+        _ if v >= i32::MIN as i64 && v < 0 => {
+            out.push(0xd2);
+            out.extend_from_slice(&(v as i32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xd3);
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    let len = s.len();
+    if len < 32 {
+        out.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        out.push(0xd9);
+        out.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(0xda);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0xdb);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_array_len(out: &mut Vec<u8>, len: usize) {
+    write_len(out, len, 0x90, 0xdc);
+}
+
+fn write_map_len(out: &mut Vec<u8>, len: usize) {
+    write_len(out, len, 0x80, 0xde);
+}
+
+/// Writes the length of an array or map, as a fix marker or one with a 16 or 32 bit length.
+fn write_len(out: &mut Vec<u8>, len: usize, fix: u8, marker16: u8) {
+    if len < 16 {
+        out.push(fix | len as u8);
+    } else if len <= u16::MAX as usize {
+        out.push(marker16);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(marker16 + 1);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+/// A MessagePack value which is not a collection.
+enum Scalar<'a> {
+    Nil,
+    Text(&'a str),
+    Other(String),
+}
+
+struct MsgpackReader<'a> {
+    reader: SnapshotReader<'a>,
+}
+
+impl<'a> MsgpackReader<'a> {
+    fn read_be(&mut self, len: usize) -> Result<u64, DataError> {
+        Ok(self.reader.take(len)?.iter().fold(0, |n, &b| n << 8 | b as u64))
+    }
+
+    fn read_column(&mut self) -> Result<DataColumn, DataError> {
+        let mut column = DataColumn::empty();
+        let mut dtype = None;
+        let mut categories = None;
+
+        for _ in 0..self.read_map_len()? {
+            match self.read_str()? {
+                "name" => {
+                    column.name = match self.read_scalar()? {
+                        Scalar::Nil => None,
+                        Scalar::Text(name) => Some(name.to_string()),
+                        Scalar::Other(_) => return Err(DataError::InvalidStateError),
+                    }
+                }
+                "dtype" => {
+                    dtype = match self.read_scalar()? {
+                        Scalar::Nil => None,
+                        Scalar::Text("bool") => Some(DType::Bool),
+                        Scalar::Text("i64") => Some(DType::I64),
+                        Scalar::Text("f64") => Some(DType::F64),
+                        Scalar::Text("string") => Some(DType::String),
+                        _ => return Err(DataError::InvalidStateError),
+                    }
+                }
+                "categories" => {
+                    let len = self.read_array_len()?;
+                    let labels: HashMap<String, usize> = (0..len)
+                        .map(|i| self.read_str().map(|label| (label.to_string(), i)))
+                        .collect::<Result<_, _>>()?;
+                    // A repeated label would leave an index without a label.
+                    if labels.len() != len {
+                        return Err(DataError::InvalidStateError);
+                    }
+                    categories = Some(labels);
+                }
+                "values" => {
+                    for _ in 0..self.read_array_len()? {
+                        match self.read_scalar()? {
+                            Scalar::Nil => column.push_missing(""),
+                            Scalar::Text(value) => column.push(value),
+                            Scalar::Other(value) => column.push(value),
+                        }
+                    }
+                }
+                _ => self.skip(0)?,
+            }
+        }
+
+        if let Some(categories) = categories {
+            column.set_categories(categories);
+        }
+        if let Some(dtype) = dtype {
+            column.set_dtype(dtype);
+        }
+
+        Ok(column)
+    }
+
+    fn read_scalar(&mut self) -> Result<Scalar<'a>, DataError> {
+        let marker = self.reader.read_u8()?;
+        let value = match marker {
+            0x00..=0x7f => Scalar::Other(marker.to_string()),
+            0xe0..=0xff => Scalar::Other((marker as i8).to_string()),
+            0xa0..=0xbf => return self.read_text((marker & 0x1f) as usize),
+            0xc0 => Scalar::Nil,
+            0xc2 => Scalar::Other("false".to_string()),
+            0xc3 => Scalar::Other("true".to_string()),
+            0xc4 | 0xd9 => {
+                let len = self.read_be(1)? as usize;
+                return self.read_text(len);
+            }
+            0xc5 | 0xda => {
+                let len = self.read_be(2)? as usize;
+                return self.read_text(len);
+            }
+            0xc6 | 0xdb => {
+                let len = self.read_be(4)? as usize;
+                return self.read_text(len);
+            }
+            0xca => Scalar::Other(f32::from_bits(self.read_be(4)? as u32).to_string()),
+            0xcb => Scalar::Other(f64::from_bits(self.read_be(8)?).to_string()),
+            0xcc..=0xcf => {
+                let len = 1 << (marker - 0xcc);
+                Scalar::Other(self.read_be(len)?.to_string())
+            }
+            0xd0 => Scalar::Other((self.read_be(1)? as u8 as i8).to_string()),
+            0xd1 => Scalar::Other((self.read_be(2)? as u16 as i16).to_string()),
+            0xd2 => Scalar::Other((self.read_be(4)? as u32 as i32).to_string()),
+            0xd3 => Scalar::Other((self.read_be(8)? as i64).to_string()),
+            _ => return Err(DataError::InvalidStateError),
+        };
+        Ok(value)
+    }
+
+    /// Reads a string, or binary data holding UTF-8, of the length.
+    fn read_text(&mut self, len: usize) -> Result<Scalar<'a>, DataError> {
+        let bytes = self.reader.take(len)?;
+        str::from_utf8(bytes).map(Scalar::Text).map_err(|_| DataError::InvalidStateError)
+    }
+
+    fn read_str(&mut self) -> Result<&'a str, DataError> {
+        match self.read_scalar()? {
+            Scalar::Text(s) => Ok(s),
+            _ => Err(DataError::InvalidStateError),
+        }
+    }
+
+    fn read_array_len(&mut self) -> Result<usize, DataError> {
+        let len = match self.reader.read_u8()? {
+            marker @ 0x90..=0x9f => (marker & 0x0f) as usize,
+            0xdc => self.read_be(2)? as usize,
+            0xdd => self.read_be(4)? as usize,
+            _ => return Err(DataError::InvalidStateError),
+        };
+        self.check_len(len)
+    }
+
+    fn read_map_len(&mut self) -> Result<usize, DataError> {
+        let len = match self.reader.read_u8()? {
+            marker @ 0x80..=0x8f => (marker & 0x0f) as usize,
+            0xde => self.read_be(2)? as usize,
+            0xdf => self.read_be(4)? as usize,
+            _ => return Err(DataError::InvalidStateError),
+        };
+        self.check_len(len)
+    }
+
+    /// Checks that enough bytes remain for the elements, each at least one byte.
+    fn check_len(&self, len: usize) -> Result<usize, DataError> {
+        if len > self.reader.bytes.len() {
+            return Err(DataError::InvalidStateError);
+        }
+        Ok(len)
+    }
+
+    /// Skips over the next value, which may be a collection.
+    fn skip(&mut self, depth: usize) -> Result<(), DataError> {
+        if depth > MAX_DEPTH {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let marker = *self.reader.bytes.first().ok_or(DataError::InvalidStateError)?;
+        match marker {
+            0x90..=0x9f | 0xdc | 0xdd => {
+                for _ in 0..self.read_array_len()? {
+                    self.skip(depth + 1)?;
+                }
+            }
+            0x80..=0x8f | 0xde | 0xdf => {
+                for _ in 0..self.read_map_len()? * 2 {
+                    self.skip(depth + 1)?;
+                }
+            }
+            _ => {
+                self.read_scalar()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,362 @@
+//! The npy module.
+//!
+//! Provides writing of DataTables as NumPy `.npy` and `.npz` files, so
+//! that results can be loaded in Python with `np.load`.
+//!
+//! Files use version 1.0 of the format with little endian values. An
+//! `.npz` file is a zip archive holding one uncompressed `.npy` file for
+//! each array.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+use std::str::FromStr;
+
+use datatable::{cast_value, DataColumn, DataTable};
+use error::{CastError, CellLocation, DataError};
+
+/// A type which can be written as the elements of a NumPy array.
+pub trait NpyElement: FromStr + Sized {
+    /// The NumPy type description, such as `<f8`.
+    const DESCR: &'static str;
+
+    /// The value written for missing values, if the type has one.
+    fn missing() -> Option<Self>;
+
+    /// Appends the little endian bytes of the value.
+    fn write_le(&self, out: &mut Vec<u8>);
+}
+
+impl NpyElement for f64 {
+    const DESCR: &'static str = "<f8";
+
+    /// Missing values are written as NaN.
+    fn missing() -> Option<f64> {
+        Some(f64::NAN)
+    }
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl NpyElement for f32 {
+    const DESCR: &'static str = "<f4";
+
+    /// Missing values are written as NaN.
+    fn missing() -> Option<f32> {
+        Some(f32::NAN)
+    }
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl NpyElement for i64 {
+    const DESCR: &'static str = "<i8";
+
+    fn missing() -> Option<i64> {
+        None
+    }
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl NpyElement for i32 {
+    const DESCR: &'static str = "<i4";
+
+    fn missing() -> Option<i32> {
+        None
+    }
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl NpyElement for u8 {
+    const DESCR: &'static str = "|u1";
+
+    fn missing() -> Option<u8> {
+        None
+    }
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+}
+
+impl NpyElement for bool {
+    const DESCR: &'static str = "|b1";
+
+    fn missing() -> Option<bool> {
+        None
+    }
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl DataTable {
+    /// Writes the table as a 2-D `.npy` array with a row for each row of the table.
+    ///
+    /// Each value is parsed as `T`. Missing values are written as NaN for
+    /// floats.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value does not parse as `T`, or is missing and
+    ///   `T` is not a float.
+    /// - Io : The writer failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "0.5"], vec!["2", "1.5"]];
+    /// let table = DataTable::from_records(Some(vec!["x", "y"]), records).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// table.write_npy::<f64, _>(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(&bytes[..6], b"\x93NUMPY");
+    /// assert_eq!(bytes.len(), 128 + 4 * 8);
+    /// ```
+    pub fn write_npy<T, W>(&self, writer: &mut W) -> Result<(), DataError>
+        where T: NpyElement,
+              T::Err: Error + Send + Sync + 'static,
+              W: Write
+    {
+        let shape = format!("({}, {})", self.rows(), self.cols());
+        let mut out = npy_header(T::DESCR, &shape);
+
+        for row in 0..self.rows() {
+            for (col, column) in self.data_cols.iter().enumerate() {
+                cell_value::<T>(column, row, col)?.write_le(&mut out);
+            }
+        }
+
+        writer.write_all(&out)?;
+        Ok(())
+    }
+
+    /// Writes the table to a `.npy` file, see `write_npy`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value does not parse as `T`, or is missing and
+    ///   `T` is not a float.
+    /// - Io : The file could not be written.
+    pub fn to_npy<T, P>(&self, path: P) -> Result<(), DataError>
+        where T: NpyElement,
+              T::Err: Error + Send + Sync + 'static,
+              P: AsRef<Path>
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_npy::<T, _>(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes each column as a 1-D array of an `.npz` archive.
+    ///
+    /// The arrays are named after their columns, or `arr_` followed by
+    /// their index for columns without a name, so `np.load(path)["x"]`
+    /// gives the column named `x`. Values are parsed as in `write_npy`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value does not parse as `T`, or is missing and
+    ///   `T` is not a float.
+    /// - InvalidStateError : Two columns have the same name, a name is
+    ///   longer than 65535 bytes, or the archive would be larger than 4GiB.
+    /// - Io : The writer failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "0.5"], vec!["2", "1.5"]];
+    /// let table = DataTable::from_records(Some(vec!["x", "yé"]), records).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// table.write_npz::<f64, _>(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(&bytes[..4], b"PK\x03\x04");
+    /// // The names are marked as UTF-8.
+    /// assert_eq!(&bytes[6..8], &[0x00, 0x08]);
+    ///
+    /// let long = "x".repeat(70000);
+    /// let table = DataTable::from_records(Some(vec![&long[..]]), vec![vec!["1"]]).unwrap();
+    /// assert!(table.write_npz::<f64, _>(&mut Vec::new()).is_err());
+    /// ```
+    pub fn write_npz<T, W>(&self, writer: &mut W) -> Result<(), DataError>
+        where T: NpyElement,
+              T::Err: Error + Send + Sync + 'static,
+              W: Write
+    {
+        let mut names: Vec<String> = Vec::with_capacity(self.cols());
+        for (col, column) in self.data_cols.iter().enumerate() {
+            let name = column.name.clone().unwrap_or_else(|| format!("arr_{}", col));
+            if names.contains(&name) {
+                return Err(DataError::InvalidStateError);
+            }
+            names.push(name);
+        }
+
+        let mut zip = ZipWriter::default();
+        for (col, (column, name)) in self.data_cols.iter().zip(names).enumerate() {
+            let mut out = npy_header(T::DESCR, &format!("({},)", column.len()));
+            for row in 0..column.len() {
+                cell_value::<T>(column, row, col)?.write_le(&mut out);
+            }
+            zip.add(&format!("{}.npy", name), &out, writer)?;
+        }
+
+        zip.finish(writer)
+    }
+
+    /// Writes the table to an `.npz` file, see `write_npz`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value does not parse as `T`, or is missing and
+    ///   `T` is not a float.
+    /// - InvalidStateError : Two columns have the same name, a name is
+    ///   longer than 65535 bytes, or the archive would be larger than 4GiB.
+    /// - Io : The file could not be written.
+    pub fn to_npz<T, P>(&self, path: P) -> Result<(), DataError>
+        where T: NpyElement,
+              T::Err: Error + Send + Sync + 'static,
+              P: AsRef<Path>
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_npz::<T, _>(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Parses the value in the row, or gives the missing value of `T`.
+pub(crate) fn cell_value<T>(column: &DataColumn, row: usize, col: usize) -> Result<T, DataError>
+    where T: NpyElement,
+          T::Err: Error + Send + Sync + 'static
+{
+    let location = || CellLocation {
+        row: Some(row),
+        column: Some(col),
+        column_name: column.name.clone(),
+    };
+
+    if column.is_missing(row) {
+        return T::missing().ok_or_else(|| {
+            DataError::DataCastError(CastError::rejected::<T>(&column[row]).at(location()))
+        });
+    }
+    cast_value(&column[row], location)
+}
+
+/// The magic string, version and header describing an array.
+///
+/// The header is padded so that the data starts on a multiple of 64 bytes.
+fn npy_header(descr: &str, shape: &str) -> Vec<u8> {
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+                             descr,
+                             shape);
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut out = b"\x93NUMPY\x01\x00".to_vec();
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    out
+}
+
+/// Writes a zip archive of uncompressed files.
+#[derive(Default)]
+struct ZipWriter {
+    central: Vec<u8>,
+    offset: u64,
+    entries: u16,
+}
+
+/// The date 1980-01-01 in the MS-DOS format used by zip.
+const DOS_DATE: u16 = (1 << 5) | 1;
+/// The flag marking a file name as UTF-8 rather than code page 437.
+const UTF8_NAME: u16 = 0x0800;
+
+impl ZipWriter {
+    fn add<W: Write>(&mut self, name: &str, data: &[u8], writer: &mut W) -> Result<(), DataError> {
+        if data.len() > u32::MAX as usize || self.offset > u32::MAX as u64 || self.entries == !0 {
+            return Err(DataError::InvalidStateError);
+        }
+        if name.len() > u16::MAX as usize {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let crc = crc32fast::hash(data);
+        let mut fields = Vec::new();
+        // Version 2.0, a UTF-8 name, stored, no time.
+        push_u16(&mut fields, 20);
+        push_u16(&mut fields, UTF8_NAME);
+        push_u16(&mut fields, 0);
+        push_u16(&mut fields, 0);
+        push_u16(&mut fields, DOS_DATE);
+        fields.extend_from_slice(&crc.to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        push_u16(&mut fields, name.len() as u16);
+        push_u16(&mut fields, 0);
+
+        let mut local = 0x0403_4b50u32.to_le_bytes().to_vec();
+        local.extend_from_slice(&fields);
+        local.extend_from_slice(name.as_bytes());
+        writer.write_all(&local)?;
+        writer.write_all(data)?;
+
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        push_u16(&mut self.central, 20);
+        self.central.extend_from_slice(&fields);
+        // No comment, disk 0, no attributes.
+        self.central.extend_from_slice(&[0; 10]);
+        self.central.extend_from_slice(&(self.offset as u32).to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+
+        self.offset += (local.len() + data.len()) as u64;
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn finish<W: Write>(self, writer: &mut W) -> Result<(), DataError> {
+        if self.offset > u32::MAX as u64 || self.central.len() > u32::MAX as usize {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut end = 0x0605_4b50u32.to_le_bytes().to_vec();
+        push_u16(&mut end, 0);
+        push_u16(&mut end, 0);
+        push_u16(&mut end, self.entries);
+        push_u16(&mut end, self.entries);
+        end.extend_from_slice(&(self.central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&(self.offset as u32).to_le_bytes());
+        push_u16(&mut end, 0);
+
+        writer.write_all(&self.central)?;
+        writer.write_all(&end)?;
+        Ok(())
+    }
+}
+
+fn push_u16(out: &mut Vec<u8>, val: u16) {
+    out.extend_from_slice(&val.to_le_bytes());
+}
@@ -0,0 +1,587 @@
+//! The online module.
+//!
+//! Provides statistics which are computed in one pass over a stream of
+//! values, so that a file can be summarised as it is loaded.
+//!
+//! The mean and variance use Welford's method, which stays accurate over
+//! long streams. Quantiles are estimated with a t-digest, which uses
+//! bounded memory however many values there are and is most accurate
+//! for the extreme quantiles such as p99.
+//!
+//! The number of distinct values is estimated with HyperLogLog, which
+//! also uses bounded memory.
+//!
+//! Statistics of separate chunks of a file can be merged to give the
+//! statistics of the whole file.
+
+use std::f64::consts::PI;
+use std::mem;
+
+use datatable::DataColumn;
+use error::DataError;
+use fingerprint::Fnv;
+use loader::Accumulator;
+
+/// The compression of a t-digest by default.
+const DEFAULT_COMPRESSION: f64 = 100.0;
+
+/// Statistics over the numbers in a stream of values.
+///
+/// As an `Accumulator`, values which are missing or blank are counted as
+/// missing, and values which are not numbers are counted as invalid. Both
+/// are otherwise ignored.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use rusty_data::loader::{Loader, LoaderOptions, SharedAccumulator};
+/// use rusty_data::online::OnlineStats;
+///
+/// let stats = Arc::new(Mutex::new(OnlineStats::new()));
+/// let mut options = LoaderOptions {
+///     has_header: true,
+///     na_tokens: vec!["NA".to_string()],
+///     ..LoaderOptions::default()
+/// };
+/// let accumulator: SharedAccumulator = stats.clone();
+/// options.column_accumulators.insert("score".to_string(), vec![accumulator]);
+///
+/// let data = "id,score\n1,2.0\n2,4.0\n3,NA\n4,9.0\n";
+/// let table = Loader::from_str(data, options).load_file().unwrap();
+///
+/// let stats = stats.lock().unwrap();
+/// assert_eq!(table.rows(), 4);
+/// assert_eq!(stats.count(), 3);
+/// assert_eq!(stats.missing(), 1);
+/// assert_eq!(stats.mean(), Some(5.0));
+/// assert_eq!(stats.max(), Some(9.0));
+/// assert_eq!(stats.quantile(0.5), Some(4.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct OnlineStats {
+    count: usize,
+    missing: usize,
+    invalid: usize,
+    mean: f64,
+    // The sum of squared differences from the mean.
+    m2: f64,
+    min: f64,
+    max: f64,
+    digest: TDigest,
+}
+
+impl OnlineStats {
+    /// Creates statistics over no values.
+    pub fn new() -> OnlineStats {
+        OnlineStats::with_compression(DEFAULT_COMPRESSION)
+    }
+
+    /// Creates statistics which estimate quantiles with the compression, see `TDigest`.
+    pub fn with_compression(compression: f64) -> OnlineStats {
+        OnlineStats {
+            count: 0,
+            missing: 0,
+            invalid: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            digest: TDigest::with_compression(compression),
+        }
+    }
+
+    /// Adds a number. NaN is counted as invalid.
+    pub fn push(&mut self, value: f64) {
+        if value.is_nan() {
+            self.invalid += 1;
+            return;
+        }
+
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.digest.push(value);
+    }
+
+    /// Adds the values counted by `other`, as if they had been pushed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::online::OnlineStats;
+    ///
+    /// let mut first = OnlineStats::new();
+    /// let mut second = OnlineStats::new();
+    /// for v in 0..50 {
+    ///     first.push(v as f64);
+    ///     second.push((v + 50) as f64);
+    /// }
+    ///
+    /// first.merge(&second);
+    ///
+    /// assert_eq!(first.count(), 100);
+    /// assert_eq!(first.mean(), Some(49.5));
+    /// assert_eq!(first.max(), Some(99.0));
+    /// ```
+    pub fn merge(&mut self, other: &OnlineStats) {
+        self.missing += other.missing;
+        self.invalid += other.invalid;
+        if other.count == 0 {
+            return;
+        }
+
+        // Combines the sums of squares as in Chan's parallel algorithm.
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * weight;
+        self.mean += delta * weight;
+        self.count = count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.digest.merge(&other.digest);
+    }
+
+    /// The number of numbers added.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The number of missing values added.
+    pub fn missing(&self) -> usize {
+        self.missing
+    }
+
+    /// The number of values added which were not numbers.
+    pub fn invalid(&self) -> usize {
+        self.invalid
+    }
+
+    /// The mean of the numbers, or `None` if there are none.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 { None } else { Some(self.mean) }
+    }
+
+    /// The sample variance of the numbers, or `None` if there are fewer than two.
+    pub fn variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            None
+        } else {
+            Some(self.m2 / (self.count - 1) as f64)
+        }
+    }
+
+    /// The sample standard deviation of the numbers, or `None` if there are fewer than two.
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// The smallest number, or `None` if there are none.
+    pub fn min(&self) -> Option<f64> {
+        if self.count == 0 { None } else { Some(self.min) }
+    }
+
+    /// The largest number, or `None` if there are none.
+    pub fn max(&self) -> Option<f64> {
+        if self.count == 0 { None } else { Some(self.max) }
+    }
+
+    /// Estimates the `q` quantile of the numbers, see `TDigest::quantile`.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.digest.quantile(q)
+    }
+}
+
+impl Default for OnlineStats {
+    fn default() -> OnlineStats {
+        OnlineStats::new()
+    }
+}
+
+impl Accumulator for OnlineStats {
+    fn update(&mut self, value: Option<&str>) {
+        match value.map(str::trim) {
+            None | Some("") => self.missing += 1,
+            Some(value) => {
+                match value.parse() {
+                    Ok(value) => self.push(value),
+                    Err(_) => self.invalid += 1,
+                }
+            }
+        }
+    }
+}
+
+/// A group of nearby values within a `TDigest`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A t-digest, which estimates the quantiles of a stream of numbers.
+///
+/// The numbers are summarised by the means of groups of nearby numbers.
+/// Groups are kept small near the ends of the distribution, so that
+/// extreme quantiles are estimated more accurately than the median.
+/// Estimates are exact while there are only a few numbers.
+///
+/// Higher compression keeps more groups, using more memory and giving
+/// more accurate estimates. There are at most around `compression`
+/// groups, and the default of 100 usually estimates quantiles to within
+/// 0.1% of their rank.
+///
+/// See Dunning and Ertl, "Computing Extremely Accurate Quantiles Using
+/// t-Digests".
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::online::TDigest;
+///
+/// let mut digest = TDigest::new();
+/// for v in 0..100_000 {
+///     digest.push(v as f64);
+/// }
+///
+/// let p99 = digest.quantile(0.99).unwrap();
+///
+/// assert!((p99 - 99_000.0).abs() < 100.0);
+/// assert_eq!(digest.quantile(0.0), Some(0.0));
+/// assert_eq!(digest.count(), 100_000);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    // Numbers and centroids added since the centroids were last merged.
+    buffer: Vec<Centroid>,
+    count: usize,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    /// Creates a digest of no numbers with the default compression of 100.
+    pub fn new() -> TDigest {
+        TDigest::with_compression(DEFAULT_COMPRESSION)
+    }
+
+    /// Creates a digest of no numbers with the compression.
+    ///
+    /// Compressions below 10 are raised to 10.
+    pub fn with_compression(compression: f64) -> TDigest {
+        TDigest {
+            compression: compression.max(10.0),
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Adds a number. NaN is ignored.
+    pub fn push(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(Centroid { mean: value, weight: 1.0 });
+        if self.buffer.len() >= self.buffer_limit() {
+            self.compress();
+        }
+    }
+
+    /// Adds the numbers summarised by `other`, as if they had been pushed.
+    pub fn merge(&mut self, other: &TDigest) {
+        if other.count == 0 {
+            return;
+        }
+
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.buffer.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        if self.buffer.len() >= self.buffer_limit() {
+            self.compress();
+        }
+    }
+
+    /// The number of numbers added.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Estimates the `q` quantile of the numbers.
+    ///
+    /// The estimate interpolates between the means of neighbouring groups,
+    /// and is never below the smallest number or above the largest.
+    /// Returns `None` if there are no numbers or `q` is not between 0 and 1.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        if !self.buffer.is_empty() {
+            let mut merged = self.clone();
+            merged.compress();
+            return merged.quantile(q);
+        }
+
+        let centroids = &self.centroids;
+        let total = self.count as f64;
+        let index = q * total;
+
+        // The middle of each centroid is taken as the rank of its mean.
+        let first = centroids[0];
+        if index < first.weight / 2.0 {
+            return Some(self.min + (first.mean - self.min) * index / (first.weight / 2.0));
+        }
+
+        let mut rank = first.weight / 2.0;
+        for pair in centroids.windows(2) {
+            let step = (pair[0].weight + pair[1].weight) / 2.0;
+            if rank + step > index {
+                let t = (index - rank) / step;
+                return Some(pair[0].mean + (pair[1].mean - pair[0].mean) * t);
+            }
+            rank += step;
+        }
+
+        let last = centroids[centroids.len() - 1];
+        let t = if total > rank { (index - rank) / (total - rank) } else { 1.0 };
+        Some((last.mean + (self.max - last.mean) * t).min(self.max))
+    }
+
+    /// Estimates the median of the numbers.
+    pub fn median(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    /// The number of centroids added before they are merged.
+    fn buffer_limit(&self) -> usize {
+        (self.compression * 5.0) as usize
+    }
+
+    /// Merges the buffer into the centroids.
+    fn compress(&mut self) {
+        let mut items = mem::take(&mut self.centroids);
+        items.append(&mut self.buffer);
+        items.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total = self.count as f64;
+        let mut merged = Vec::with_capacity(self.compression as usize);
+        let mut current = items[0];
+        let mut before = 0.0;
+        let mut limit = total * self.quantile_limit(0.0);
+
+        for item in items.into_iter().skip(1) {
+            if before + current.weight + item.weight <= limit {
+                current.weight += item.weight;
+                current.mean += (item.mean - current.mean) * item.weight / current.weight;
+            } else {
+                before += current.weight;
+                merged.push(current);
+                limit = total * self.quantile_limit(before / total);
+                current = item;
+            }
+        }
+        merged.push(current);
+
+        self.centroids = merged;
+    }
+
+    /// The largest quantile which a centroid starting at quantile `q` may reach.
+    ///
+    /// Uses the scale function `k(q) = compression / 2pi * asin(2q - 1)`,
+    /// allowing each centroid to span one unit of `k`.
+    fn quantile_limit(&self, q: f64) -> f64 {
+        let k = self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin() + 1.0;
+        if k >= self.compression / 4.0 {
+            1.0
+        } else {
+            ((k * 2.0 * PI / self.compression).sin() + 1.0) / 2.0
+        }
+    }
+}
+
+impl Default for TDigest {
+    fn default() -> TDigest {
+        TDigest::new()
+    }
+}
+
+impl Accumulator for TDigest {
+    /// Adds values which parse as numbers, ignoring any others.
+    fn update(&mut self, value: Option<&str>) {
+        if let Some(Ok(value)) = value.map(|v| v.trim().parse()) {
+            self.push(value);
+        }
+    }
+}
+
+/// The precision of a HyperLogLog by default.
+const DEFAULT_PRECISION: u8 = 14;
+
+/// A HyperLogLog, which estimates the number of distinct values in a stream.
+///
+/// A precision of `p` keeps `2^p` single byte registers, and gives a
+/// relative error of around `1.04 / sqrt(2^p)`. The default precision of
+/// 14 uses 16KiB and has a standard error of around 0.8%.
+///
+/// Values are hashed with a stable hash, so estimates are the same on
+/// every run, and sketches built separately can be merged.
+///
+/// See Flajolet et al., "HyperLogLog: the analysis of a near-optimal
+/// cardinality estimation algorithm".
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::online::HyperLogLog;
+///
+/// let mut sketch = HyperLogLog::new();
+/// for v in 0..100_000 {
+///     sketch.insert(&(v % 5_000).to_string());
+/// }
+///
+/// let estimate = sketch.estimate() as f64;
+///
+/// assert!((estimate - 5_000.0).abs() < 100.0);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Creates a sketch of no values with the default precision of 14.
+    pub fn new() -> HyperLogLog {
+        HyperLogLog::with_precision(DEFAULT_PRECISION)
+    }
+
+    /// Creates a sketch of no values with the precision.
+    ///
+    /// The precision is clamped to between 4 and 18.
+    pub fn with_precision(precision: u8) -> HyperLogLog {
+        let precision = precision.clamp(4, 18);
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Adds a value.
+    pub fn insert(&mut self, value: &str) {
+        let mut hasher = Fnv::new();
+        hasher.write(value.as_bytes());
+        let hash = mix(hasher.0);
+
+        // The first bits choose a register, which keeps the longest run of
+        // leading zeros seen in the remaining bits.
+        let register = (hash >> (64 - self.precision)) as usize;
+        let rest = hash << self.precision;
+        let rank = (rest.leading_zeros() as u8 + 1).min(64 - self.precision + 1);
+        if rank > self.registers[register] {
+            self.registers[register] = rank;
+        }
+    }
+
+    /// Adds the values counted by `other`, as if they had been inserted.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The sketches have different precisions.
+    pub fn merge(&mut self, other: &HyperLogLog) -> Result<(), DataError> {
+        if self.precision != other.precision {
+            return Err(DataError::InvalidStateError);
+        }
+
+        for (register, &theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(theirs);
+        }
+        Ok(())
+    }
+
+    /// Estimates the number of distinct values added.
+    pub fn estimate(&self) -> usize {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum: f64 = self.registers.iter().map(|&r| (-(r as f64)).exp2()).sum();
+        let estimate = alpha * m * m / sum;
+
+        // Small counts are estimated more accurately from the empty registers.
+        let empty = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && empty > 0 {
+            (m * (m / empty as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> HyperLogLog {
+        HyperLogLog::new()
+    }
+}
+
+impl Accumulator for HyperLogLog {
+    /// Adds values which are not missing.
+    fn update(&mut self, value: Option<&str>) {
+        if let Some(value) = value {
+            self.insert(value);
+        }
+    }
+}
+
+/// Spreads the bits of an FNV hash, whose high bits depend weakly on the
+/// last bytes hashed. This is the finalizer of MurmurHash3.
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+impl DataColumn {
+    /// Estimates the number of distinct values in the column, see `HyperLogLog`.
+    ///
+    /// Values are compared as strings and missing values are not counted.
+    /// This uses 16KiB however many distinct values there are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec!["a", "b", "a", "c"]);
+    ///
+    /// assert_eq!(dc.approx_n_unique(), 3);
+    /// ```
+    pub fn approx_n_unique(&self) -> usize {
+        let mut sketch = HyperLogLog::new();
+        for (idx, val) in self.iter().enumerate() {
+            if !self.is_missing(idx) {
+                sketch.insert(val);
+            }
+        }
+        sketch.estimate()
+    }
+}
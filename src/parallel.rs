@@ -0,0 +1,368 @@
+//! The parallel module.
+//!
+//! Provides methods for running computations over the rows and
+//! columns of a DataTable on multiple threads, and the SharedTable
+//! for sharing a table between threads.
+
+use std::cmp;
+use std::ops::Deref;
+use std::panic;
+use std::sync::Arc;
+use std::thread;
+
+#[cfg(feature = "rayon")]
+use std::ops::Range;
+
+#[cfg(feature = "rayon")]
+use rayon::iter::plumbing::{Consumer, ProducerCallback, UnindexedConsumer};
+#[cfg(feature = "rayon")]
+use rayon::iter::Map;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "rayon")]
+use rayon::range::Iter as RangeIter;
+
+#[cfg(feature = "rayon")]
+use datatable::RowIter;
+use datatable::{DataColumn, DataTable, Row};
+
+/// The number of threads to split work across.
+fn thread_count(items: usize) -> usize {
+    let available = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    cmp::max(1, cmp::min(available, items))
+}
+
+// Tables must stay shareable between threads, this fails to compile otherwise.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<DataTable>();
+    assert_send_sync::<DataColumn>();
+    assert_send_sync::<SharedTable>();
+};
+
+/// A read-only DataTable which can be shared between threads.
+///
+/// Cloning a SharedTable only clones an `Arc`, so every clone reads
+/// the same data. The table is accessed through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+///
+/// use rusty_data::datatable::{DataColumn, DataTable};
+///
+/// let column: DataColumn = vec!["1", "2"].into_iter().collect();
+/// let shared = DataTable { data_cols: vec![column] }.into_shared();
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let shared = shared.clone();
+///         thread::spawn(move || shared.rows())
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     assert_eq!(handle.join().unwrap(), 2);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedTable {
+    table: Arc<DataTable>,
+}
+
+impl SharedTable {
+    /// Constructs a SharedTable from the table.
+    pub fn new(table: DataTable) -> SharedTable {
+        SharedTable { table: Arc::new(table) }
+    }
+
+    /// The number of SharedTables reading this table.
+    pub fn share_count(&self) -> usize {
+        Arc::strong_count(&self.table)
+    }
+
+    /// Returns true if both SharedTables read the same table.
+    pub fn ptr_eq(&self, other: &SharedTable) -> bool {
+        Arc::ptr_eq(&self.table, &other.table)
+    }
+
+    /// Gets back the table if this is the only SharedTable reading it.
+    ///
+    /// # Failures
+    ///
+    /// - Returns the SharedTable unchanged if it has been cloned.
+    pub fn try_unwrap(self) -> Result<DataTable, SharedTable> {
+        Arc::try_unwrap(self.table).map_err(|table| SharedTable { table })
+    }
+}
+
+impl Deref for SharedTable {
+    type Target = DataTable;
+
+    fn deref(&self) -> &DataTable {
+        &self.table
+    }
+}
+
+impl From<DataTable> for SharedTable {
+    fn from(table: DataTable) -> SharedTable {
+        SharedTable::new(table)
+    }
+}
+
+impl DataTable {
+    /// Moves the table into a SharedTable.
+    pub fn into_shared(self) -> SharedTable {
+        SharedTable::new(self)
+    }
+
+    /// Applies the function to every row, splitting the rows across threads.
+    ///
+    /// The results are returned in row order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{DataColumn, DataTable};
+    ///
+    /// let mut table = DataTable::empty();
+    /// for _ in 0..2 {
+    ///     let mut column = DataColumn::empty();
+    ///     for r in 0..1000 {
+    ///         column.push(r.to_string());
+    ///     }
+    ///     table.data_cols.push(column);
+    /// }
+    ///
+    /// let sums = table.par_map_rows(|row| {
+    ///     row.get_as::<f64>(0).unwrap() + row.get_as::<f64>(1).unwrap()
+    /// });
+    ///
+    /// assert_eq!(sums.len(), 1000);
+    /// assert_eq!(sums[10], 20.0);
+    /// ```
+    pub fn par_map_rows<F, T>(&self, f: F) -> Vec<T>
+        where F: Fn(Row) -> T + Sync,
+              T: Send
+    {
+        let rows = self.rows();
+        let threads = thread_count(rows);
+        let chunk = rows.div_ceil(threads);
+        let f = &f;
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let start = cmp::min(rows, t * chunk);
+                    let end = cmp::min(rows, start + chunk);
+
+                    scope.spawn(move || {
+                        (start..end).map(|i| f(self.row(i).expect("row is in range"))).collect::<Vec<T>>()
+                    })
+                })
+                .collect();
+
+            let mut results = Vec::with_capacity(rows);
+            for handle in handles {
+                results.extend(handle.join().unwrap_or_else(|e| panic::resume_unwind(e)));
+            }
+            results
+        })
+    }
+
+    /// Applies the function to every column, splitting the columns across threads.
+    ///
+    /// The results are returned in column order.
+    pub fn par_map_columns<F, T>(&self, f: F) -> Vec<T>
+        where F: Fn(&DataColumn) -> T + Sync,
+              T: Send
+    {
+        let cols = self.cols();
+        let threads = thread_count(cols);
+        let chunk = cols.div_ceil(threads);
+        let f = &f;
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = self.data_cols
+                .chunks(cmp::max(1, chunk))
+                .map(|columns| scope.spawn(move || columns.iter().map(f).collect::<Vec<T>>()))
+                .collect();
+
+            let mut results = Vec::with_capacity(cols);
+            for handle in handles {
+                results.extend(handle.join().unwrap_or_else(|e| panic::resume_unwind(e)));
+            }
+            results
+        })
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl DataTable {
+    /// Returns a rayon parallel iterator over views of each row in the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate rayon;
+    /// extern crate rusty_data;
+    ///
+    /// use rayon::prelude::*;
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// # fn main() {
+    /// let records: Vec<Vec<String>> = (0..1000)
+    ///     .map(|r| vec![r.to_string(), (2 * r).to_string()])
+    ///     .collect();
+    /// let table = DataTable::from_records(None::<Vec<&str>>, records).unwrap();
+    ///
+    /// let total: f64 = table.par_rows()
+    ///     .map(|row| row.get_as::<f64>(0).unwrap() + row.get_as::<f64>(1).unwrap())
+    ///     .sum();
+    /// assert_eq!(total, 3.0 * 999.0 * 1000.0 / 2.0);
+    ///
+    /// let rows: Vec<usize> = table.iter_rows().into_par_iter().map(|row| row.index()).collect();
+    /// assert_eq!(rows[10], 10);
+    /// # }
+    /// ```
+    pub fn par_rows(&self) -> ParRows<'_> {
+        self.iter_rows().into_par_iter()
+    }
+
+    /// Returns a rayon parallel iterator over the columns of the table.
+    pub fn par_columns(&self) -> rayon::slice::Iter<'_, DataColumn> {
+        self.data_cols.par_iter()
+    }
+}
+
+/// A rayon parallel iterator over the rows of a DataTable.
+#[cfg(feature = "rayon")]
+pub struct ParRows<'a> {
+    table: &'a DataTable,
+    range: Range<usize>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> ParRows<'a> {
+    /// The rows as an indexed rayon iterator, which the trait methods delegate to.
+    fn rows(self) -> Map<RangeIter<usize>, impl Fn(usize) -> Row<'a> + Sync + Send> {
+        let table = self.table;
+        self.range.into_par_iter().map(move |i| table.row(i).expect("row is in range"))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> IntoParallelIterator for RowIter<'a> {
+    type Iter = ParRows<'a>;
+    type Item = Row<'a>;
+
+    fn into_par_iter(self) -> ParRows<'a> {
+        ParRows {
+            table: self.table,
+            range: self.next..self.end,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> ParallelIterator for ParRows<'a> {
+    type Item = Row<'a>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where C: UnindexedConsumer<Row<'a>>
+    {
+        self.rows().drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.range.len())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> IndexedParallelIterator for ParRows<'a> {
+    fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    fn drive<C: Consumer<Row<'a>>>(self, consumer: C) -> C::Result {
+        self.rows().drive(consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Row<'a>>>(self, callback: CB) -> CB::Output {
+        self.rows().with_producer(callback)
+    }
+}
+
+/// A rayon parallel iterator over the values in a DataColumn.
+///
+/// # Examples
+///
+/// ```
+/// extern crate rayon;
+/// extern crate rusty_data;
+///
+/// use rayon::prelude::*;
+/// use rusty_data::datatable::DataColumn;
+///
+/// # fn main() {
+/// let column: DataColumn = vec!["ann", "bob", "cy"].into_iter().collect();
+///
+/// let lengths: Vec<usize> = (&column).into_par_iter().map(str::len).collect();
+/// assert_eq!(lengths, vec![3, 3, 2]);
+/// # }
+/// ```
+#[cfg(feature = "rayon")]
+pub struct ParValues<'a> {
+    column: &'a DataColumn,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> ParValues<'a> {
+    /// The values as an indexed rayon iterator, which the trait methods delegate to.
+    fn values(self) -> Map<RangeIter<usize>, impl Fn(usize) -> &'a str + Sync + Send> {
+        let column = self.column;
+        (0..column.len()).into_par_iter().map(move |i| column.get(i).expect("value is in range"))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> IntoParallelIterator for &'a DataColumn {
+    type Iter = ParValues<'a>;
+    type Item = &'a str;
+
+    fn into_par_iter(self) -> ParValues<'a> {
+        ParValues { column: self }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> ParallelIterator for ParValues<'a> {
+    type Item = &'a str;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where C: UnindexedConsumer<&'a str>
+    {
+        self.values().drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.column.len())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a> IndexedParallelIterator for ParValues<'a> {
+    fn len(&self) -> usize {
+        self.column.len()
+    }
+
+    fn drive<C: Consumer<&'a str>>(self, consumer: C) -> C::Result {
+        self.values().drive(consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<&'a str>>(self, callback: CB) -> CB::Output {
+        self.values().with_producer(callback)
+    }
+}
@@ -0,0 +1,128 @@
+//! The parse module.
+//!
+//! Provides helpers which clean up formatted values, such as amounts
+//! of money and percentages, so that they can be parsed as numbers.
+
+use std::borrow::Cow;
+
+/// The currency symbols removed by `strip_currency`.
+pub const CURRENCY_SYMBOLS: &str = "$€£¥¢₹₩₽₺₪₫฿₴₦";
+
+/// Strips currency formatting from the value.
+///
+/// Currency symbols and whitespace are removed and a value wrapped in
+/// parentheses is made negative, as in accounting formats. `,` separators
+/// are only removed when the digits are grouped in threes, as in
+/// `strip_thousands`, so other uses of `,` are left to fail parsing.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::parse::strip_currency;
+///
+/// assert_eq!(strip_currency("(1,234.50)"), "-1234.50");
+/// assert_eq!(strip_currency("$ 12"), "12");
+/// assert_eq!(strip_currency("-£3.20"), "-3.20");
+/// assert_eq!(strip_currency("€1,5"), "1,5");
+/// assert_eq!(strip_currency("1,2,3"), "1,2,3");
+/// ```
+pub fn strip_currency(value: &str) -> Cow<'_, str> {
+    match strip(value, |c| CURRENCY_SYMBOLS.contains(c)) {
+        Cow::Borrowed(stripped) => strip_thousands(stripped, ','),
+        Cow::Owned(stripped) => {
+            let cleaned = match strip_thousands(&stripped, ',') {
+                Cow::Owned(cleaned) => Some(cleaned),
+                Cow::Borrowed(_) => None,
+            };
+            Cow::Owned(cleaned.unwrap_or(stripped))
+        }
+    }
+}
+
+/// Strips a percent sign from the value.
+///
+/// The number is kept as written, so `"12.5%"` becomes `"12.5"`. A value
+/// wrapped in parentheses is made negative.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::parse::strip_percent;
+///
+/// assert_eq!(strip_percent("12.5%"), "12.5");
+/// assert_eq!(strip_percent("(3 %)"), "-3");
+/// ```
+pub fn strip_percent(value: &str) -> Cow<'_, str> {
+    strip(value, |c| c == '%')
+}
+
+/// Strips thousands separators from a number.
+///
+/// The separator is only removed when the digits are grouped in threes,
+/// otherwise the value is returned unchanged so that it fails to parse
+/// rather than silently becoming a different number. When the separator
+/// is `.` the decimal mark is taken to be `,` and is replaced with `.`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::parse::strip_thousands;
+///
+/// assert_eq!(strip_thousands("1,234,567", ','), "1234567");
+/// assert_eq!(strip_thousands("-1 234.5", ' '), "-1234.5");
+/// assert_eq!(strip_thousands("1.234,5", '.'), "1234.5");
+/// assert_eq!(strip_thousands("12,34", ','), "12,34");
+/// ```
+pub fn strip_thousands(value: &str, separator: char) -> Cow<'_, str> {
+    let decimal = if separator == '.' { ',' } else { '.' };
+    let (int_part, frac_part) = match value.find(decimal) {
+        Some(idx) => (&value[..idx], Some(&value[idx + decimal.len_utf8()..])),
+        None => (value, None),
+    };
+
+    if !int_part.contains(separator) && (decimal == '.' || frac_part.is_none()) {
+        return Cow::Borrowed(value);
+    }
+
+    let digits = int_part.trim_start_matches(['-', '+']);
+    let mut groups = digits.split(separator);
+    let first = groups.next().unwrap_or("");
+
+    let grouped = !first.is_empty() && first.len() <= 3 &&
+                  groups.all(|g| g.len() == 3 && g.bytes().all(|b| b.is_ascii_digit()));
+
+    if !grouped {
+        return Cow::Borrowed(value);
+    }
+
+    let mut cleaned: String = int_part.chars().filter(|&c| c != separator).collect();
+    if let Some(frac) = frac_part {
+        cleaned.push('.');
+        cleaned.push_str(frac);
+    }
+
+    Cow::Owned(cleaned)
+}
+
+/// Removes whitespace and the characters matching `remove`, and converts
+/// parentheses to a leading minus sign.
+fn strip<F: Fn(char) -> bool>(value: &str, remove: F) -> Cow<'_, str> {
+    let trimmed = value.trim();
+    let (negative, inner) = if trimmed.starts_with('(') && trimmed.ends_with(')') {
+        (true, &trimmed[1..trimmed.len() - 1])
+    } else {
+        (false, trimmed)
+    };
+
+    if !negative && !inner.chars().any(|c| c.is_whitespace() || remove(c)) {
+        return Cow::Borrowed(inner);
+    }
+
+    let mut cleaned = String::with_capacity(inner.len() + 1);
+    if negative {
+        cleaned.push('-');
+    }
+    cleaned.extend(inner.chars().filter(|&c| !c.is_whitespace() && !remove(c)));
+
+    Cow::Owned(cleaned)
+}
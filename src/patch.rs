@@ -0,0 +1,334 @@
+//! The patch module.
+//!
+//! Provides the Patch which holds the changes turning one version of a
+//! DataTable into another, so that a large table can be kept in sync by
+//! sending only what changed.
+//!
+//! A patch is written as a magic number, the shape of the table it
+//! applies to and its operations. Each operation is a tag byte followed
+//! by its row, then its column and value for an update or the row's
+//! values for an insert. Values are a flag byte, zero when missing,
+//! followed by their length and bytes. All integers are little endian.
+
+use compare::cell;
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use snapshot::{write_u64, SnapshotReader};
+
+const MAGIC: &[u8; 8] = b"RDPATCH1";
+
+/// A change made by a Patch.
+///
+/// Rows refer to the table as it is when the operation is applied, after
+/// the operations before it. Values are `None` when missing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchOp {
+    /// Sets the value in the row and column.
+    Update {
+        /// The row of the value.
+        row: usize,
+        /// The index of the column.
+        column: usize,
+        /// The new value.
+        value: Option<String>,
+    },
+    /// Inserts a row before `row`, shifting later rows along.
+    Insert {
+        /// The index of the new row.
+        row: usize,
+        /// One value for each column.
+        values: Vec<Option<String>>,
+    },
+    /// Deletes the row, shifting later rows back.
+    Delete {
+        /// The index of the row.
+        row: usize,
+    },
+}
+
+/// The changes turning one table into another, see `DataTable::diff_patch`.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::DataTable;
+/// use rusty_data::patch::{Patch, PatchOp};
+///
+/// let records = vec![vec!["a", "1"], vec!["b", "2"], vec!["c", "3"]];
+/// let old = DataTable::from_records(Some(vec!["id", "v"]), records).unwrap();
+/// let records = vec![vec!["a", "1"], vec!["b", "5"], vec!["x", "0"], vec!["c", "3"]];
+/// let new = DataTable::from_records(Some(vec!["id", "v"]), records).unwrap();
+///
+/// let patch = old.diff_patch(&new).unwrap();
+/// assert_eq!(patch.ops()[0], PatchOp::Update { row: 1, column: 1, value: Some("5".into()) });
+///
+/// // Send the patch to another copy of the old table.
+/// let received = Patch::from_bytes(&patch.to_bytes()).unwrap();
+///
+/// let mut copy = old.clone();
+/// copy.apply_patch(&received).unwrap();
+/// assert!(copy.compare(&new).is_empty());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch {
+    cols: usize,
+    rows: usize,
+    ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    /// The operations of the patch, in the order they are applied.
+    pub fn ops(&self) -> &[PatchOp] {
+        &self.ops
+    }
+
+    /// The number of operations in the patch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns true if the patch makes no changes.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Writes the patch in its binary form.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        push_u64(&mut bytes, self.cols);
+        push_u64(&mut bytes, self.rows);
+        push_u64(&mut bytes, self.ops.len());
+
+        for op in self.ops.iter() {
+            match *op {
+                PatchOp::Update { row, column, ref value } => {
+                    bytes.push(0);
+                    push_u64(&mut bytes, row);
+                    push_u64(&mut bytes, column);
+                    push_value(&mut bytes, value);
+                }
+                PatchOp::Insert { row, ref values } => {
+                    bytes.push(1);
+                    push_u64(&mut bytes, row);
+                    for value in values {
+                        push_value(&mut bytes, value);
+                    }
+                }
+                PatchOp::Delete { row } => {
+                    bytes.push(2);
+                    push_u64(&mut bytes, row);
+                }
+            }
+        }
+
+        bytes
+    }
+
+    /// Reads a patch written by `to_bytes`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The bytes are not a valid patch.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Patch, DataError> {
+        let mut reader = SnapshotReader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let cols = reader.read_len()?;
+        let rows = reader.read_len()?;
+        let count = reader.read_len()?;
+        let mut ops = Vec::with_capacity(count.min(reader.bytes.len() / 9));
+
+        for _ in 0..count {
+            let tag = reader.read_u8()?;
+            let row = reader.read_len()?;
+
+            ops.push(match tag {
+                0 => {
+                    let column = reader.read_len()?;
+                    PatchOp::Update { row, column, value: read_value(&mut reader)? }
+                }
+                1 => {
+                    let values = (0..cols)
+                        .map(|_| read_value(&mut reader))
+                        .collect::<Result<_, _>>()?;
+                    PatchOp::Insert { row, values }
+                }
+                2 => PatchOp::Delete { row },
+                _ => return Err(DataError::InvalidStateError),
+            });
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(Patch { cols, rows, ops })
+    }
+}
+
+impl DataTable {
+    /// Builds the patch which turns this table into `new`.
+    ///
+    /// Rows shared at the start and end of both tables are left alone.
+    /// The rows between are updated cell by cell, and rows are inserted
+    /// or deleted where the tables differ in length. Missing values are
+    /// compared and written without their text.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The tables do not have the same column names.
+    pub fn diff_patch(&self, new: &DataTable) -> Result<Patch, DataError> {
+        let names = |t: &DataTable| t.data_cols.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        if names(self) != names(new) {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let (old_rows, new_rows) = (self.rows(), new.rows());
+        let same = |old: usize, new_row: usize| {
+            self.data_cols
+                .iter()
+                .zip(new.data_cols.iter())
+                .all(|(a, b)| cell(a, old) == cell(b, new_row))
+        };
+
+        let shared = old_rows.min(new_rows);
+        let prefix = (0..shared).take_while(|&r| same(r, r)).count();
+        let suffix = (1..shared - prefix + 1)
+            .take_while(|&r| same(old_rows - r, new_rows - r))
+            .count();
+
+        let old_mid = old_rows - prefix - suffix;
+        let new_mid = new_rows - prefix - suffix;
+        let mut ops = Vec::new();
+
+        for row in prefix..prefix + old_mid.min(new_mid) {
+            for (column, (a, b)) in self.data_cols.iter().zip(new.data_cols.iter()).enumerate() {
+                let value = cell(b, row);
+                if cell(a, row) != value {
+                    ops.push(PatchOp::Update { row, column, value: value.map(String::from) });
+                }
+            }
+        }
+
+        let end = prefix + old_mid.min(new_mid);
+        for _ in new_mid..old_mid {
+            ops.push(PatchOp::Delete { row: end });
+        }
+        for row in end..prefix + new_mid {
+            let values = new.data_cols.iter().map(|c| cell(c, row).map(String::from)).collect();
+            ops.push(PatchOp::Insert { row, values });
+        }
+
+        Ok(Patch {
+            cols: self.cols(),
+            rows: old_rows,
+            ops,
+        })
+    }
+
+    /// Applies the patch to the table.
+    ///
+    /// The patch is checked before any change is made, so the table is
+    /// left unchanged on failure. Each column the patch changes is rebuilt
+    /// once, however many operations it holds.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The table does not have the shape the patch
+    ///   was built from, or an operation is out of range.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), DataError> {
+        if self.cols() != patch.cols || self.rows() != patch.rows {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut rows = patch.rows;
+        for op in patch.ops.iter() {
+            let valid = match *op {
+                PatchOp::Update { row, column, .. } => row < rows && column < patch.cols,
+                PatchOp::Insert { row, ref values } => row <= rows && values.len() == patch.cols,
+                PatchOp::Delete { row } => row < rows,
+            };
+            if !valid {
+                return Err(DataError::InvalidStateError);
+            }
+
+            match *op {
+                PatchOp::Insert { .. } => rows += 1,
+                PatchOp::Delete { .. } => rows -= 1,
+                PatchOp::Update { .. } => {}
+            }
+        }
+
+        // Each column is rebuilt once, replaying the operations on its values.
+        let reshapes = patch.ops.iter().any(|op| !matches!(*op, PatchOp::Update { .. }));
+        for (index, column) in self.data_cols.iter_mut().enumerate() {
+            let updated = patch.ops.iter().any(|op| match *op {
+                PatchOp::Update { column, .. } => column == index,
+                _ => false,
+            });
+            if reshapes || updated {
+                apply_ops(column, index, &patch.ops);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Applies the operations to the column with the index, in one edit.
+///
+/// Missing values are left empty.
+fn apply_ops(column: &mut DataColumn, index: usize, ops: &[PatchOp]) {
+    let mut missing: Vec<bool> = (0..column.len()).map(|row| column.is_missing(row)).collect();
+    let text = |value: &Option<String>| value.as_ref().map_or("", |v| &v[..]).into();
+
+    column.edit_values(|values| {
+        for op in ops {
+            match *op {
+                PatchOp::Update { row, column, ref value } if column == index => {
+                    values[row] = text(value);
+                    missing[row] = value.is_none();
+                }
+                PatchOp::Update { .. } => {}
+                PatchOp::Insert { row, values: ref new } => {
+                    values.insert(row, text(&new[index]));
+                    missing.insert(row, new[index].is_none());
+                }
+                PatchOp::Delete { row } => {
+                    values.remove(row);
+                    missing.remove(row);
+                }
+            }
+        }
+    });
+    column.set_missing_mask(missing);
+}
+
+fn push_u64(bytes: &mut Vec<u8>, val: usize) {
+    // Writing to a Vec cannot fail.
+    write_u64(bytes, val as u64).expect("write to a Vec failed");
+}
+
+fn push_value(bytes: &mut Vec<u8>, value: &Option<String>) {
+    match *value {
+        Some(ref v) => {
+            bytes.push(1);
+            push_u64(bytes, v.len());
+            bytes.extend_from_slice(v.as_bytes());
+        }
+        None => bytes.push(0),
+    }
+}
+
+fn read_value(reader: &mut SnapshotReader) -> Result<Option<String>, DataError> {
+    match reader.read_u8()? {
+        0 => Ok(None),
+        1 => {
+            let len = reader.read_len()?;
+            Ok(Some(reader.read_str(len)?.to_string()))
+        }
+        _ => Err(DataError::InvalidStateError),
+    }
+}
@@ -0,0 +1,149 @@
+//! The plot module.
+//!
+//! Provides quick plots of table columns written as SVG images with
+//! plotters, for exploring data without exporting it to another tool.
+//! Enabled by the `plot` feature.
+
+use std::f64;
+use std::io;
+use std::path::Path;
+
+use plotters::coord::types::RangedCoordf64;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use stats::Bins;
+
+const SIZE: (u32, u32) = (640, 480);
+const FILL: RGBColor = RGBColor(70, 130, 180);
+
+/// A chart with a pair of labelled axes.
+type Chart<'a, 'b> = ChartContext<'a, SVGBackend<'b>, Cartesian2d<RangedCoordf64, RangedCoordf64>>;
+
+impl DataTable {
+    /// Writes a scatter plot of column `x` against column `y` to an SVG file.
+    ///
+    /// Rows where either value is not finite are left out of the plot.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : There is no column with one of the names.
+    /// - DataCastError : A value could not be parsed as an `f64`.
+    /// - Io : The file could not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rusty_data::loader::Loader;
+    ///
+    /// let table = Loader::new(true, "path/to/file.data", ',').load_file().unwrap();
+    /// table.plot_scatter("height", "weight", "scatter.svg").unwrap();
+    /// ```
+    pub fn plot_scatter<P: AsRef<Path>>(&self, x: &str, y: &str, path: P) -> Result<(), DataError> {
+        let xs = numeric_values(self.column(x))?;
+        let ys = numeric_values(self.column(y))?;
+
+        let points: Vec<(f64, f64)> = xs.into_iter()
+            .zip(ys)
+            .filter(|&(a, b)| a.is_finite() && b.is_finite())
+            .collect();
+
+        let x_range = range(points.iter().map(|p| p.0));
+        let y_range = range(points.iter().map(|p| p.1));
+
+        let root = SVGBackend::new(path.as_ref(), SIZE).into_drawing_area();
+        let mut chart = axes(&root, x, y, x_range, y_range)?;
+
+        chart.draw_series(points.iter().map(|&p| Circle::new(p, 3, FILL.filled())))
+            .map_err(plot_error)?;
+
+        root.present().map_err(plot_error)
+    }
+
+    /// Writes a histogram of the column to an SVG file.
+    ///
+    /// The number of bins is chosen from the number of values using Sturges' rule.
+    /// Missing values and values which are not finite are left out of the
+    /// plot, see `DataColumn::histogram`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : There is no column with the name.
+    /// - DataCastError : A value could not be parsed as an `f64`.
+    /// - Io : The file could not be written.
+    pub fn plot_hist<P: AsRef<Path>>(&self, col: &str, path: P) -> Result<(), DataError> {
+        let column = self.column(col).ok_or(DataError::InvalidStateError)?;
+        let hist = column.histogram(Bins::Sturges)?;
+        let (min, max) = (hist.edges[0], hist.edges[hist.counts.len()]);
+        let top = hist.counts.iter().cloned().max().unwrap_or(0).max(1) as f64;
+
+        let root = SVGBackend::new(path.as_ref(), SIZE).into_drawing_area();
+        let mut chart = axes(&root, col, "count", (min, max), (0.0, top))?;
+
+        let bars: Vec<[(f64, f64); 2]> = hist.edges
+            .windows(2)
+            .zip(&hist.counts)
+            .map(|(edge, &count)| [(edge[0], 0.0), (edge[1], count as f64)])
+            .collect();
+
+        chart.draw_series(bars.iter().map(|&bar| Rectangle::new(bar, FILL.filled())))
+            .map_err(plot_error)?;
+        chart.draw_series(bars.iter().map(|&bar| Rectangle::new(bar, WHITE)))
+            .map_err(plot_error)?;
+
+        root.present().map_err(plot_error)
+    }
+}
+
+/// Parses every value of the column as an `f64`.
+fn numeric_values(column: Option<&DataColumn>) -> Result<Vec<f64>, DataError> {
+    let column = column.ok_or(DataError::InvalidStateError)?;
+    (0..column.len()).map(|i| column.get_as::<f64>(i)).collect()
+}
+
+/// Finds the smallest and largest values, widening empty or flat ranges.
+fn range<I: Iterator<Item = f64>>(values: I) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY),
+                                 |(lo, hi), v| (lo.min(v), hi.max(v)));
+
+    if min > max {
+        (0.0, 1.0)
+    } else if min == max {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    }
+}
+
+/// Fills the image and draws a pair of labelled axes covering the ranges.
+fn axes<'a, 'b>(root: &'a DrawingArea<SVGBackend<'b>, Shift>,
+                x_label: &str,
+                y_label: &str,
+                x_range: (f64, f64),
+                y_range: (f64, f64))
+                -> Result<Chart<'a, 'b>, DataError> {
+    root.fill(&WHITE).map_err(plot_error)?;
+
+    let mut chart = ChartBuilder::on(root)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(x_range.0..x_range.1, y_range.0..y_range.1)
+        .map_err(plot_error)?;
+
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_desc(x_label)
+        .y_desc(y_label)
+        .draw()
+        .map_err(plot_error)?;
+
+    Ok(chart)
+}
+
+/// Wraps an error from plotters, which fails when the image cannot be written.
+fn plot_error(err: DrawingAreaErrorKind<io::Error>) -> DataError {
+    DataError::Io(io::Error::other(err))
+}
@@ -0,0 +1,256 @@
+//! The profile module.
+//!
+//! Provides a report on each column of a DataTable, which is usually the
+//! first thing to look at for a new dataset. A report can be printed as
+//! text or written as JSON.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use category::write_json_string;
+use datatable::{DataColumn, DataTable};
+use schema::{infer_column, DType};
+use stats::{histogram_of, Bins};
+
+/// The number of most common values kept for each column.
+const TOP_VALUES: usize = 5;
+
+/// The number of bins in the histogram of a numeric column.
+const HISTOGRAM_BINS: usize = 10;
+
+/// The characters used to draw histograms, from the lowest to the highest.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A summary of the values of one column within a `Profile`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnProfile {
+    /// The index of the column in the table.
+    pub column: usize,
+    /// The name of the column.
+    pub name: Option<String>,
+    /// The narrowest type which every non-null value parses as.
+    pub dtype: DType,
+    /// The number of values which are missing or empty.
+    pub null_count: usize,
+    /// The fraction of values which are missing or empty.
+    pub null_fraction: f64,
+    /// The number of distinct non-null values.
+    pub distinct: usize,
+    /// The most common non-null values and how often they occur, most common first.
+    pub top: Vec<(String, usize)>,
+    /// The smallest non-null value, compared as numbers for numeric columns.
+    pub min: Option<String>,
+    /// The largest non-null value, compared as numbers for numeric columns.
+    pub max: Option<String>,
+    /// The counts of the finite numbers in equal-width bins from `min` to `max`.
+    ///
+    /// Empty unless the column is numeric.
+    pub histogram: Vec<usize>,
+}
+
+/// A summary of every column of a table.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    /// The number of rows in the table.
+    pub rows: usize,
+    /// The summary of each column, in table order.
+    pub columns: Vec<ColumnProfile>,
+}
+
+impl Profile {
+    /// Writes the profile as a JSON object.
+    ///
+    /// Columns without a name, and missing minimums and maximums, are
+    /// written as `null`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["a"], vec!["b"], vec!["a"]];
+    /// let table = DataTable::from_records(Some(vec!["id"]), records).unwrap();
+    ///
+    /// let json = table.profile().to_json();
+    ///
+    /// assert!(json.starts_with(r#"{"rows":3,"columns":[{"name":"id","dtype":"string""#));
+    /// assert!(json.contains(r#""top":[["a",2],["b",1]]"#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"rows\":{},\"columns\":[", self.rows);
+
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+
+            json.push_str("{\"name\":");
+            write_json_option(&mut json, column.name.as_deref());
+            json.push_str(&format!(",\"dtype\":\"{}\",\"null_count\":{},\"null_fraction\":{}",
+                                   column.dtype,
+                                   column.null_count,
+                                   column.null_fraction));
+            json.push_str(&format!(",\"distinct\":{},\"top\":[", column.distinct));
+            for (j, &(ref value, count)) in column.top.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                json.push('[');
+                write_json_string(&mut json, value);
+                json.push_str(&format!(",{}]", count));
+            }
+            json.push_str("],\"min\":");
+            write_json_option(&mut json, column.min.as_deref());
+            json.push_str(",\"max\":");
+            write_json_option(&mut json, column.max.as_deref());
+
+            let counts: Vec<String> = column.histogram.iter().map(|c| c.to_string()).collect();
+            json.push_str(&format!(",\"histogram\":[{}]}}", counts.join(",")));
+        }
+
+        json.push_str("]}");
+        json
+    }
+}
+
+fn write_json_option(json: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => write_json_string(json, value),
+        None => json.push_str("null"),
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} rows, {} columns", self.rows, self.columns.len())?;
+
+        for column in &self.columns {
+            writeln!(f)?;
+            match column.name {
+                Some(ref name) => write!(f, "{}", name)?,
+                None => write!(f, "column {}", column.column)?,
+            }
+            write!(f,
+                   " ({}): {:.1}% null, {} distinct",
+                   column.dtype,
+                   column.null_fraction * 100.0,
+                   column.distinct)?;
+
+            if let (Some(min), Some(max)) = (column.min.as_ref(), column.max.as_ref()) {
+                write!(f, ", range {} to {}", min, max)?;
+            }
+            if !column.top.is_empty() {
+                let top: Vec<String> = column.top
+                    .iter()
+                    .map(|&(ref value, count)| format!("{:?} ({})", value, count))
+                    .collect();
+                write!(f, "\n  top: {}", top.join(", "))?;
+            }
+            if let Some(&highest) = column.histogram.iter().max() {
+                let bars: String = column.histogram
+                    .iter()
+                    .map(|&count| if count == 0 {
+                        ' '
+                    } else {
+                        BARS[count * (BARS.len() - 1) / highest]
+                    })
+                    .collect();
+                write!(f, "\n  histogram: {}", bars)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DataTable {
+    /// Summarizes the values of each column.
+    ///
+    /// Values which are missing or empty are counted as nulls. The type
+    /// of each column is inferred as in `infer_column`, and columns of
+    /// integers or floats are summarized as numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::schema::DType;
+    ///
+    /// let records = vec![vec!["ann", "9"], vec!["bob", "10"], vec!["ann", ""]];
+    /// let table = DataTable::from_records(Some(vec!["name", "score"]), records).unwrap();
+    ///
+    /// let profile = table.profile();
+    /// let score = &profile.columns[1];
+    ///
+    /// assert_eq!(score.dtype, DType::I64);
+    /// assert_eq!(score.null_count, 1);
+    /// assert_eq!(score.min, Some("9".to_string()));
+    /// assert_eq!(score.max, Some("10".to_string()));
+    /// assert_eq!(profile.columns[0].top[0], ("ann".to_string(), 2));
+    ///
+    /// println!("{}", profile);
+    /// ```
+    pub fn profile(&self) -> Profile {
+        Profile {
+            rows: self.rows(),
+            columns: self.data_cols
+                .iter()
+                .enumerate()
+                .map(|(i, column)| profile_column(i, column))
+                .collect(),
+        }
+    }
+}
+
+fn profile_column(index: usize, column: &DataColumn) -> ColumnProfile {
+    let inference = infer_column(column);
+    let numeric = inference.dtype == DType::I64 || inference.dtype == DType::F64;
+
+    // The first row and count of each value.
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    let mut numbers = Vec::new();
+    for (idx, val) in column.iter().enumerate() {
+        if column.is_missing(idx) || val.is_empty() {
+            continue;
+        }
+        counts.entry(val).or_insert((idx, 0)).1 += 1;
+        if numeric {
+            if let Ok(number) = val.parse::<f64>() {
+                numbers.push((number, val));
+            }
+        }
+    }
+
+    let mut top: Vec<(&str, usize, usize)> =
+        counts.iter().map(|(&val, &(first, count))| (val, first, count)).collect();
+    top.sort_by(|a, b| b.2.cmp(&a.2).then(a.1.cmp(&b.1)));
+    top.truncate(TOP_VALUES);
+
+    let (min, max) = if numeric {
+        let order = |a: &&(f64, &str), b: &&(f64, &str)| a.0.total_cmp(&b.0);
+        let finite = || numbers.iter().filter(|n| !n.0.is_nan());
+        (finite().min_by(order).map(|n| n.1), finite().max_by(order).map(|n| n.1))
+    } else {
+        let order = |a: &&&str, b: &&&str| a.cmp(b);
+        (counts.keys().min_by(order).cloned(), counts.keys().max_by(order).cloned())
+    };
+
+    let numbers: Vec<f64> = numbers.into_iter().map(|n| n.0).filter(|n| n.is_finite()).collect();
+    let rows = column.len();
+
+    ColumnProfile {
+        column: index,
+        name: column.name.clone(),
+        dtype: inference.dtype,
+        null_count: inference.null_count,
+        null_fraction: if rows == 0 { 0.0 } else { inference.null_count as f64 / rows as f64 },
+        distinct: counts.len(),
+        top: top.into_iter().map(|(val, _, count)| (val.to_string(), count)).collect(),
+        min: min.map(str::to_string),
+        max: max.map(str::to_string),
+        histogram: match histogram_of(&numbers, Bins::Count(HISTOGRAM_BINS)) {
+            Ok(hist) if numeric => hist.counts,
+            _ => Vec::new(),
+        },
+    }
+}
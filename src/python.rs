@@ -0,0 +1,197 @@
+//! The python module.
+//!
+//! Provides PyO3 bindings so that DataTables can be loaded, cleaned and
+//! shared from Python. Enabled by the `python` feature.
+//!
+//! An extension module registers the bindings with `register`, for
+//! example from a `#[pymodule]` in a crate built with maturin.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyIOError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use datatable::DataTable;
+use error::DataError;
+use loader::Loader;
+
+/// A DataTable which can be used from Python.
+///
+/// Rows are passed to and from Python as lists of dicts, keyed by column
+/// name in the same way as `DataTable::to_row_maps`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate pyo3;
+/// extern crate rusty_data;
+///
+/// use std::ffi::CString;
+///
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyDict;
+/// use rusty_data::python::register;
+///
+/// # fn main() {
+/// Python::initialize();
+///
+/// Python::attach(|py| {
+///     let module = PyModule::new(py, "rusty_data").unwrap();
+///     register(&module).unwrap();
+///
+///     let locals = PyDict::new(py);
+///     locals.set_item("rusty_data", module).unwrap();
+///
+///     let code = CString::new("
+/// table = rusty_data.DataTable.from_dicts([{'x': '1', 'y': 2}, {'x': '3', 'y': 4}])
+/// assert table.headers() == ['x', 'y']
+/// assert table.column('y') == ['2', '4']
+/// assert table.to_dicts()[1] == {'x': '3', 'y': '4'}
+/// ").unwrap();
+///     py.run(&code, None, Some(&locals)).unwrap();
+/// });
+/// # }
+/// ```
+#[pyclass(name = "DataTable", module = "rusty_data")]
+pub struct PyDataTable {
+    table: DataTable,
+}
+
+impl PyDataTable {
+    /// Constructs a PyDataTable holding the table.
+    pub fn new(table: DataTable) -> PyDataTable {
+        PyDataTable { table }
+    }
+
+    /// The table held for Python.
+    pub fn table(&self) -> &DataTable {
+        &self.table
+    }
+
+    /// Consumes the PyDataTable and returns the table.
+    pub fn into_table(self) -> DataTable {
+        self.table
+    }
+
+    /// The keys used for each column in `to_dicts`.
+    fn names(&self) -> Vec<String> {
+        self.table
+            .data_cols
+            .iter()
+            .enumerate()
+            .map(|(i, c)| c.name.clone().unwrap_or_else(|| i.to_string()))
+            .collect()
+    }
+}
+
+impl From<DataTable> for PyDataTable {
+    fn from(table: DataTable) -> PyDataTable {
+        PyDataTable::new(table)
+    }
+}
+
+#[pymethods]
+impl PyDataTable {
+    /// Loads a table from a delimited file.
+    #[staticmethod]
+    #[pyo3(signature = (path, has_header = true, delimiter = ','))]
+    fn load(path: &str, has_header: bool, delimiter: char) -> PyResult<PyDataTable> {
+        let table = Loader::new(has_header, path, delimiter).load_file().map_err(py_error)?;
+        Ok(PyDataTable::new(table))
+    }
+
+    /// Constructs a table from a list of dicts.
+    ///
+    /// The columns are the keys of the first dict unless `columns` is
+    /// given. Values which are not strings are converted with `str`.
+    #[staticmethod]
+    #[pyo3(signature = (rows, columns = None))]
+    fn from_dicts(rows: Vec<Bound<'_, PyDict>>,
+                  columns: Option<Vec<String>>)
+                  -> PyResult<PyDataTable> {
+        let columns = match (columns, rows.first()) {
+            (Some(columns), _) => columns,
+            (None, Some(first)) => first.keys().extract()?,
+            (None, None) => Vec::new(),
+        };
+
+        let mut maps = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let mut map = HashMap::with_capacity(row.len());
+            for (key, val) in row.iter() {
+                map.insert(key.extract()?, val.str()?.to_string());
+            }
+            maps.push(map);
+        }
+
+        let table = DataTable::from_row_maps(&columns, &maps).map_err(|_| {
+            PyKeyError::new_err("a dict has no value for one of the columns")
+        })?;
+        Ok(PyDataTable::new(table))
+    }
+
+    /// Converts each row into a dict from column name to value.
+    fn to_dicts<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let names = self.names();
+
+        self.table
+            .iter_rows()
+            .map(|row| {
+                let dict = PyDict::new(py);
+                for (name, val) in names.iter().zip(row) {
+                    dict.set_item(name, val)?;
+                }
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// The number of rows in the table.
+    fn rows(&self) -> usize {
+        self.table.rows()
+    }
+
+    /// The number of columns in the table.
+    fn cols(&self) -> usize {
+        self.table.cols()
+    }
+
+    /// The names of the columns, using an empty name for unnamed columns.
+    fn headers(&self) -> Vec<String> {
+        self.table.headers().into_iter().map(String::from).collect()
+    }
+
+    /// The values of the column with the given name.
+    fn column(&self, name: &str) -> PyResult<Vec<String>> {
+        match self.table.column(name) {
+            Some(column) => Ok(column.iter().map(String::from).collect()),
+            None => Err(PyKeyError::new_err(name.to_string())),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.table.rows()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DataTable(rows={}, cols={})", self.table.rows(), self.table.cols())
+    }
+}
+
+/// Adds the `DataTable` class to the Python module.
+///
+/// # Failures
+///
+/// - Fails if the class cannot be added to the module.
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDataTable>()
+}
+
+/// Converts the error into the closest Python exception.
+fn py_error(err: DataError) -> PyErr {
+    match err {
+        DataError::Io(e) => PyIOError::new_err(e.to_string()),
+        err => PyValueError::new_err(err.to_string()),
+    }
+}
@@ -0,0 +1,81 @@
+//! Fast byte scanning used when splitting lines.
+//!
+//! Searches a word of bytes at a time rather than a character at a
+//! time. Only ASCII needles are supported, which is safe on UTF-8 text
+//! as ASCII bytes never occur inside a multi-byte character.
+
+use std::mem;
+
+const WORD: usize = mem::size_of::<usize>();
+const LO: usize = usize::MAX / 255;
+const HI: usize = LO * 0x80;
+
+/// Returns a word with every byte set to `b`.
+fn splat(b: u8) -> usize {
+    LO * b as usize
+}
+
+/// Returns true if any byte in the word is zero.
+fn has_zero_byte(x: usize) -> bool {
+    x.wrapping_sub(LO) & !x & HI != 0
+}
+
+fn read_word(chunk: &[u8]) -> usize {
+    let mut buf = [0u8; WORD];
+    buf.copy_from_slice(chunk);
+    usize::from_ne_bytes(buf)
+}
+
+/// Finds the first occurrence of `needle` in `haystack`.
+pub fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+    let pattern = splat(needle);
+
+    let mut offset = 0;
+    for chunk in haystack.chunks_exact(WORD) {
+        if has_zero_byte(read_word(chunk) ^ pattern) {
+            break;
+        }
+        offset += WORD;
+    }
+
+    haystack[offset..].iter().position(|&b| b == needle).map(|i| offset + i)
+}
+
+/// Finds the first occurrence of either `a` or `b` in `haystack`.
+pub fn find_either(haystack: &[u8], a: u8, b: u8) -> Option<usize> {
+    let pattern_a = splat(a);
+    let pattern_b = splat(b);
+
+    let mut offset = 0;
+    for chunk in haystack.chunks_exact(WORD) {
+        let word = read_word(chunk);
+        if has_zero_byte(word ^ pattern_a) || has_zero_byte(word ^ pattern_b) {
+            break;
+        }
+        offset += WORD;
+    }
+
+    haystack[offset..].iter().position(|&c| c == a || c == b).map(|i| offset + i)
+}
+
+/// Counts the occurrences of `needle` in `haystack`.
+pub fn count_byte(haystack: &[u8], needle: u8) -> usize {
+    let mut count = 0;
+    let mut rest = haystack;
+
+    while let Some(i) = find_byte(rest, needle) {
+        count += 1;
+        rest = &rest[i + 1..];
+    }
+
+    count
+}
+
+/// Converts the char to a byte if it can be scanned for.
+pub fn ascii_byte(c: char) -> Option<u8> {
+    if c.is_ascii() {
+        Some(c as u8)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,287 @@
+//! The schema module.
+//!
+//! Describes the types which the values of a column are expected
+//! to parse as.
+
+use std::fmt;
+
+use datatable::{cast_value, DataColumn, DataTable};
+use error::{CellLocation, DataError};
+
+/// The type of the values in a column.
+///
+/// The variants are ordered from the narrowest to the widest. Inference
+/// picks the first type which every value parses as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DType {
+    /// Values parse as `bool`.
+    Bool,
+    /// Values parse as `i64`.
+    I64,
+    /// Values parse as `f64`.
+    F64,
+    /// Any value.
+    String,
+}
+
+impl DType {
+    /// The name of the type.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            DType::Bool => "bool",
+            DType::I64 => "i64",
+            DType::F64 => "f64",
+            DType::String => "string",
+        }
+    }
+
+    /// Returns true if the value parses as this type.
+    pub fn parses(&self, value: &str) -> bool {
+        self.check(value, CellLocation::default).is_ok()
+    }
+
+    /// Checks that the value parses as this type.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : The value does not parse as this type. The error
+    ///   is given the location returned by `location`.
+    pub fn check<F>(&self, value: &str, location: F) -> Result<(), DataError>
+        where F: FnOnce() -> CellLocation
+    {
+        match *self {
+            DType::Bool => cast_value::<bool, _>(value, location).map(|_| ()),
+            DType::I64 => cast_value::<i64, _>(value, location).map(|_| ()),
+            DType::F64 => cast_value::<f64, _>(value, location).map(|_| ()),
+            DType::String => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The number of offending values kept as examples by `infer_column`.
+const MAX_EXAMPLES: usize = 3;
+
+/// How well the values of a column fit one type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeCandidate {
+    /// The type considered.
+    pub dtype: DType,
+    /// The fraction of the non-null values which parse as the type.
+    pub confidence: f64,
+    /// Examples of values which do not parse as the type.
+    pub offending: Vec<String>,
+}
+
+/// The inferred type of a column along with the evidence for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnInference {
+    /// The name of the column.
+    pub name: Option<String>,
+    /// The narrowest type which every non-null value parses as.
+    ///
+    /// A column holding only nulls is inferred as `DType::String`.
+    pub dtype: DType,
+    /// Every type from the narrowest to the widest, with how well it fits.
+    pub candidates: Vec<TypeCandidate>,
+    /// The number of values which are missing or empty.
+    pub null_count: usize,
+    /// The number of values examined.
+    pub sampled: usize,
+}
+
+/// Infers the type of the column from its values.
+///
+/// Values which are marked as missing or are empty are counted as nulls
+/// and do not affect the type.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::DataColumn;
+/// use rusty_data::schema::{infer_column, DType};
+///
+/// let mut dc = DataColumn::empty();
+/// for v in &["1", "2", "", "3.5"] {
+///     dc.push(*v);
+/// }
+///
+/// let inference = infer_column(&dc);
+///
+/// assert_eq!(inference.dtype, DType::F64);
+/// assert_eq!(inference.null_count, 1);
+/// assert_eq!(inference.candidates[1].offending, vec!["3.5"]);
+/// ```
+pub fn infer_column(column: &DataColumn) -> ColumnInference {
+    let types = [DType::Bool, DType::I64, DType::F64, DType::String];
+    let mut matches = [0usize; 4];
+    let mut offending = vec![Vec::new(); 4];
+    let mut null_count = 0;
+
+    for idx in 0..column.len() {
+        let val = &column[idx];
+
+        if column.is_missing(idx) || val.is_empty() {
+            null_count += 1;
+            continue;
+        }
+
+        for (i, dtype) in types.iter().enumerate() {
+            if dtype.parses(val) {
+                matches[i] += 1;
+            } else if offending[i].len() < MAX_EXAMPLES {
+                offending[i].push(val.to_string());
+            }
+        }
+    }
+
+    let present = column.len() - null_count;
+    let candidates: Vec<TypeCandidate> = types.iter()
+        .zip(matches.iter())
+        .zip(offending)
+        .map(|((&dtype, &count), offending)| {
+            TypeCandidate {
+                dtype,
+                confidence: if present == 0 { 1.0 } else { count as f64 / present as f64 },
+                offending,
+            }
+        })
+        .collect();
+
+    // A column of only nulls gives no evidence so is left as strings.
+    let dtype = candidates.iter()
+        .find(|c| present > 0 && c.offending.is_empty())
+        .map(|c| c.dtype)
+        .unwrap_or(DType::String);
+
+    ColumnInference {
+        name: column.name.clone(),
+        dtype,
+        candidates,
+        null_count,
+        sampled: column.len(),
+    }
+}
+
+impl DataColumn {
+    /// Guesses the narrowest type which the first `sample` values parse as.
+    ///
+    /// Types are tried in the order `Bool`, `I64`, `F64` then `String`.
+    /// Missing and empty values are skipped, and a sample holding only
+    /// nulls is guessed as `DType::String`. See `infer_column` for how
+    /// well each type fits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::schema::DType;
+    ///
+    /// let dc: DataColumn = vec!["1", "2", "2.5", "x"].into_iter().collect();
+    ///
+    /// assert_eq!(dc.guess_type(2), DType::I64);
+    /// assert_eq!(dc.guess_type(3), DType::F64);
+    /// assert_eq!(dc.guess_type(10), DType::String);
+    /// ```
+    pub fn guess_type(&self, sample: usize) -> DType {
+        let types = [DType::Bool, DType::I64, DType::F64, DType::String];
+        let mut narrowest = 0;
+        let mut seen = false;
+
+        for idx in 0..sample.min(self.len()) {
+            let val = &self[idx];
+
+            if self.is_missing(idx) || val.is_empty() {
+                continue;
+            }
+            seen = true;
+
+            while !types[narrowest].parses(val) {
+                narrowest += 1;
+            }
+
+            if types[narrowest] == DType::String {
+                break;
+            }
+        }
+
+        if seen { types[narrowest] } else { DType::String }
+    }
+}
+
+impl DataTable {
+    /// Checks that the named columns parse as the given types and records them.
+    ///
+    /// Each column has its type recorded, see `DataColumn::dtype`. Columns
+    /// without missing values also keep their parsed values, so a later
+    /// `into_vec_cached` or `into_consistent_data_cached` of the same type
+    /// does not parse them again. Nothing is changed unless every column parses.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has one of the names.
+    /// - DataCastError : A present value does not parse as the type of its
+    ///   column. The error gives the row and the name of the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::schema::DType;
+    ///
+    /// let records = vec![vec!["31", "1.8"], vec!["twelve", "1.5"]];
+    /// let mut table = DataTable::from_records(Some(vec!["age", "height"]), records).unwrap();
+    ///
+    /// let err = table.convert_columns(&[("age", DType::I64)]).unwrap_err();
+    /// assert!(err.to_string().contains("age"));
+    ///
+    /// table.convert_columns(&[("height", DType::F64)]).unwrap();
+    /// assert_eq!(table[1].dtype(), Some(DType::F64));
+    /// assert_eq!(table[1].cached::<f64>(), Some(&[1.8, 1.5][..]));
+    /// ```
+    pub fn convert_columns(&mut self, columns: &[(&str, DType)]) -> Result<(), DataError> {
+        let mut found = Vec::with_capacity(columns.len());
+
+        for &(name, dtype) in columns {
+            let col = self.column_index(name).ok_or(DataError::InvalidStateError)?;
+            let column = &self.data_cols[col];
+
+            for (row, val) in column.iter().enumerate() {
+                if column.is_missing(row) {
+                    continue;
+                }
+
+                dtype.check(val, || {
+                    CellLocation {
+                        row: Some(row),
+                        column: Some(col),
+                        column_name: Some(name.to_string()),
+                    }
+                })?;
+            }
+
+            found.push((col, dtype));
+        }
+
+        for (col, dtype) in found {
+            let column = &mut self.data_cols[col];
+
+            if !column.has_missing() {
+                match dtype {
+                    DType::Bool => column.cast_cached::<bool>().map(|_| ())?,
+                    DType::I64 => column.cast_cached::<i64>().map(|_| ())?,
+                    DType::F64 => column.cast_cached::<f64>().map(|_| ())?,
+                    DType::String => column.clear_cache(),
+                }
+            }
+            column.set_dtype(dtype);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+//! The snapshot module.
+//!
+//! Provides a compact binary format for saving and loading DataTables
+//! without parsing them again.
+//!
+//! A snapshot starts with a magic number and the shape of the table.
+//! Each column then holds its name, its recorded type, a bitmask of its
+//! missing values, its values and its categories. All integers are little
+//! endian.
+//!
+//! The values of a column with a recorded `bool`, `i64` or `f64` type are
+//! held in a typed buffer of one value per row, followed by the text of
+//! each missing value. This is used when every present value is written
+//! exactly as the type displays it, so that loading gives back the same
+//! text. Other columns hold the end offset of each value and the bytes of
+//! every value back to back.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::str;
+use std::str::FromStr;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use schema::DType;
+
+const MAGIC: &[u8; 8] = b"RDSNAP02";
+
+impl DataTable {
+    /// Saves the table to a binary snapshot file.
+    ///
+    /// The names, values, missing marks, recorded types and categories of
+    /// the columns are kept.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::schema::DType;
+    ///
+    /// let records = vec![vec!["1", "a"], vec!["2", "b"]];
+    /// let table = DataTable::from_records(Some(vec!["n", "s"]), records).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("rusty-data-snapshot-doctest.bin");
+    /// table.save(&path).unwrap();
+    ///
+    /// let loaded = DataTable::load(&path).unwrap();
+    /// assert_eq!(loaded.headers(), vec!["n", "s"]);
+    /// assert_eq!(loaded[1].data(), vec!["a", "b"]);
+    ///
+    /// // Typed columns are loaded with their parsed values.
+    /// let mut table = table;
+    /// table.convert_columns(&[("n", DType::I64)]).unwrap();
+    /// table.data_cols[1].update_categories();
+    /// table.save(&path).unwrap();
+    ///
+    /// let loaded = DataTable::load(&path).unwrap();
+    /// assert_eq!(loaded[0].cached::<i64>(), Some(&[1, 2][..]));
+    /// assert_eq!(loaded[1].category_labels(), Some(vec!["a", "b"]));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        write_u64(&mut writer, self.cols() as u64)?;
+        write_u64(&mut writer, self.rows() as u64)?;
+
+        for column in self.data_cols.iter() {
+            write_column(&mut writer, column)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads a table from a snapshot file written by `save`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : The file is not a valid snapshot.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<DataTable, DataError> {
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+
+        let mut reader = SnapshotReader { bytes: &bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let cols = reader.read_len()?;
+        let rows = reader.read_len()?;
+        let mut table = DataTable::empty();
+
+        for _ in 0..cols {
+            table.data_cols.push(reader.read_column(rows)?);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(table)
+    }
+}
+
+pub(crate) fn write_u64<W: Write>(writer: &mut W, val: u64) -> Result<(), DataError> {
+    writer.write_all(&val.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_column<W: Write>(writer: &mut W, column: &DataColumn) -> Result<(), DataError> {
+    match column.name {
+        Some(ref name) => {
+            writer.write_all(&[1])?;
+            write_u64(writer, name.len() as u64)?;
+            writer.write_all(name.as_bytes())?;
+        }
+        None => writer.write_all(&[0])?,
+    }
+
+    let dtype = match column.dtype() {
+        None => 0,
+        Some(DType::Bool) => 1,
+        Some(DType::I64) => 2,
+        Some(DType::F64) => 3,
+        Some(DType::String) => 4,
+    };
+    writer.write_all(&[dtype])?;
+
+    let mut mask = vec![0u8; column.len().div_ceil(8)];
+    for row in 0..column.len() {
+        if column.is_missing(row) {
+            mask[row / 8] |= 1 << (row % 8);
+        }
+    }
+    writer.write_all(&mask)?;
+
+    let typed = match column.dtype() {
+        Some(DType::Bool) => typed_buffer::<bool>(column),
+        Some(DType::I64) => typed_buffer::<i64>(column),
+        Some(DType::F64) => typed_buffer::<f64>(column),
+        Some(DType::String) | None => None,
+    };
+
+    match typed {
+        Some(buffer) => {
+            writer.write_all(&[1])?;
+            writer.write_all(&buffer)?;
+
+            let missing: Vec<&str> = (0..column.len())
+                .filter(|&row| column.is_missing(row))
+                .map(|row| &column[row])
+                .collect();
+            write_strings(writer, &missing)?;
+        }
+        None => {
+            writer.write_all(&[0])?;
+            write_strings(writer, &column.iter().collect::<Vec<_>>())?;
+        }
+    }
+
+    match column.categories() {
+        Some(categories) => {
+            let mut categories: Vec<(String, usize)> = categories.into_iter().collect();
+            categories.sort_by_key(|&(_, idx)| idx);
+
+            writer.write_all(&[1])?;
+            write_u64(writer, categories.len() as u64)?;
+            for (label, idx) in categories {
+                write_u64(writer, idx as u64)?;
+                write_u64(writer, label.len() as u64)?;
+                writer.write_all(label.as_bytes())?;
+            }
+        }
+        None => writer.write_all(&[0])?,
+    }
+
+    Ok(())
+}
+
+/// Writes the end offset of each value followed by the bytes of every value.
+fn write_strings<W: Write>(writer: &mut W, values: &[&str]) -> Result<(), DataError> {
+    let mut end = 0u64;
+    for val in values {
+        end += val.len() as u64;
+        write_u64(writer, end)?;
+    }
+    for val in values {
+        writer.write_all(val.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// A type which is held in a snapshot as a fixed number of bytes.
+trait Fixed: FromStr + ToString + Copy + Send + Sync + 'static {
+    const WIDTH: usize;
+
+    fn write_le(self, bytes: &mut Vec<u8>);
+
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl Fixed for bool {
+    const WIDTH: usize = 1;
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.push(self as u8);
+    }
+
+    fn read_le(bytes: &[u8]) -> bool {
+        bytes[0] != 0
+    }
+}
+
+impl Fixed for i64 {
+    const WIDTH: usize = 8;
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> i64 {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        i64::from_le_bytes(buf)
+    }
+}
+
+impl Fixed for f64 {
+    const WIDTH: usize = 8;
+
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> f64 {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        f64::from_le_bytes(buf)
+    }
+}
+
+/// Packs one value per row, or returns `None` if a present value would
+/// not be displayed with the same text once loaded.
+///
+/// Missing rows hold a zero value.
+fn typed_buffer<T: Fixed>(column: &DataColumn) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(column.len() * T::WIDTH);
+
+    for (row, val) in column.iter().enumerate() {
+        if column.is_missing(row) {
+            bytes.resize(bytes.len() + T::WIDTH, 0);
+            continue;
+        }
+
+        let parsed: T = val.parse().ok()?;
+        if parsed.to_string() != val {
+            return None;
+        }
+        parsed.write_le(&mut bytes);
+    }
+
+    Some(bytes)
+}
+
+/// Reads the parts of a snapshot, or a patch, from the front of its bytes.
+pub(crate) struct SnapshotReader<'a> {
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], DataError> {
+        if len > self.bytes.len() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let (front, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(front)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, DataError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn read_len(&mut self) -> Result<usize, DataError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf) as usize)
+    }
+
+    pub(crate) fn read_str(&mut self, len: usize) -> Result<&'a str, DataError> {
+        str::from_utf8(self.take(len)?).map_err(|_| DataError::InvalidStateError)
+    }
+
+    fn read_column(&mut self, rows: usize) -> Result<DataColumn, DataError> {
+        let mut column = DataColumn::empty();
+
+        column.name = match self.read_u8()? {
+            0 => None,
+            1 => {
+                let len = self.read_len()?;
+                Some(self.read_str(len)?.to_string())
+            }
+            _ => return Err(DataError::InvalidStateError),
+        };
+
+        let dtype = match self.read_u8()? {
+            0 => None,
+            1 => Some(DType::Bool),
+            2 => Some(DType::I64),
+            3 => Some(DType::F64),
+            4 => Some(DType::String),
+            _ => return Err(DataError::InvalidStateError),
+        };
+
+        let mask = self.take(rows.div_ceil(8))?;
+        let missing = |row: usize| mask[row / 8] & (1 << (row % 8)) != 0;
+
+        match (self.read_u8()?, dtype) {
+            (0, _) => {
+                let values = self.read_strings(rows)?;
+
+                for (row, val) in values.into_iter().enumerate() {
+                    if missing(row) {
+                        column.push_missing(val);
+                    } else {
+                        column.push(val);
+                    }
+                }
+            }
+            (1, Some(DType::Bool)) => self.read_typed::<bool, _>(&mut column, rows, missing)?,
+            (1, Some(DType::I64)) => self.read_typed::<i64, _>(&mut column, rows, missing)?,
+            (1, Some(DType::F64)) => self.read_typed::<f64, _>(&mut column, rows, missing)?,
+            _ => return Err(DataError::InvalidStateError),
+        }
+
+        if let Some(dtype) = dtype {
+            column.set_dtype(dtype);
+        }
+
+        match self.read_u8()? {
+            0 => {}
+            1 => {
+                let count = self.read_len()?;
+                let mut categories = HashMap::with_capacity(count.min(self.bytes.len() / 16));
+
+                for _ in 0..count {
+                    let idx = self.read_len()?;
+                    let len = self.read_len()?;
+                    categories.insert(self.read_str(len)?.to_string(), idx);
+                }
+                column.set_categories(categories);
+            }
+            _ => return Err(DataError::InvalidStateError),
+        }
+
+        Ok(column)
+    }
+
+    /// Reads `count` end offsets followed by the bytes of the values.
+    fn read_strings(&mut self, count: usize) -> Result<Vec<&'a str>, DataError> {
+        let mut ends = Vec::with_capacity(count.min(self.bytes.len() / 8));
+        for _ in 0..count {
+            ends.push(self.read_len()?);
+        }
+
+        let values = self.read_str(ends.last().cloned().unwrap_or(0))?;
+        let mut start = 0;
+
+        ends.into_iter()
+            .map(|end| {
+                let val = values.get(start..end).ok_or(DataError::InvalidStateError)?;
+                start = end;
+                Ok(val)
+            })
+            .collect()
+    }
+
+    /// Reads a typed buffer and the text of the missing values into the column.
+    ///
+    /// The parsed values are kept in the cache of a column without missing values.
+    fn read_typed<T, F>(&mut self,
+                        column: &mut DataColumn,
+                        rows: usize,
+                        missing: F)
+                        -> Result<(), DataError>
+        where T: Fixed,
+              F: Fn(usize) -> bool
+    {
+        let buffer = self.take(rows.checked_mul(T::WIDTH).ok_or(DataError::InvalidStateError)?)?;
+        let missing_count = (0..rows).filter(|&row| missing(row)).count();
+        let mut missing_values = self.read_strings(missing_count)?.into_iter();
+
+        let mut values = Vec::with_capacity(rows);
+        for (row, bytes) in buffer.chunks(T::WIDTH).enumerate() {
+            if missing(row) {
+                column.push_missing(missing_values.next().expect("one value per missing row"));
+            } else {
+                let val = T::read_le(bytes);
+                column.push(val.to_string());
+                values.push(val);
+            }
+        }
+
+        if missing_count == 0 {
+            column.set_cache(values);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,605 @@
+//! The sort module.
+//!
+//! Provides sorting of DataTables and of streams of rows by the values of
+//! a column.
+//!
+//! Streams which do not fit in memory are sorted externally. Rows are
+//! sorted in runs which are written to temporary files, and the runs are
+//! then merged as the sorted rows are read. Files which are already
+//! sorted, such as partitions of a log, are merged in the same way.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use std::vec;
+
+use compare::cell;
+use datatable::{DataColumn, DataTable, HeaderMatch};
+use disk::temp_path;
+use error::DataError;
+use loader::{Input, Loader, LoaderOptions, Record, RowStream};
+use storage::SmallString;
+
+/// The most runs which are merged at once.
+const MAX_MERGE_RUNS: usize = 64;
+
+/// Options for sorting streams of rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortOptions {
+    /// Sort from the largest value to the smallest
+    pub descending: bool,
+    /// The number of rows sorted in memory before they are written to disk
+    pub run_rows: usize,
+    /// The directory for sorted runs, or the system temporary directory if `None`
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for SortOptions {
+    fn default() -> SortOptions {
+        SortOptions {
+            descending: false,
+            run_rows: 1_000_000,
+            temp_dir: None,
+        }
+    }
+}
+
+/// The value of a sort key, in the order keys are sorted.
+enum Key<'a> {
+    Number(f64),
+    Text(&'a str),
+    Missing,
+}
+
+impl<'a> Key<'a> {
+    fn of(value: Option<&'a str>) -> Key<'a> {
+        match value {
+            None | Some("") => Key::Missing,
+            Some(value) => {
+                match value.trim().parse::<f64>() {
+                    Ok(number) if !number.is_nan() => Key::Number(number),
+                    _ => Key::Text(value),
+                }
+            }
+        }
+    }
+}
+
+/// Compares two values of a sort key.
+///
+/// Numbers come first in numeric order, then other values in the order of
+/// their text. Missing and empty values come last, even when descending.
+pub(crate) fn compare_values(a: Option<&str>, b: Option<&str>, descending: bool) -> Ordering {
+    match (Key::of(a), Key::of(b)) {
+        (Key::Missing, Key::Missing) => Ordering::Equal,
+        (Key::Missing, _) => Ordering::Greater,
+        (_, Key::Missing) => Ordering::Less,
+        (a, b) => {
+            let order = match (a, b) {
+                (Key::Number(a), Key::Number(b)) => a.total_cmp(&b),
+                (Key::Text(a), Key::Text(b)) => a.cmp(b),
+                (Key::Number(_), _) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            if descending { order.reverse() } else { order }
+        }
+    }
+}
+
+impl DataTable {
+    /// Sorts the rows by the values of the named column.
+    ///
+    /// Values which parse as numbers come first in numeric order, then
+    /// other values in the order of their text. Missing and empty values
+    /// come last, even when `descending`. The sort is stable, so rows with
+    /// equal values keep their order.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["ann", "9"], vec!["bob", ""], vec!["cat", "10"]];
+    /// let table = DataTable::from_records(Some(vec!["name", "score"]), records).unwrap();
+    ///
+    /// let sorted = table.sort_by("score", false).unwrap();
+    /// assert_eq!(sorted[0].data(), vec!["ann", "cat", "bob"]);
+    ///
+    /// let sorted = table.sort_by("score", true).unwrap();
+    /// assert_eq!(sorted[0].data(), vec!["cat", "ann", "bob"]);
+    /// ```
+    pub fn sort_by(&self, key: &str, descending: bool) -> Result<DataTable, DataError> {
+        let start = Instant::now();
+        let column = self.column(key).ok_or(DataError::InvalidStateError)?;
+
+        let mut rows: Vec<usize> = (0..column.len()).collect();
+        rows.sort_by(|&a, &b| compare_values(cell(column, a), cell(column, b), descending));
+        let sorted = self.take_rows(&rows);
+
+        log_info!("sorted {} rows by {:?} in {:?}", rows.len(), key, start.elapsed());
+        Ok(sorted)
+    }
+}
+
+impl<R: BufRead> RowStream<R> {
+    /// Sorts the rows of the stream by the values of the named column.
+    ///
+    /// Values are ordered as in `DataTable::sort_by`. Runs of
+    /// `options.run_rows` rows are sorted in memory and written to
+    /// temporary files, which are merged as the sorted rows are read, so
+    /// streams larger than memory can be sorted. Streams which fit in a
+    /// single run are never written to disk. The temporary files are
+    /// removed when the sorted rows are dropped.
+    ///
+    /// The sort is stable, and each record keeps the row and line it was
+    /// read from.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The stream has no header naming the column,
+    ///   or a run could not be read back.
+    /// - Io : A run could not be written.
+    /// - Fails in the same way as the stream for each row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    /// use rusty_data::sort::SortOptions;
+    ///
+    /// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+    /// let data = "id,t\na,3\nb,1\nc,2\nd,1\n";
+    /// let stream = Loader::from_str(data, options).stream_rows().unwrap();
+    ///
+    /// // Runs of two rows force a merge from disk.
+    /// let sort = SortOptions { run_rows: 2, ..SortOptions::default() };
+    /// let ids: Vec<String> = stream.sort_by("t", &sort)
+    ///     .unwrap()
+    ///     .map(|r| r.unwrap().get(0).unwrap().to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(ids, vec!["b", "d", "c", "a"]);
+    /// ```
+    pub fn sort_by(self, key: &str, options: &SortOptions) -> Result<SortedRows, DataError> {
+        let headers = self.headers.clone();
+        let header_match = self.options.header_match;
+        let index = key_index(headers.as_ref().map(|h| &h[..]), key, header_match)?;
+
+        let dir = options.temp_dir.clone().unwrap_or_else(env::temp_dir);
+        let run_rows = options.run_rows.max(1);
+        let mut sorted = SortedRows {
+            rows: Rows::Memory(Vec::new().into_iter()),
+            headers: headers.clone(),
+            runs: Vec::new(),
+        };
+
+        let mut run = Vec::new();
+        for record in self {
+            run.push(record?);
+            if run.len() == run_rows {
+                sort_run(&mut run, index, options.descending);
+                sorted.runs.push(write_run(&dir, mem::take(&mut run).into_iter().map(Ok))?);
+            }
+        }
+        sort_run(&mut run, index, options.descending);
+
+        if sorted.runs.is_empty() {
+            sorted.rows = Rows::Memory(run.into_iter());
+            return Ok(sorted);
+        }
+        if !run.is_empty() {
+            sorted.runs.push(write_run(&dir, run.into_iter().map(Ok))?);
+        }
+
+        // Merges groups of neighbouring runs so that equal rows keep their order.
+        while sorted.runs.len() > MAX_MERGE_RUNS {
+            let count = sorted.runs.len();
+            for start in (0..count).step_by(MAX_MERGE_RUNS) {
+                let group = &sorted.runs[start..count.min(start + MAX_MERGE_RUNS)];
+                let readers = open_runs(group, &headers, header_match)?;
+                let merge = Merge::new(readers, vec![index; group.len()], options.descending)?;
+                let run = write_run(&dir, merge)?;
+                sorted.runs.push(run);
+            }
+            for (path, _) in sorted.runs.drain(..count) {
+                let _ = fs::remove_file(path);
+            }
+        }
+
+        let readers = open_runs(&sorted.runs, &headers, header_match)?;
+        let merge = Merge::new(readers, vec![index; sorted.runs.len()], options.descending)?;
+        sorted.rows = Rows::Runs(merge);
+        Ok(sorted)
+    }
+}
+
+impl<'a> Loader<'a> {
+    /// Merges files which are each sorted by the named column into one sorted stream.
+    ///
+    /// The files are read with the options and must have headers, which
+    /// must be the same in every file. Values are ordered as in
+    /// `DataTable::sort_by`, and rows with equal values come from the
+    /// earlier file first. Only one row of each file is held in memory,
+    /// so the merged rows can be written out as they are read, or
+    /// collected with `SortedRows::into_table`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No files were given, the files have different
+    ///   headers, none named the column, or the options hold column parsers
+    ///   or accumulators, which the merge does not apply.
+    /// - Io : A file could not be opened.
+    /// - Fails in the same way as `Loader::stream_rows` for each file.
+    ///
+    /// While reading the merged rows:
+    ///
+    /// - InvalidStateError : A file was not sorted by the column. The
+    ///   merge then ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let first = std::env::temp_dir().join("rusty-data-merge-doctest-1.csv");
+    /// let second = std::env::temp_dir().join("rusty-data-merge-doctest-2.csv");
+    /// std::fs::write(&first, "time,event\n1,start\n4,stop\n").unwrap();
+    /// std::fs::write(&second, "time,event\n2,ping\n3,ping\n").unwrap();
+    ///
+    /// let options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+    /// let table = Loader::merge_sorted(&[&first, &second], "time", options)
+    ///     .unwrap()
+    ///     .into_table()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(table[1].data(), vec!["start", "ping", "ping", "stop"]);
+    ///
+    /// let mut options = LoaderOptions { has_header: true, ..LoaderOptions::default() };
+    /// options.column_parsers.insert("time".to_string(), Box::new(|v| Ok(v.to_string())));
+    /// assert!(Loader::merge_sorted(&[&first, &second], "time", options).is_err());
+    /// # std::fs::remove_file(&first).unwrap();
+    /// # std::fs::remove_file(&second).unwrap();
+    /// ```
+    pub fn merge_sorted<P: AsRef<Path>>(files: &[P],
+                                        key: &str,
+                                        options: LoaderOptions)
+                                        -> Result<SortedRows, DataError> {
+        let mut streams = Vec::with_capacity(files.len());
+        for file in files {
+            let stream_options = options.stream_copy()?;
+            let input = Input::File(BufReader::new(File::open(file)?));
+            streams.push(RowStream::new(input, stream_options)?);
+        }
+
+        let headers = streams.first().and_then(|s| s.headers.clone());
+        if streams.iter().any(|s| s.headers != headers) {
+            return Err(DataError::InvalidStateError);
+        }
+        let index = key_index(headers.as_ref().map(|h| &h[..]), key, options.header_match)?;
+
+        let merge = Merge::new(streams, vec![index; files.len()], false)?;
+        Ok(SortedRows {
+            rows: Rows::Files(merge),
+            headers,
+            runs: Vec::new(),
+        })
+    }
+}
+
+/// Finds the column with the name among the headers.
+pub(crate) fn key_index(headers: Option<&[String]>,
+                        key: &str,
+                        header_match: HeaderMatch)
+                        -> Result<usize, DataError> {
+    headers.and_then(|h| h.iter().position(|name| header_match.matches(name, key)))
+        .ok_or(DataError::InvalidStateError)
+}
+
+/// Sorts the records of a run by the field at `index`.
+fn sort_run(run: &mut [Record], index: usize, descending: bool) {
+    run.sort_by(|a, b| compare_values(a.get(index), b.get(index), descending));
+}
+
+/// Writes the records to a new temporary file, returning its path and the number of records.
+///
+/// Each record is written as its row, line and number of fields, followed
+/// by the length and bytes of each field.
+fn write_run<I>(dir: &Path, records: I) -> Result<(PathBuf, usize), DataError>
+    where I: IntoIterator<Item = Result<Record, DataError>>
+{
+    let path = temp_path(dir, "run");
+    let mut len = 0;
+    let result = File::create(&path).map_err(DataError::from).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        for record in records {
+            let record = record?;
+            writer.write_all(&(record.row as u64).to_le_bytes())?;
+            writer.write_all(&(record.line as u64).to_le_bytes())?;
+            writer.write_all(&(record.fields.len() as u64).to_le_bytes())?;
+            for field in &record.fields {
+                writer.write_all(&(field.len() as u64).to_le_bytes())?;
+                writer.write_all(field.as_bytes())?;
+            }
+            len += 1;
+        }
+        writer.flush()?;
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => Ok((path, len)),
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+fn open_runs(runs: &[(PathBuf, usize)],
+             headers: &Option<Arc<Vec<String>>>,
+             header_match: HeaderMatch)
+             -> Result<Vec<RunReader>, DataError> {
+    runs.iter()
+        .map(|&(ref path, len)| {
+            Ok(RunReader {
+                reader: BufReader::new(File::open(path)?),
+                remaining: len,
+                headers: headers.clone(),
+                header_match,
+            })
+        })
+        .collect()
+}
+
+/// Reads back the records of a run written by `write_run`.
+struct RunReader {
+    reader: BufReader<File>,
+    remaining: usize,
+    headers: Option<Arc<Vec<String>>>,
+    header_match: HeaderMatch,
+}
+
+impl RunReader {
+    fn read_u64(&mut self) -> Result<u64, DataError> {
+        let mut buf = [0; 8];
+        self.reader.read_exact(&mut buf).map_err(|_| DataError::InvalidStateError)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_record(&mut self) -> Result<Record, DataError> {
+        let row = self.read_u64()? as usize;
+        let line = self.read_u64()? as usize;
+        let count = self.read_u64()? as usize;
+
+        let mut fields = Vec::with_capacity(count);
+        let mut bytes = Vec::new();
+        for _ in 0..count {
+            bytes.resize(self.read_u64()? as usize, 0);
+            self.reader.read_exact(&mut bytes).map_err(|_| DataError::InvalidStateError)?;
+            match ::std::str::from_utf8(&bytes) {
+                Ok(field) => fields.push(SmallString::from(field)),
+                Err(_) => return Err(DataError::InvalidStateError),
+            }
+        }
+
+        Ok(Record {
+            fields,
+            row,
+            line,
+            headers: self.headers.clone(),
+            header_match: self.header_match,
+        })
+    }
+}
+
+impl Iterator for RunReader {
+    type Item = Result<Record, DataError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.read_record())
+    }
+}
+
+/// The next record of one source of a `Merge`.
+struct Head {
+    record: Record,
+    source: usize,
+    key: usize,
+    descending: bool,
+}
+
+impl Ord for Head {
+    /// Orders the heads so that the largest comes first from the sources,
+    /// as `BinaryHeap` gives the largest first.
+    fn cmp(&self, other: &Head) -> Ordering {
+        compare_values(self.record.get(self.key), other.record.get(other.key), self.descending)
+            .then(self.source.cmp(&other.source))
+            .reverse()
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Head) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Head) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+/// Merges sources of records which are each sorted by a key.
+///
+/// Records with equal keys come from the earlier source first.
+pub(crate) struct Merge<S> {
+    sources: Vec<S>,
+    keys: Vec<usize>,
+    heads: BinaryHeap<Head>,
+    descending: bool,
+}
+
+impl<S> Merge<S>
+    where S: Iterator<Item = Result<Record, DataError>>
+{
+    /// Creates a merge of the sources, whose keys are the fields at `keys`.
+    pub(crate) fn new(sources: Vec<S>,
+                      keys: Vec<usize>,
+                      descending: bool)
+                      -> Result<Merge<S>, DataError> {
+        let mut merge = Merge {
+            heads: BinaryHeap::with_capacity(sources.len()),
+            sources,
+            keys,
+            descending,
+        };
+
+        for source in 0..merge.sources.len() {
+            merge.advance(source, None)?;
+        }
+        Ok(merge)
+    }
+
+    /// Reads the next record of the source into the heads.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The record comes before `previous`, the last
+    ///   record of the source, so the source is not sorted.
+    fn advance(&mut self, source: usize, previous: Option<&Record>) -> Result<(), DataError> {
+        if let Some(record) = self.sources[source].next() {
+            let record = record?;
+            let key = self.keys[source];
+            if let Some(previous) = previous {
+                if compare_values(record.get(key), previous.get(key), self.descending) ==
+                   Ordering::Less {
+                    return Err(DataError::InvalidStateError);
+                }
+            }
+
+            self.heads.push(Head {
+                record,
+                source,
+                key,
+                descending: self.descending,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<S> Iterator for Merge<S>
+    where S: Iterator<Item = Result<Record, DataError>>
+{
+    type Item = Result<Record, DataError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.heads.pop()?;
+        match self.advance(head.source, Some(&head.record)) {
+            Ok(()) => Some(Ok(head.record)),
+            Err(e) => {
+                // Stops the merge, as its order can no longer be kept.
+                self.heads.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+enum Rows {
+    Memory(vec::IntoIter<Record>),
+    Runs(Merge<RunReader>),
+    Files(Merge<RowStream<Input<'static>>>),
+}
+
+/// Iterator over sorted rows.
+///
+/// Created by `RowStream::sort_by` and `Loader::merge_sorted`.
+pub struct SortedRows {
+    rows: Rows,
+    headers: Option<Arc<Vec<String>>>,
+    // The path and number of records of each run on disk.
+    runs: Vec<(PathBuf, usize)>,
+}
+
+impl SortedRows {
+    /// The names of the columns, if the rows have a header.
+    pub fn headers(&self) -> Option<&[String]> {
+        self.headers.as_ref().map(|h| &h[..])
+    }
+
+    /// Collects the rows into a table.
+    ///
+    /// The columns are named by the headers, if there are any. Values are
+    /// kept as they were read, and none are marked as missing.
+    ///
+    /// # Failures
+    ///
+    /// - Fails in the same way as the rows.
+    pub fn into_table(mut self) -> Result<DataTable, DataError> {
+        let mut columns: Vec<DataColumn> = match self.headers {
+            Some(ref headers) => {
+                headers.iter()
+                    .map(|name| {
+                        let mut column = DataColumn::empty();
+                        column.name = Some(name.clone());
+                        column
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        for record in &mut self {
+            let record = record?;
+            while columns.len() < record.len() {
+                columns.push(DataColumn::empty());
+            }
+            for (column, field) in columns.iter_mut().zip(&record.fields) {
+                column.push(field.as_str());
+            }
+        }
+
+        Ok(DataTable { data_cols: columns })
+    }
+}
+
+impl Iterator for SortedRows {
+    type Item = Result<Record, DataError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.rows {
+            Rows::Memory(ref mut records) => records.next().map(Ok),
+            Rows::Runs(ref mut merge) => merge.next(),
+            Rows::Files(ref mut merge) => merge.next(),
+        }
+    }
+}
+
+impl Drop for SortedRows {
+    fn drop(&mut self) {
+        // Closes the runs before removing them.
+        self.rows = Rows::Memory(Vec::new().into_iter());
+        for (path, _) in &self.runs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! The stats module.
+//!
+//! Provides summary statistics over the values of DataColumns.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+
+impl DataColumn {
+    /// Parses the values and returns the row index and value of the smallest.
+    ///
+    /// Missing values, and values which cannot be compared such as `NaN`,
+    /// are skipped. The first of several equal values is returned.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec!["10", "9", "100"]);
+    ///
+    /// assert_eq!(dc.min_as::<u32>().unwrap(), Some((1, 9)));
+    /// assert_eq!(dc.max_as::<u32>().unwrap(), Some((2, 100)));
+    /// assert_eq!(dc.argmax::<u32>().unwrap(), Some(2));
+    /// ```
+    pub fn min_as<T>(&self) -> Result<Option<(usize, T)>, DataError>
+        where T: FromStr + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.extremum(Ordering::Less)
+    }
+
+    /// Parses the values and returns the row index and value of the largest.
+    ///
+    /// Missing values, and values which cannot be compared such as `NaN`,
+    /// are skipped. The first of several equal values is returned.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn max_as<T>(&self) -> Result<Option<(usize, T)>, DataError>
+        where T: FromStr + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.extremum(Ordering::Greater)
+    }
+
+    /// The row index of the smallest value, see `min_as`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn argmin<T>(&self) -> Result<Option<usize>, DataError>
+        where T: FromStr + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        Ok(self.min_as::<T>()?.map(|(idx, _)| idx))
+    }
+
+    /// The row index of the largest value, see `max_as`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn argmax<T>(&self) -> Result<Option<usize>, DataError>
+        where T: FromStr + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        Ok(self.max_as::<T>()?.map(|(idx, _)| idx))
+    }
+
+    /// The most common values in the column and how often they occur.
+    ///
+    /// Values are compared as strings. When several values are equally
+    /// common they are all returned in the order they first appear.
+    /// Missing values are not counted. Returns `None` if there are no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec!["b", "a", "b", "a", "c"]);
+    ///
+    /// assert_eq!(dc.mode(), Some((vec!["b", "a"], 2)));
+    /// ```
+    pub fn mode(&self) -> Option<(Vec<&str>, usize)> {
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+
+        for (idx, val) in self.iter().enumerate() {
+            if !self.is_missing(idx) {
+                counts.entry(val).or_insert((idx, 0)).1 += 1;
+            }
+        }
+
+        let top = counts.values().map(|&(_, count)| count).max()?;
+
+        let mut modes: Vec<(usize, &str)> = counts.iter()
+            .filter(|&(_, &(_, count))| count == top)
+            .map(|(&val, &(first, _))| (first, val))
+            .collect();
+        modes.sort();
+
+        Some((modes.into_iter().map(|(_, val)| val).collect(), top))
+    }
+
+    /// Finds the first value which compares as `wanted` to every other value.
+    fn extremum<T>(&self, wanted: Ordering) -> Result<Option<(usize, T)>, DataError>
+        where T: FromStr + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let mut best: Option<(usize, T)> = None;
+
+        for idx in 0..self.len() {
+            if self.is_missing(idx) {
+                continue;
+            }
+
+            let val: T = self.get_as(idx)?;
+
+            // Skips values which are not comparable with themselves, such as NaN.
+            if val.partial_cmp(&val).is_none() {
+                continue;
+            }
+
+            let better = match best {
+                Some((_, ref current)) => val.partial_cmp(current) == Some(wanted),
+                None => true,
+            };
+
+            if better {
+                best = Some((idx, val));
+            }
+        }
+
+        Ok(best)
+    }
+}
+
+impl DataTable {
+    /// Gets the `n` rows with the largest values in the named column, largest first.
+    ///
+    /// Values are parsed as floats. Missing values and `NaN` are skipped,
+    /// and equal values keep their table order. Selecting the rows does not
+    /// sort the whole column.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    /// - DataCastError : A value could not be parsed as a float.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["ann", "71"], vec!["bob", "93"], vec!["cat", "85"]];
+    /// let table = DataTable::from_records(Some(vec!["name", "score"]), records).unwrap();
+    ///
+    /// let top = table.nlargest(2, "score").unwrap();
+    /// assert_eq!(top[0].data(), vec!["bob", "cat"]);
+    ///
+    /// let bottom = table.nsmallest(1, "score").unwrap();
+    /// assert_eq!(bottom[0].data(), vec!["ann"]);
+    /// ```
+    pub fn nlargest(&self, n: usize, column: &str) -> Result<DataTable, DataError> {
+        self.select_extremes(n, column, Ordering::Greater)
+    }
+
+    /// Gets the `n` rows with the smallest values in the named column, smallest first.
+    ///
+    /// Values are handled as in `nlargest`.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    /// - DataCastError : A value could not be parsed as a float.
+    pub fn nsmallest(&self, n: usize, column: &str) -> Result<DataTable, DataError> {
+        self.select_extremes(n, column, Ordering::Less)
+    }
+
+    /// Gets the `n` rows whose values come first when ordered towards `first`.
+    fn select_extremes(&self,
+                       n: usize,
+                       column: &str,
+                       first: Ordering)
+                       -> Result<DataTable, DataError> {
+        let column = self.column(column).ok_or(DataError::InvalidStateError)?;
+        let mut values = Vec::with_capacity(column.len());
+
+        for idx in 0..column.len() {
+            if column.is_missing(idx) {
+                continue;
+            }
+
+            let val: f64 = column.get_as(idx)?;
+            if !val.is_nan() {
+                values.push((idx, val));
+            }
+        }
+
+        // Orders the wanted values first, breaking ties by their row.
+        let order = |a: &(usize, f64), b: &(usize, f64)| {
+            let by_value = a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal);
+            let by_value = if first == Ordering::Greater { by_value.reverse() } else { by_value };
+            by_value.then(a.0.cmp(&b.0))
+        };
+
+        if n == 0 {
+            values.clear();
+        } else if n < values.len() {
+            values.select_nth_unstable_by(n - 1, order);
+            values.truncate(n);
+        }
+        values.sort_unstable_by(order);
+
+        let rows: Vec<usize> = values.into_iter().map(|(idx, _)| idx).collect();
+        Ok(self.take_rows(&rows))
+    }
+}
+
+/// The missing values of one column within a `MissingReport`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMissing {
+    /// The index of the column in the table.
+    pub column: usize,
+    /// The name of the column.
+    pub name: Option<String>,
+    /// The number of missing values.
+    pub null_count: usize,
+    /// The fraction of values which are present.
+    pub completeness: f64,
+}
+
+/// A summary of the columns of a table which have missing values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MissingReport {
+    /// The number of rows in the table.
+    pub rows: usize,
+    /// The columns with at least one missing value, in table order.
+    pub columns: Vec<ColumnMissing>,
+}
+
+impl MissingReport {
+    /// Returns true if no column has missing values.
+    pub fn is_complete(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+impl fmt::Display for MissingReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_complete() {
+            return write!(f, "no missing values in {} rows", self.rows);
+        }
+
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            match column.name {
+                Some(ref name) => write!(f, "{}", name)?,
+                None => write!(f, "column {}", column.column)?,
+            }
+            write!(f,
+                   ": {} of {} missing ({:.1}% complete)",
+                   column.null_count,
+                   self.rows,
+                   column.completeness * 100.0)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DataTable {
+    /// Summarizes which columns have missing values and how many.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{DataColumn, DataTable};
+    ///
+    /// let mut table = DataTable::empty();
+    /// table.data_cols.push(DataColumn::from_vec(vec![1, 2]));
+    ///
+    /// let mut gappy = DataColumn::empty();
+    /// gappy.name = Some("age".to_string());
+    /// gappy.push_opt(None::<u32>);
+    /// gappy.push_opt(Some(30));
+    /// table.data_cols.push(gappy);
+    ///
+    /// let report = table.missing_report();
+    ///
+    /// assert_eq!(report.columns.len(), 1);
+    /// assert_eq!(report.columns[0].column, 1);
+    /// assert_eq!(report.to_string(), "age: 1 of 2 missing (50.0% complete)");
+    /// ```
+    pub fn missing_report(&self) -> MissingReport {
+        let columns = self.data_cols
+            .iter()
+            .enumerate()
+            .filter(|&(_, c)| c.has_missing())
+            .map(|(i, c)| {
+                ColumnMissing {
+                    column: i,
+                    name: c.name.clone(),
+                    null_count: c.null_count(),
+                    completeness: c.completeness(),
+                }
+            })
+            .collect();
+
+        MissingReport {
+            rows: self.rows(),
+            columns,
+        }
+    }
+}
+
+/// How the bins of a histogram are chosen.
+///
+/// The rules choose a bin width from the number of values and how they
+/// are spread, then use as many equal-width bins as cover the range.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Bins {
+    /// The given number of bins.
+    Count(usize),
+    /// `log2(n) + 1` bins, which suits small samples from a normal distribution.
+    #[default]
+    Sturges,
+    /// `sqrt(n)` bins.
+    Sqrt,
+    /// Bins of width `3.49 * sd / n^(1/3)`, which suits normal distributions.
+    Scott,
+    /// Bins of width `2 * IQR / n^(1/3)`, which is robust to outliers.
+    FreedmanDiaconis,
+}
+
+/// The counts of values in a set of equal-width bins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Histogram {
+    /// The edges of the bins, one more than there are bins.
+    ///
+    /// Each bin holds the values from its lower edge up to but not
+    /// including its upper edge, except the last which includes both.
+    pub edges: Vec<f64>,
+    /// The number of values in each bin.
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// The width of each bin.
+    pub fn bin_width(&self) -> f64 {
+        match (self.edges.first(), self.edges.last()) {
+            (Some(first), Some(last)) if !self.counts.is_empty() => {
+                (last - first) / self.counts.len() as f64
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// The largest number of bins chosen by a rule.
+const MAX_AUTO_BINS: usize = 1000;
+
+impl DataColumn {
+    /// Parses the values as floats and counts them in equal-width bins.
+    ///
+    /// The bins cover the smallest to the largest value, widened by a half
+    /// either side if every value is the same. Missing values and values
+    /// which are not finite are skipped. A column without values gives a
+    /// single empty bin from 0 to 1.
+    ///
+    /// The rules never choose more than 1000 bins, and fall back to
+    /// `Bins::Sturges` when the values have no spread.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed as a float.
+    /// - InvalidStateError : `Bins::Count(0)` was given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::stats::Bins;
+    ///
+    /// let dc = DataColumn::from_vec(vec![1.0, 2.0, 2.5, 3.0, 5.0]);
+    ///
+    /// let hist = dc.histogram(Bins::Count(2)).unwrap();
+    ///
+    /// assert_eq!(hist.edges, vec![1.0, 3.0, 5.0]);
+    /// assert_eq!(hist.counts, vec![3, 2]);
+    /// assert_eq!(dc.histogram(Bins::Sturges).unwrap().counts.len(), 4);
+    /// ```
+    pub fn histogram(&self, bins: Bins) -> Result<Histogram, DataError> {
+        let mut values = Vec::with_capacity(self.len());
+        for idx in 0..self.len() {
+            if !self.is_missing(idx) {
+                let val: f64 = self.get_as(idx)?;
+                if val.is_finite() {
+                    values.push(val);
+                }
+            }
+        }
+
+        histogram_of(&values, bins)
+    }
+}
+
+/// Counts the finite values in equal-width bins, see `DataColumn::histogram`.
+pub(crate) fn histogram_of(values: &[f64], bins: Bins) -> Result<Histogram, DataError> {
+    if bins == Bins::Count(0) {
+        return Err(DataError::InvalidStateError);
+    }
+
+    let (mut low, mut high) = values.iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if values.is_empty() {
+        low = 0.0;
+        high = 1.0;
+    } else if low == high {
+        low -= 0.5;
+        high += 0.5;
+    }
+
+    let n = values.len() as f64;
+    let sturges = (n.max(1.0).log2().ceil() as usize + 1).min(MAX_AUTO_BINS);
+    let from_width = |width: f64| if width > 0.0 && width.is_finite() {
+        (((high - low) / width).ceil() as usize).clamp(1, MAX_AUTO_BINS)
+    } else {
+        sturges
+    };
+
+    let count = match bins {
+        Bins::Count(count) => count,
+        Bins::Sturges => sturges,
+        Bins::Sqrt => (n.sqrt().ceil() as usize).clamp(1, MAX_AUTO_BINS),
+        Bins::Scott => {
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+            from_width(3.49 * variance.sqrt() / n.cbrt())
+        }
+        Bins::FreedmanDiaconis => {
+            let mut sorted = values.to_vec();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            let iqr = quantile_sorted(&sorted, 0.75) - quantile_sorted(&sorted, 0.25);
+            from_width(2.0 * iqr / n.cbrt())
+        }
+    };
+
+    let width = (high - low) / count as f64;
+    let mut edges: Vec<f64> = (0..count).map(|i| low + width * i as f64).collect();
+    edges.push(high);
+
+    let mut counts = vec![0; count];
+    for &val in values {
+        let bin = ((val - low) / width) as usize;
+        counts[bin.min(count - 1)] += 1;
+    }
+
+    Ok(Histogram { edges, counts })
+}
+
+/// The `q` quantile of sorted values, interpolating between them.
+fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = q * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64)
+}
@@ -0,0 +1,848 @@
+//! The storage module.
+//!
+//! Provides the representations used to hold the values
+//! within DataColumns.
+
+use std::borrow::{Borrow, Cow};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Deref;
+use std::str;
+use std::sync::{Arc, Mutex};
+
+/// The number of bytes a SmallString can hold without allocating.
+pub const INLINE_CAPACITY: usize = 22;
+
+/// A string which stores short values inline.
+///
+/// Most fields in a data file are short numbers or labels. Values of
+/// up to `INLINE_CAPACITY` bytes are kept inside the SmallString itself
+/// and only longer values are allocated on the heap.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::storage::SmallString;
+///
+/// let short = SmallString::from("3.14");
+/// let long = SmallString::from("a value which is too long to be inline");
+///
+/// assert!(short.is_inline());
+/// assert!(!long.is_inline());
+/// assert_eq!(&short[..], "3.14");
+/// ```
+#[derive(Clone)]
+pub struct SmallString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, buf: [u8; INLINE_CAPACITY] },
+    Heap(Box<str>),
+}
+
+impl SmallString {
+    /// Constructs an empty SmallString.
+    pub fn new() -> SmallString {
+        SmallString(Repr::Inline {
+            len: 0,
+            buf: [0; INLINE_CAPACITY],
+        })
+    }
+
+    /// Gets the value as a string slice.
+    pub fn as_str(&self) -> &str {
+        match self.0 {
+            Repr::Inline { len, ref buf } => {
+                // The inline buffer is only ever filled from a complete &str.
+                unsafe { str::from_utf8_unchecked(&buf[..len as usize]) }
+            }
+            Repr::Heap(ref s) => s,
+        }
+    }
+
+    /// Returns true if the value is stored without a heap allocation.
+    pub fn is_inline(&self) -> bool {
+        match self.0 {
+            Repr::Inline { .. } => true,
+            Repr::Heap(_) => false,
+        }
+    }
+
+    /// Consumes self and returns the value as a String.
+    pub fn into_string(self) -> String {
+        match self.0 {
+            Repr::Inline { .. } => self.as_str().to_string(),
+            Repr::Heap(s) => s.into_string(),
+        }
+    }
+
+    fn inline(s: &str) -> Option<SmallString> {
+        if s.len() > INLINE_CAPACITY {
+            return None;
+        }
+
+        let mut buf = [0; INLINE_CAPACITY];
+        buf[..s.len()].copy_from_slice(s.as_bytes());
+
+        Some(SmallString(Repr::Inline {
+            len: s.len() as u8,
+            buf,
+        }))
+    }
+}
+
+impl Default for SmallString {
+    fn default() -> SmallString {
+        SmallString::new()
+    }
+}
+
+impl<'a> From<&'a str> for SmallString {
+    fn from(s: &'a str) -> SmallString {
+        SmallString::inline(s).unwrap_or_else(|| SmallString(Repr::Heap(s.into())))
+    }
+}
+
+impl From<String> for SmallString {
+    fn from(s: String) -> SmallString {
+        SmallString::inline(&s).unwrap_or_else(|| SmallString(Repr::Heap(s.into_boxed_str())))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for SmallString {
+    fn from(s: Cow<'a, str>) -> SmallString {
+        match s {
+            Cow::Borrowed(s) => SmallString::from(s),
+            Cow::Owned(s) => SmallString::from(s),
+        }
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for SmallString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &SmallString) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialEq<str> for SmallString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for SmallString {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for SmallString {
+    fn partial_cmp(&self, other: &SmallString) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallString {
+    fn cmp(&self, other: &SmallString) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// A bump arena holding the bytes of many strings back to back.
+///
+/// Each value is stored as an offset and length into a single buffer
+/// so that a column of many small values needs only a handful of
+/// allocations.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::storage::StringArena;
+///
+/// let mut arena = StringArena::new();
+/// arena.push("1.5");
+/// arena.push("2.5");
+///
+/// assert_eq!(arena.len(), 2);
+/// assert_eq!(arena.get(1), Some("2.5"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StringArena {
+    bytes: String,
+    spans: Vec<(usize, usize)>,
+}
+
+impl StringArena {
+    /// Constructs an empty StringArena.
+    pub fn new() -> StringArena {
+        StringArena {
+            bytes: String::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// The number of values in the arena.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns true if the arena holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Appends a value to the arena.
+    pub fn push(&mut self, val: &str) {
+        self.spans.push((self.bytes.len(), val.len()));
+        self.bytes.push_str(val);
+    }
+
+    /// Gets the value at the index, if it exists.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.spans.get(idx).map(|&(offset, len)| &self.bytes[offset..offset + len])
+    }
+
+    /// Gets the value at the index without checking that it is in range.
+    pub(crate) unsafe fn get_unchecked(&self, idx: usize) -> &str {
+        let (offset, len) = *self.spans.get_unchecked(idx);
+        self.bytes.get_unchecked(offset..offset + len)
+    }
+
+    /// Returns an iterator over the values in the arena.
+    pub fn iter(&self) -> ArenaIter<'_> {
+        ArenaIter {
+            bytes: &self.bytes,
+            spans: self.spans.iter(),
+        }
+    }
+
+    /// Shrinks the underlying buffers to fit the data.
+    pub fn shrink_to_fit(&mut self) {
+        self.bytes.shrink_to_fit();
+        self.spans.shrink_to_fit();
+    }
+}
+
+/// An iterator over the values in a StringArena.
+pub struct ArenaIter<'a> {
+    bytes: &'a str,
+    spans: ::std::slice::Iter<'a, (usize, usize)>,
+}
+
+impl<'a> Iterator for ArenaIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.spans.next().map(|&(offset, len)| &self.bytes[offset..offset + len])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spans.size_hint()
+    }
+}
+
+/// A run-length encoded sequence of strings.
+///
+/// Consecutive repeated values are stored once along with the index
+/// at which the run ends. Suited to sorted or slowly changing columns.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::storage::RunLength;
+///
+/// let mut runs = RunLength::new();
+/// for val in &["a", "a", "a", "b", "b", "a"] {
+///     runs.push(val);
+/// }
+///
+/// assert_eq!(runs.len(), 6);
+/// assert_eq!(runs.runs(), 3);
+/// assert_eq!(runs.get(4), Some("b"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RunLength {
+    values: Vec<SmallString>,
+    ends: Vec<usize>,
+}
+
+impl RunLength {
+    /// Constructs an empty RunLength.
+    pub fn new() -> RunLength {
+        RunLength {
+            values: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// The number of values in the sequence.
+    pub fn len(&self) -> usize {
+        self.ends.last().cloned().unwrap_or(0)
+    }
+
+    /// Returns true if the sequence holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The number of runs used to store the sequence.
+    pub fn runs(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Appends a value to the sequence.
+    pub fn push(&mut self, val: &str) {
+        if let Some(last) = self.values.last() {
+            if last.as_str() == val {
+                *self.ends.last_mut().unwrap() += 1;
+                return;
+            }
+        }
+
+        let end = self.len() + 1;
+        self.values.push(SmallString::from(val));
+        self.ends.push(end);
+    }
+
+    /// Gets the value at the index, if it exists.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        let run = self.ends.partition_point(|&end| end <= idx);
+        self.values.get(run).map(|s| s.as_str())
+    }
+
+    /// Returns an iterator over the values in the sequence.
+    pub fn iter(&self) -> RunLengthIter<'_> {
+        RunLengthIter {
+            runs: self,
+            run: 0,
+            idx: 0,
+        }
+    }
+
+    /// Shrinks the underlying buffers to fit the data.
+    pub fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.ends.shrink_to_fit();
+    }
+}
+
+/// An iterator over the values in a RunLength.
+pub struct RunLengthIter<'a> {
+    runs: &'a RunLength,
+    run: usize,
+    idx: usize,
+}
+
+impl<'a> Iterator for RunLengthIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while self.run < self.runs.ends.len() && self.runs.ends[self.run] <= self.idx {
+            self.run += 1;
+        }
+
+        if self.run == self.runs.ends.len() {
+            return None;
+        }
+
+        self.idx += 1;
+        Some(self.runs.values[self.run].as_str())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.runs.len() - self.idx;
+        (remaining, Some(remaining))
+    }
+}
+
+/// A dictionary encoded sequence of strings.
+///
+/// Each distinct value is stored once and the sequence itself is held as
+/// indices into the dictionary. Suited to columns with few distinct values.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::storage::Dictionary;
+///
+/// let mut dict = Dictionary::new();
+/// for val in &["PASS", "FAIL", "PASS", "PASS"] {
+///     dict.push(val);
+/// }
+///
+/// assert_eq!(dict.len(), 4);
+/// assert_eq!(dict.distinct(), 2);
+/// assert_eq!(dict.get(1), Some("FAIL"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    values: Vec<SmallString>,
+    lookup: HashMap<SmallString, u32>,
+    codes: Vec<u32>,
+}
+
+impl Dictionary {
+    /// Constructs an empty Dictionary.
+    pub fn new() -> Dictionary {
+        Dictionary {
+            values: Vec::new(),
+            lookup: HashMap::new(),
+            codes: Vec::new(),
+        }
+    }
+
+    /// The number of values in the sequence.
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// Returns true if the sequence holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// The number of distinct values in the dictionary.
+    pub fn distinct(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Appends a value to the sequence.
+    pub fn push(&mut self, val: &str) {
+        let code = match self.lookup.get(val) {
+            Some(&code) => code,
+            None => {
+                let code = self.values.len() as u32;
+                self.values.push(SmallString::from(val));
+                self.lookup.insert(SmallString::from(val), code);
+                code
+            }
+        };
+
+        self.codes.push(code);
+    }
+
+    /// Gets the value at the index, if it exists.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.codes.get(idx).map(|&code| self.values[code as usize].as_str())
+    }
+
+    /// Returns an iterator over the values in the sequence.
+    pub fn iter(&self) -> DictionaryIter<'_> {
+        DictionaryIter {
+            values: &self.values,
+            codes: self.codes.iter(),
+        }
+    }
+
+    /// Shrinks the underlying buffers to fit the data.
+    pub fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.lookup.shrink_to_fit();
+        self.codes.shrink_to_fit();
+    }
+}
+
+/// An iterator over the values in a Dictionary.
+pub struct DictionaryIter<'a> {
+    values: &'a [SmallString],
+    codes: ::std::slice::Iter<'a, u32>,
+}
+
+impl<'a> Iterator for DictionaryIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.codes.next().map(|&code| self.values[code as usize].as_str())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.codes.size_hint()
+    }
+}
+
+/// A pool of shared strings.
+///
+/// Interning a value returns a shared reference to the single copy of
+/// that value in the pool, so columns full of repeated values hold one
+/// allocation per distinct value. Clones of an Interner share the same
+/// pool, which lets every column of a table share their values.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use rusty_data::storage::Interner;
+///
+/// let interner = Interner::new();
+/// let a = interner.intern("PASS");
+/// let b = interner.clone().intern("PASS");
+///
+/// assert!(Arc::ptr_eq(&a, &b));
+/// assert_eq!(interner.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    pool: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    /// Constructs an empty pool.
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Gets the shared copy of the value, adding it to the pool if needed.
+    pub fn intern(&self, val: &str) -> Arc<str> {
+        let mut pool = self.lock();
+
+        match pool.get(val) {
+            Some(shared) => shared.clone(),
+            None => {
+                let shared: Arc<str> = Arc::from(val);
+                pool.insert(shared.clone());
+                shared
+            }
+        }
+    }
+
+    /// The number of distinct values in the pool.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if the pool holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if both Interners share the same pool.
+    pub fn shares_pool(&self, other: &Interner) -> bool {
+        Arc::ptr_eq(&self.pool, &other.pool)
+    }
+
+    fn lock(&self) -> ::std::sync::MutexGuard<'_, HashSet<Arc<str>>> {
+        // The pool is never left part way through an update so a
+        // poisoned lock still holds a valid set.
+        self.pool.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A sequence of strings held in an Interner's pool.
+#[derive(Clone, Debug)]
+pub(crate) struct Interned {
+    values: Vec<Arc<str>>,
+    interner: Interner,
+}
+
+impl Interned {
+    pub(crate) fn new(interner: Interner) -> Interned {
+        Interned {
+            values: Vec::new(),
+            interner,
+        }
+    }
+
+    pub(crate) fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    fn push(&mut self, val: &str) {
+        let shared = self.interner.intern(val);
+        self.values.push(shared);
+    }
+}
+
+/// The compressed representations available for a DataColumn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Store repeated consecutive values once, see `RunLength`.
+    RunLength,
+    /// Store each distinct value once, see `Dictionary`.
+    Dictionary,
+}
+
+/// The representation backing a DataColumn.
+#[derive(Clone, Debug)]
+pub(crate) enum Storage {
+    /// Each value is held as its own SmallString.
+    Inline(Vec<SmallString>),
+    /// All values share a single StringArena.
+    Arena(StringArena),
+    /// Repeated consecutive values are stored once.
+    RunLength(RunLength),
+    /// Each distinct value is stored once.
+    Dictionary(Dictionary),
+    /// Values are shared through an Interner's pool.
+    Interned(Interned),
+}
+
+impl Storage {
+    pub(crate) fn len(&self) -> usize {
+        match *self {
+            Storage::Inline(ref v) => v.len(),
+            Storage::Arena(ref a) => a.len(),
+            Storage::RunLength(ref r) => r.len(),
+            Storage::Dictionary(ref d) => d.len(),
+            Storage::Interned(ref i) => i.values.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, idx: usize) -> Option<&str> {
+        match *self {
+            Storage::Inline(ref v) => v.get(idx).map(|s| s.as_str()),
+            Storage::Arena(ref a) => a.get(idx),
+            Storage::RunLength(ref r) => r.get(idx),
+            Storage::Dictionary(ref d) => d.get(idx),
+            Storage::Interned(ref i) => i.values.get(idx).map(|s| &s[..]),
+        }
+    }
+
+    /// Finds the indices of every value equal to `val`.
+    ///
+    /// Compressed representations compare each distinct value or run once.
+    pub(crate) fn positions(&self, val: &str) -> Vec<usize> {
+        match *self {
+            Storage::Dictionary(ref d) => match d.lookup.get(val) {
+                Some(&code) => {
+                    d.codes
+                        .iter()
+                        .enumerate()
+                        .filter(|&(_, &c)| c == code)
+                        .map(|(i, _)| i)
+                        .collect()
+                }
+                None => Vec::new(),
+            },
+            Storage::RunLength(ref r) => {
+                let mut positions = Vec::new();
+                let mut start = 0;
+                for (value, &end) in r.values.iter().zip(r.ends.iter()) {
+                    if value.as_str() == val {
+                        positions.extend(start..end);
+                    }
+                    start = end;
+                }
+                positions
+            }
+            _ => self.iter().enumerate().filter(|&(_, s)| s == val).map(|(i, _)| i).collect(),
+        }
+    }
+
+    /// Gets the value at the index without checking that it is in range.
+    pub(crate) unsafe fn get_unchecked(&self, idx: usize) -> &str {
+        match *self {
+            Storage::Inline(ref v) => v.get_unchecked(idx).as_str(),
+            Storage::Arena(ref a) => a.get_unchecked(idx),
+            Storage::Interned(ref i) => i.values.get_unchecked(idx),
+            // Finding the run still needs a search so only the final check is skipped.
+            Storage::RunLength(ref r) => r.get(idx).unwrap_unchecked(),
+            Storage::Dictionary(ref d) => d.get(idx).unwrap_unchecked(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, val: SmallString) {
+        match *self {
+            Storage::Inline(ref mut v) => v.push(val),
+            Storage::Arena(ref mut a) => a.push(&val),
+            Storage::RunLength(ref mut r) => r.push(&val),
+            Storage::Dictionary(ref mut d) => d.push(&val),
+            Storage::Interned(ref mut i) => i.push(&val),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> StorageIter<'_> {
+        match *self {
+            Storage::Inline(ref v) => StorageIter::Inline(v.iter()),
+            Storage::Arena(ref a) => StorageIter::Arena(a.iter()),
+            Storage::RunLength(ref r) => StorageIter::RunLength(r.iter()),
+            Storage::Dictionary(ref d) => StorageIter::Dictionary(d.iter()),
+            Storage::Interned(ref i) => StorageIter::Interned(i.values.iter()),
+        }
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        match *self {
+            Storage::Inline(ref mut v) => v.shrink_to_fit(),
+            Storage::Arena(ref mut a) => a.shrink_to_fit(),
+            Storage::RunLength(ref mut r) => r.shrink_to_fit(),
+            Storage::Dictionary(ref mut d) => d.shrink_to_fit(),
+            Storage::Interned(ref mut i) => i.values.shrink_to_fit(),
+        }
+    }
+
+    /// Applies an edit to the values, keeping the current representation.
+    ///
+    /// Representations other than `Inline` are rebuilt after the edit.
+    pub(crate) fn edit<F, T>(&mut self, f: F) -> T
+        where F: FnOnce(&mut Vec<SmallString>) -> T
+    {
+        if let Storage::Inline(ref mut v) = *self {
+            return f(v);
+        }
+
+        let storage = mem::replace(self, Storage::Inline(Vec::new()));
+        let kind = storage.kind();
+        let mut values = storage.into_inline();
+        let result = f(&mut values);
+
+        *self = match kind {
+            StorageKind::Inline => Storage::Inline(values),
+            StorageKind::Arena => Storage::Inline(values).into_arena_storage(),
+            StorageKind::Compressed(c) => Storage::Inline(values).into_compressed(c),
+            StorageKind::Interned(interner) => Storage::Inline(values).into_interned(interner),
+        };
+
+        result
+    }
+
+    fn kind(&self) -> StorageKind {
+        match *self {
+            Storage::Inline(_) => StorageKind::Inline,
+            Storage::Arena(_) => StorageKind::Arena,
+            Storage::RunLength(_) => StorageKind::Compressed(Compression::RunLength),
+            Storage::Dictionary(_) => StorageKind::Compressed(Compression::Dictionary),
+            Storage::Interned(ref i) => StorageKind::Interned(i.interner.clone()),
+        }
+    }
+
+    fn into_arena_storage(self) -> Storage {
+        Storage::Arena(self.into_arena())
+    }
+
+    /// Converts the storage into a Vec of SmallStrings.
+    pub(crate) fn into_inline(self) -> Vec<SmallString> {
+        match self {
+            Storage::Inline(v) => v,
+            other => other.iter().map(SmallString::from).collect(),
+        }
+    }
+
+    /// Converts the storage into a StringArena.
+    pub(crate) fn into_arena(self) -> StringArena {
+        match self {
+            Storage::Arena(a) => a,
+            other => {
+                let mut arena = StringArena::new();
+                for s in other.iter() {
+                    arena.push(s);
+                }
+                arena
+            }
+        }
+    }
+
+    /// Converts the storage into values shared through the interner's pool.
+    pub(crate) fn into_interned(self, interner: Interner) -> Storage {
+        let mut interned = Interned::new(interner);
+        for s in self.iter() {
+            interned.push(s);
+        }
+        Storage::Interned(interned)
+    }
+
+    /// Converts the storage into the given compressed representation.
+    pub(crate) fn into_compressed(self, compression: Compression) -> Storage {
+        match (compression, self) {
+            (Compression::RunLength, Storage::RunLength(r)) => Storage::RunLength(r),
+            (Compression::Dictionary, Storage::Dictionary(d)) => Storage::Dictionary(d),
+            (Compression::RunLength, other) => {
+                let mut runs = RunLength::new();
+                for s in other.iter() {
+                    runs.push(s);
+                }
+                Storage::RunLength(runs)
+            }
+            (Compression::Dictionary, other) => {
+                let mut dict = Dictionary::new();
+                for s in other.iter() {
+                    dict.push(s);
+                }
+                Storage::Dictionary(dict)
+            }
+        }
+    }
+}
+
+/// The representation used by a Storage.
+enum StorageKind {
+    Inline,
+    Arena,
+    Compressed(Compression),
+    Interned(Interner),
+}
+
+/// An iterator over the values held in a Storage.
+pub(crate) enum StorageIter<'a> {
+    Inline(::std::slice::Iter<'a, SmallString>),
+    Arena(ArenaIter<'a>),
+    RunLength(RunLengthIter<'a>),
+    Dictionary(DictionaryIter<'a>),
+    Interned(::std::slice::Iter<'a, Arc<str>>),
+}
+
+impl<'a> Iterator for StorageIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        match *self {
+            StorageIter::Inline(ref mut it) => it.next().map(|s| s.as_str()),
+            StorageIter::Arena(ref mut it) => it.next(),
+            StorageIter::RunLength(ref mut it) => it.next(),
+            StorageIter::Dictionary(ref mut it) => it.next(),
+            StorageIter::Interned(ref mut it) => it.next().map(|s| &s[..]),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            StorageIter::Inline(ref it) => it.size_hint(),
+            StorageIter::Arena(ref it) => it.size_hint(),
+            StorageIter::RunLength(ref it) => it.size_hint(),
+            StorageIter::Dictionary(ref it) => it.size_hint(),
+            StorageIter::Interned(ref it) => it.size_hint(),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! The toml module.
+//!
+//! Provides loading of TOML arrays of tables, such as `[[experiments]]`
+//! blocks, into DataTables.
+//!
+//! Each table of the array is a row and each key a column. Keys of sub
+//! tables are joined to their table's name with a `.`, so `[experiments.model]`
+//! gives columns such as `model.layers`.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use datatable::DataTable;
+use error::DataError;
+use interop::FieldRecords;
+
+impl DataTable {
+    /// Loads the array of tables named `table` from TOML text.
+    ///
+    /// Strings are unescaped and underscores are removed from numbers.
+    /// Arrays and inline tables are written on one line with their values
+    /// as written, such as `[1, "a"]`. Keys which a table does not have
+    /// are missing. Everything outside the array is ignored.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The text is not valid TOML, or has no array
+    ///   of tables named `table`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let text = r#"
+    /// title = "Sweep"
+    ///
+    /// [[experiments]]
+    /// name = "baseline"
+    /// rate = 1_000
+    ///
+    /// [experiments.model]
+    /// layers = [64, 64]
+    ///
+    /// [[experiments]]
+    /// name = "wide"   # twice the width
+    /// model.layers = [128, 128]
+    /// "#;
+    ///
+    /// let table = DataTable::from_toml_str(text, "experiments").unwrap();
+    ///
+    /// assert_eq!(table.headers(), vec!["name", "rate", "model.layers"]);
+    /// assert_eq!(table[0].data(), vec!["baseline", "wide"]);
+    /// assert_eq!(table[1][0], *"1000");
+    /// assert!(table[1].is_missing(1));
+    /// assert_eq!(table[2][1], *"[128, 128]");
+    ///
+    /// // The array must be in the text.
+    /// assert!(DataTable::from_toml_str(text, "runs").is_err());
+    /// ```
+    pub fn from_toml_str(text: &str, table: &str) -> Result<DataTable, DataError> {
+        let mut parser = Parser { text, pos: 0 };
+        let mut records = FieldRecords::default();
+        let mut record: Option<Vec<(String, String)>> = None;
+        // The prefix of keys in the current section, or None outside the array.
+        let mut prefix: Option<String> = None;
+        let mut found = false;
+
+        while parser.skip_blank() {
+            if parser.eat("[[") {
+                let name = parser.header("]]")?;
+                if name == table || !name.starts_with(&format!("{}.", table)) {
+                    if let Some(fields) = record.take() {
+                        records.push(fields);
+                    }
+                }
+                // Arrays within the array's tables are skipped.
+                if name == table {
+                    found = true;
+                    record = Some(Vec::new());
+                    prefix = Some(String::new());
+                } else {
+                    prefix = None;
+                }
+            } else if parser.eat("[") {
+                let name = parser.header("]")?;
+                let sub = name.strip_prefix(table).and_then(|n| n.strip_prefix('.'));
+                prefix = match (&record, sub) {
+                    (&Some(_), Some(sub)) => Some(format!("{}.", sub)),
+                    _ => None,
+                };
+            } else {
+                let key = parser.key()?;
+                parser.skip_spaces();
+                if !parser.eat("=") {
+                    return Err(DataError::InvalidStateError);
+                }
+                parser.skip_spaces();
+                let value = parser.value()?;
+                parser.end_line()?;
+
+                if let (Some(ref prefix), Some(ref mut fields)) = (&prefix, &mut record) {
+                    fields.push((format!("{}{}", prefix, key), value));
+                }
+            }
+        }
+
+        if let Some(fields) = record {
+            records.push(fields);
+        }
+        if !found {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(records.finish())
+    }
+
+    /// Loads the array of tables named `table` from a TOML file, see `from_toml_str`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : The file is not valid TOML, or has no array
+    ///   of tables named `table`.
+    pub fn load_toml<P: AsRef<Path>>(path: P, table: &str) -> Result<DataTable, DataError> {
+        let mut text = String::new();
+        BufReader::new(File::open(path)?).read_to_string(&mut text)?;
+        DataTable::from_toml_str(&text, table)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        let found = self.rest().starts_with(prefix);
+        if found {
+            self.pos += prefix.len();
+        }
+        found
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    fn skip_comment(&mut self) {
+        if self.rest().starts_with('#') {
+            self.pos += self.rest().find('\n').unwrap_or(self.rest().len());
+        }
+    }
+
+    /// Skips blank lines and comments, returning false at the end of the text.
+    fn skip_blank(&mut self) -> bool {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if !self.eat("\n") && !self.eat("\r\n") {
+                return !self.rest().is_empty();
+            }
+        }
+    }
+
+    /// Checks that nothing but a comment follows on the line.
+    fn end_line(&mut self) -> Result<(), DataError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.rest().is_empty() || self.eat("\n") || self.eat("\r\n") {
+            Ok(())
+        } else {
+            Err(DataError::InvalidStateError)
+        }
+    }
+
+    /// Reads the name of a table header up to its closing brackets.
+    fn header(&mut self, close: &str) -> Result<String, DataError> {
+        self.skip_spaces();
+        let name = self.key()?;
+        self.skip_spaces();
+        if !self.eat(close) {
+            return Err(DataError::InvalidStateError);
+        }
+        self.end_line()?;
+        Ok(name)
+    }
+
+    /// Reads a key, joining the parts of a dotted key with `.`.
+    fn key(&mut self) -> Result<String, DataError> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            let rest = self.rest();
+            let part = if rest.starts_with('"') || rest.starts_with('\'') {
+                self.string()?
+            } else {
+                let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(DataError::InvalidStateError);
+                }
+                self.pos += len;
+                rest[..len].to_string()
+            };
+            parts.push(part);
+
+            self.skip_spaces();
+            if !self.eat(".") {
+                return Ok(parts.join("."));
+            }
+        }
+    }
+
+    /// Reads a value as the text stored in the table.
+    fn value(&mut self) -> Result<String, DataError> {
+        let rest = self.rest();
+        if rest.starts_with('"') || rest.starts_with('\'') {
+            return self.string();
+        }
+        if rest.starts_with('[') || rest.starts_with('{') {
+            return self.nested();
+        }
+
+        let mut len = rest.find([' ', '\t', '\r', '\n', ',', ']', '}', '#']).unwrap_or(rest.len());
+        // A date and time may be separated by a space.
+        let date = rest.as_bytes().get(4) == Some(&b'-') && len == 10;
+        if date && rest[len..].starts_with(' ') &&
+           rest[len + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+            len += 1 + rest[len + 1..].find([' ', '\t', '\r', '\n', ',', ']', '}', '#'])
+                .unwrap_or(rest.len() - len - 1);
+        }
+        if len == 0 {
+            return Err(DataError::InvalidStateError);
+        }
+        self.pos += len;
+
+        let value = &rest[..len];
+        if value.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-') {
+            Ok(value.replace('_', ""))
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    /// Reads an array or inline table, which may span lines, on one line
+    /// without comments.
+    fn nested(&mut self) -> Result<String, DataError> {
+        let (open, close) = match self.rest().chars().next() {
+            Some('[') => ('[', ']'),
+            Some('{') => ('{', '}'),
+            _ => return Err(DataError::InvalidStateError),
+        };
+        self.pos += 1;
+        let mut items = Vec::new();
+        let mut item = String::new();
+
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            let rest = self.rest();
+            match rest.chars().next() {
+                None => return Err(DataError::InvalidStateError),
+                Some(c) if c == close || c == ',' => {
+                    self.pos += 1;
+                    if !item.is_empty() {
+                        items.push(item.trim_end().to_string());
+                        item.clear();
+                    }
+                    if c == close {
+                        return Ok(format!("{}{}{}", open, items.join(", "), close));
+                    }
+                }
+                Some('\n') | Some('\r') => self.pos += 1,
+                Some('=') => {
+                    self.pos += 1;
+                    item.push_str(" = ");
+                }
+                Some('[') | Some('{') => item.push_str(&self.nested()?),
+                Some('"') | Some('\'') => {
+                    let start = self.pos;
+                    self.string()?;
+                    item.push_str(&self.text[start..self.pos]);
+                }
+                Some(_) => {
+                    let len = rest.find([' ', '\t', '\r', '\n', ',', ']', '}', '#', '='])
+                        .unwrap_or(rest.len());
+                    self.pos += len.max(1);
+                    item.push_str(&rest[..len.max(1)]);
+                }
+            }
+        }
+    }
+
+    /// Reads a basic, literal or multi-line string.
+    fn string(&mut self) -> Result<String, DataError> {
+        let quote = if self.rest().starts_with('"') { "\"" } else { "'" };
+        let triple = quote.repeat(3);
+
+        let (delimiter, multiline) = if self.eat(&triple) {
+            // A line break straight after the opening quotes is not part of the string.
+            if !self.eat("\n") {
+                self.eat("\r\n");
+            }
+            (&triple[..], true)
+        } else {
+            self.pos += 1;
+            (quote, false)
+        };
+
+        let rest = self.rest();
+        let mut end = None;
+        let mut escaped = false;
+        for (i, c) in rest.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && quote == "\"" {
+                escaped = true;
+            } else if c == '\n' && !multiline {
+                break;
+            } else if rest[i..].starts_with(delimiter) {
+                end = Some(i);
+                break;
+            }
+        }
+
+        let end = end.ok_or(DataError::InvalidStateError)?;
+        self.pos += end + delimiter.len();
+
+        if quote == "\"" {
+            unescape(&rest[..end])
+        } else {
+            Ok(rest[..end].to_string())
+        }
+    }
+}
+
+/// Replaces the escapes in the contents of a double quoted string.
+///
+/// A backslash at the end of a line removes the line break and the
+/// whitespace after it.
+pub(crate) fn unescape(text: &str) -> Result<String, DataError> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('0') => out.push('\0'),
+            Some('"') => out.push('"'),
+            Some('\'') => out.push('\''),
+            Some('/') => out.push('/'),
+            Some('\\') => out.push('\\'),
+            Some(u) if u == 'u' || u == 'U' => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                let c = u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32);
+                out.push(c.ok_or(DataError::InvalidStateError)?);
+            }
+            Some(c) if c.is_whitespace() => {
+                let rest = chars.as_str().trim_start();
+                chars = rest.chars();
+            }
+            _ => return Err(DataError::InvalidStateError),
+        }
+    }
+
+    Ok(out)
+}
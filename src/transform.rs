@@ -0,0 +1,651 @@
+//! The transform module.
+//!
+//! Provides transformations over the values of DataColumns.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use datatable::{DataColumn, DataTable};
+use error::DataError;
+use storage::SmallString;
+
+/// How `DataColumn::rank` ranks equal values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RankMethod {
+    /// Equal values get the mean of the ranks they span.
+    #[default]
+    Average,
+    /// Equal values get the lowest of the ranks they span.
+    Min,
+    /// Equal values share a rank and the next value gets the following rank.
+    Dense,
+}
+
+impl DataColumn {
+    /// Parses the values and returns a column of their running total.
+    ///
+    /// Missing values are skipped by the total and stay missing in the
+    /// new column. The new column keeps the name of this one.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec![1, 2]);
+    /// dc.push_missing("");
+    /// dc.push("4");
+    ///
+    /// let total = dc.cumsum::<i32>().unwrap();
+    ///
+    /// assert_eq!(total.data(), vec!["1", "3", "", "7"]);
+    /// assert!(total.is_missing(2));
+    /// assert_eq!(dc.cummax::<i32>().unwrap().data(), vec!["1", "2", "", "4"]);
+    /// ```
+    pub fn cumsum<T>(&self) -> Result<DataColumn, DataError>
+        where T: FromStr + Display + Add<Output = T> + Copy,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.accumulate(|acc: T, val| acc + val)
+    }
+
+    /// Parses the values and returns a column of their running product.
+    ///
+    /// Missing values are skipped as in `cumsum`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn cumprod<T>(&self) -> Result<DataColumn, DataError>
+        where T: FromStr + Display + Mul<Output = T> + Copy,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.accumulate(|acc: T, val| acc * val)
+    }
+
+    /// Parses the values and returns a column of the largest value so far.
+    ///
+    /// Missing values are skipped as in `cumsum`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn cummax<T>(&self) -> Result<DataColumn, DataError>
+        where T: FromStr + Display + PartialOrd + Copy,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.accumulate(|acc: T, val| if val > acc { val } else { acc })
+    }
+
+    /// Parses the values and returns a column of the smallest value so far.
+    ///
+    /// Missing values are skipped as in `cumsum`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn cummin<T>(&self) -> Result<DataColumn, DataError>
+        where T: FromStr + Display + PartialOrd + Copy,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.accumulate(|acc: T, val| if val < acc { val } else { acc })
+    }
+
+    /// Parses the values and ranks them from smallest to largest.
+    ///
+    /// Ranks start at 1. Missing values, and values which cannot be
+    /// compared such as `NaN`, are not ranked and give `None`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    /// use rusty_data::transform::RankMethod;
+    ///
+    /// let dc = DataColumn::from_vec(vec![10, 30, 20, 30, 40]);
+    ///
+    /// let average = dc.rank::<u32>(RankMethod::Average).unwrap();
+    /// assert_eq!(average, vec![Some(1.0), Some(3.5), Some(2.0), Some(3.5), Some(5.0)]);
+    ///
+    /// let min = dc.rank::<u32>(RankMethod::Min).unwrap();
+    /// assert_eq!(min, vec![Some(1.0), Some(3.0), Some(2.0), Some(3.0), Some(5.0)]);
+    ///
+    /// let dense = dc.rank::<u32>(RankMethod::Dense).unwrap();
+    /// assert_eq!(dense, vec![Some(1.0), Some(3.0), Some(2.0), Some(3.0), Some(4.0)]);
+    /// ```
+    pub fn rank<T>(&self, method: RankMethod) -> Result<Vec<Option<f64>>, DataError>
+        where T: FromStr + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let mut values = Vec::with_capacity(self.len());
+
+        for idx in 0..self.len() {
+            if self.is_missing(idx) {
+                continue;
+            }
+
+            let val: T = self.get_as(idx)?;
+            if val.partial_cmp(&val).is_some() {
+                values.push((idx, val));
+            }
+        }
+
+        // Every value left is comparable, so the ordering is total.
+        values.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        let mut ranks = vec![None; self.len()];
+        let mut start = 0;
+        let mut dense = 0;
+
+        while start < values.len() {
+            let mut end = start + 1;
+            while end < values.len() && values[end].1 == values[start].1 {
+                end += 1;
+            }
+            dense += 1;
+
+            let rank = match method {
+                RankMethod::Average => (start + 1 + end) as f64 / 2.0,
+                RankMethod::Min => (start + 1) as f64,
+                RankMethod::Dense => dense as f64,
+            };
+
+            for &(idx, _) in &values[start..end] {
+                ranks[idx] = Some(rank);
+            }
+            start = end;
+        }
+
+        Ok(ranks)
+    }
+
+    /// Parses the values and returns a column of the difference between
+    /// each value and the value `n` rows before it.
+    ///
+    /// The first `n` values, and any value where either side is missing,
+    /// are missing in the new column.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let dc = DataColumn::from_vec(vec![1, 4, 9, 16]);
+    ///
+    /// let diff = dc.diff::<i32>(1).unwrap();
+    /// assert_eq!(diff.data(), vec!["", "3", "5", "7"]);
+    /// assert!(diff.is_missing(0));
+    ///
+    /// let change = dc.pct_change(2).unwrap();
+    /// assert_eq!(change.data(), vec!["", "", "8", "3"]);
+    /// ```
+    pub fn diff<T>(&self, n: usize) -> Result<DataColumn, DataError>
+        where T: FromStr + Display + Sub<Output = T> + Copy,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.lagged(n, |prev: T, val| val - prev)
+    }
+
+    /// Parses the values and returns a column of the relative change from
+    /// the value `n` rows before each value.
+    ///
+    /// A change from 10 to 15 is written as `0.5`. Values are padded with
+    /// missing values as in `diff`.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed as a float.
+    pub fn pct_change(&self, n: usize) -> Result<DataColumn, DataError> {
+        self.lagged(n, |prev: f64, val| (val - prev) / prev)
+    }
+
+    /// Pushes `f` of each value and the value `n` rows before it.
+    fn lagged<T, U, F>(&self, n: usize, mut f: F) -> Result<DataColumn, DataError>
+        where T: FromStr + Copy,
+              T::Err: Error + Send + Sync + 'static,
+              U: Display,
+              F: FnMut(T, T) -> U
+    {
+        let mut values = Vec::with_capacity(self.len());
+        for idx in 0..self.len() {
+            values.push(if self.is_missing(idx) { None } else { Some(self.get_as::<T>(idx)?) });
+        }
+
+        let mut column = DataColumn::empty();
+        column.name = self.name.clone();
+
+        for idx in 0..values.len() {
+            let prev = if idx >= n { values[idx - n] } else { None };
+
+            match (prev, values[idx]) {
+                (Some(prev), Some(val)) => column.push_value(f(prev, val)),
+                _ => column.push_missing(""),
+            }
+        }
+
+        Ok(column)
+    }
+
+    /// Parses the values and replaces any below `lower` with `lower`.
+    ///
+    /// Missing values are left as they are. The column is only changed
+    /// once every value has parsed.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec!["-3.5", "12", "101"]);
+    ///
+    /// dc.clip_lower(0.0).unwrap();
+    /// dc.clip_upper(100.0).unwrap();
+    ///
+    /// assert_eq!(dc.data(), vec!["0", "12", "100"]);
+    /// ```
+    pub fn clip_lower<T>(&mut self, lower: T) -> Result<(), DataError>
+        where T: FromStr + Display + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.clip(lower, Ordering::Less)
+    }
+
+    /// Parses the values and replaces any above `upper` with `upper`.
+    ///
+    /// Missing values are left as they are. The column is only changed
+    /// once every value has parsed.
+    ///
+    /// # Failures
+    ///
+    /// - DataCastError : A value could not be parsed to the requested type.
+    pub fn clip_upper<T>(&mut self, upper: T) -> Result<(), DataError>
+        where T: FromStr + Display + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        self.clip(upper, Ordering::Greater)
+    }
+
+    /// Replaces the values which compare as `beyond` to the bound.
+    fn clip<T>(&mut self, bound: T, beyond: Ordering) -> Result<(), DataError>
+        where T: FromStr + Display + PartialOrd,
+              T::Err: Error + Send + Sync + 'static
+    {
+        let mut clipped = Vec::new();
+
+        for idx in 0..self.len() {
+            if self.is_missing(idx) {
+                continue;
+            }
+
+            let val: T = self.get_as(idx)?;
+            if val.partial_cmp(&bound) == Some(beyond) {
+                clipped.push(idx);
+            }
+        }
+
+        if !clipped.is_empty() {
+            let bound = SmallString::from(bound.to_string());
+            self.edit_values(|values| {
+                for idx in clipped {
+                    values[idx] = bound.clone();
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Replaces each value which is a key of `mapping` with its mapped value.
+    ///
+    /// Missing values are left as they are. Returns the number of values
+    /// which were replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use rusty_data::datatable::DataColumn;
+    ///
+    /// let mut dc = DataColumn::from_vec(vec!["M", "F", "X", "M"]);
+    ///
+    /// let mut mapping = HashMap::new();
+    /// mapping.insert("M".to_string(), "male".to_string());
+    /// mapping.insert("F".to_string(), "female".to_string());
+    ///
+    /// assert_eq!(dc.replace(&mapping), 3);
+    /// assert_eq!(dc.data(), vec!["male", "female", "X", "male"]);
+    /// ```
+    pub fn replace(&mut self, mapping: &HashMap<String, String>) -> usize {
+        let replaced: Vec<(usize, &String)> = self.iter()
+            .enumerate()
+            .filter(|&(idx, _)| !self.is_missing(idx))
+            .filter_map(|(idx, val)| mapping.get(val).map(|new| (idx, new)))
+            .collect();
+        let count = replaced.len();
+
+        if count > 0 {
+            self.edit_values(|values| {
+                for (idx, new) in replaced {
+                    values[idx] = SmallString::from(&new[..]);
+                }
+            });
+        }
+
+        count
+    }
+
+    /// Folds the present values with `f`, pushing each intermediate result.
+    fn accumulate<T, F>(&self, mut f: F) -> Result<DataColumn, DataError>
+        where T: FromStr + Display + Copy,
+              T::Err: Error + Send + Sync + 'static,
+              F: FnMut(T, T) -> T
+    {
+        let mut column = DataColumn::empty();
+        column.name = self.name.clone();
+        let mut acc = None;
+
+        for idx in 0..self.len() {
+            if self.is_missing(idx) {
+                column.push_missing("");
+                continue;
+            }
+
+            let val = self.get_as(idx)?;
+            let next = match acc {
+                Some(acc) => f(acc, val),
+                None => val,
+            };
+
+            column.push_value(next);
+            acc = Some(next);
+        }
+
+        Ok(column)
+    }
+}
+
+impl DataTable {
+    /// Replaces values in every column using `mapping`, see `DataColumn::replace`.
+    ///
+    /// Returns the total number of values which were replaced.
+    pub fn replace(&mut self, mapping: &HashMap<String, String>) -> usize {
+        self.data_cols.iter_mut().map(|c| c.replace(mapping)).sum()
+    }
+
+    /// Starts building a column which takes one value where `mask` is true
+    /// and another where it is false, like SQL's `CASE` or `numpy.where`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::transform::Choice;
+    ///
+    /// let records = vec![vec!["12", "ok"], vec!["-3", "ok"], vec!["7", "ok"]];
+    /// let table = DataTable::from_records(Some(vec!["temp", "status"]), records).unwrap();
+    ///
+    /// let mask: Vec<bool> = table.iter_rows().map(|r| r.get_as::<i32>(0).unwrap() < 0).collect();
+    /// let status = table.when(&mask)
+    ///     .then(Choice::Value("faulty"))
+    ///     .otherwise(Choice::Column("status"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(status.data(), vec!["ok", "faulty", "ok"]);
+    /// ```
+    pub fn when<'a>(&'a self, mask: &'a [bool]) -> When<'a> {
+        When { table: self, mask }
+    }
+
+    /// Splits the values of the named column on `delimiter`, giving each
+    /// part its own row.
+    ///
+    /// The values of the other columns are repeated for every part. Missing
+    /// values are not split and keep a single row.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1", "a;b;c"], vec!["2", "d"]];
+    /// let table = DataTable::from_records(Some(vec!["id", "tags"]), records).unwrap();
+    ///
+    /// let exploded = table.explode("tags", ';').unwrap();
+    ///
+    /// assert_eq!(exploded[0].data(), vec!["1", "1", "1", "2"]);
+    /// assert_eq!(exploded[1].data(), vec!["a", "b", "c", "d"]);
+    /// ```
+    pub fn explode(&self, column: &str, delimiter: char) -> Result<DataTable, DataError> {
+        let target = self.column_index(column).ok_or(DataError::InvalidStateError)?;
+        let mut table = DataTable {
+            data_cols: self.data_cols.iter().map(|c| c.empty_like()).collect(),
+        };
+
+        for row in 0..self.rows() {
+            let source = &self.data_cols[target];
+
+            if source.is_missing(row) {
+                for (from, to) in self.data_cols.iter().zip(table.data_cols.iter_mut()) {
+                    to.push_from(from, row);
+                }
+                continue;
+            }
+
+            for part in source[row].split(delimiter) {
+                let columns = self.data_cols.iter().zip(table.data_cols.iter_mut());
+
+                for (col, (from, to)) in columns.enumerate() {
+                    if col == target {
+                        to.push(part);
+                    } else {
+                        to.push_from(from, row);
+                    }
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Splits each value of the named column on `delimiter` into new columns.
+    ///
+    /// The column is replaced in place by one column for each of `names`.
+    /// Missing values give a missing value in every new column.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name, or a value does not
+    ///   split into exactly one part for each name. The table is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["a", "51.5;-0.1"], vec!["b", "48.9;2.4"]];
+    /// let mut table = DataTable::from_records(Some(vec!["id", "coord"]), records).unwrap();
+    ///
+    /// table.split_column("coord", ';', &["lat", "lon"]).unwrap();
+    ///
+    /// assert_eq!(table.headers(), vec!["id", "lat", "lon"]);
+    /// assert_eq!(table[2].data(), vec!["-0.1", "2.4"]);
+    /// ```
+    pub fn split_column(&mut self,
+                        column: &str,
+                        delimiter: char,
+                        names: &[&str])
+                        -> Result<(), DataError> {
+        self.split_column_with(column, delimiter, names, false)
+    }
+
+    /// Splits the column as in `split_column`, padding values with too few
+    /// parts with missing values.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : No column has the name, or a value splits into
+    ///   more parts than there are names. The table is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let records = vec![vec!["1;2;3"], vec!["4"]];
+    /// let mut table = DataTable::from_records(Some(vec!["xyz"]), records).unwrap();
+    ///
+    /// table.split_column_padded("xyz", ';', &["x", "y", "z"]).unwrap();
+    ///
+    /// assert_eq!(table[0].data(), vec!["1", "4"]);
+    /// assert!(table[2].is_missing(1));
+    /// ```
+    pub fn split_column_padded(&mut self,
+                               column: &str,
+                               delimiter: char,
+                               names: &[&str])
+                               -> Result<(), DataError> {
+        self.split_column_with(column, delimiter, names, true)
+    }
+
+    fn split_column_with(&mut self,
+                         column: &str,
+                         delimiter: char,
+                         names: &[&str],
+                         pad: bool)
+                         -> Result<(), DataError> {
+        let target = self.column_index(column).ok_or(DataError::InvalidStateError)?;
+        let source = &self.data_cols[target];
+
+        let mut split: Vec<DataColumn> = names.iter()
+            .map(|name| {
+                let mut column = DataColumn::empty();
+                column.name = Some(name.to_string());
+                column
+            })
+            .collect();
+
+        for (row, val) in source.iter().enumerate() {
+            if source.is_missing(row) {
+                for column in split.iter_mut() {
+                    column.push_missing("");
+                }
+                continue;
+            }
+
+            let parts: Vec<&str> = val.split(delimiter).collect();
+            if parts.len() > names.len() || (parts.len() < names.len() && !pad) {
+                return Err(DataError::InvalidStateError);
+            }
+
+            for (i, column) in split.iter_mut().enumerate() {
+                match parts.get(i) {
+                    Some(&part) => column.push(part),
+                    None => column.push_missing(""),
+                }
+            }
+        }
+
+        self.data_cols.splice(target..target + 1, split);
+        Ok(())
+    }
+}
+
+/// A value chosen by `DataTable::when`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Choice<'a> {
+    /// The same value for every row.
+    Value(&'a str),
+    /// The value in the named column of the same row.
+    Column(&'a str),
+}
+
+impl<'a> Choice<'a> {
+    fn resolve(self, table: &'a DataTable) -> Result<Resolved<'a>, DataError> {
+        match self {
+            Choice::Value(val) => Ok(Resolved::Value(val)),
+            Choice::Column(name) => {
+                table.column(name).map(Resolved::Column).ok_or(DataError::InvalidStateError)
+            }
+        }
+    }
+}
+
+/// A Choice with its column looked up.
+#[derive(Clone, Copy)]
+enum Resolved<'a> {
+    Value(&'a str),
+    Column(&'a DataColumn),
+}
+
+/// The condition of a column being built by `DataTable::when`.
+pub struct When<'a> {
+    table: &'a DataTable,
+    mask: &'a [bool],
+}
+
+impl<'a> When<'a> {
+    /// Chooses the value for rows where the mask is true.
+    pub fn then(self, value: Choice<'a>) -> Then<'a> {
+        Then { when: self, value }
+    }
+}
+
+/// A column being built by `DataTable::when` with its value for true rows.
+pub struct Then<'a> {
+    when: When<'a>,
+    value: Choice<'a>,
+}
+
+impl<'a> Then<'a> {
+    /// Chooses the value for rows where the mask is false and builds the column.
+    ///
+    /// Values taken from a column keep their missing mark.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The mask is not the same length as the table,
+    ///   or no column has one of the names.
+    pub fn otherwise(self, other: Choice<'a>) -> Result<DataColumn, DataError> {
+        let table = self.when.table;
+        if self.when.mask.len() != table.rows() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let then = self.value.resolve(table)?;
+        let other = other.resolve(table)?;
+
+        let mut column = DataColumn::empty();
+        for (row, &cond) in self.when.mask.iter().enumerate() {
+            match if cond { then } else { other } {
+                Resolved::Value(val) => column.push(val),
+                Resolved::Column(from) => column.push_from(from, row),
+            }
+        }
+
+        Ok(column)
+    }
+}
@@ -0,0 +1,532 @@
+//! The writer module.
+//!
+//! Provides methods for writing DataTables out as delimited text.
+
+use std::borrow::Cow;
+use std::fs::File;
+#[cfg(feature = "zstd")]
+use std::io;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+
+#[cfg(feature = "gzip")]
+use flate2::Compression;
+#[cfg(feature = "gzip")]
+use flate2::write::GzEncoder;
+
+use category::write_json_string;
+use datatable::DataTable;
+use error::DataError;
+use schema::DType;
+
+/// Options used to fine tune writing a table
+pub struct WriterOptions {
+    /// True if the column names should be written as the first line
+    ///
+    /// Columns without a name are written with an empty header.
+    pub has_header: bool,
+    /// The delimiter character
+    pub delimiter: char,
+    /// The quote character
+    ///
+    /// Values holding the delimiter, the quote or a line break are quoted,
+    /// with any quote inside written twice.
+    pub quote_marker: char,
+    /// The line ending written after each line
+    pub line_ending: String,
+    /// How floating point values are written
+    pub number_format: NumberFormat,
+    /// True if the table should load back unchanged with `LoaderOptions::round_trip`
+    ///
+    /// Missing values and columns without a name are written as empty fields
+    /// without quotes and empty values are quoted, so the two can be told
+    /// apart. The text of missing values is not kept and the `number_format`
+    /// is not applied. A carriage return directly before a line break within
+    /// a value is read back as the line break alone.
+    pub round_trip: bool,
+}
+
+/// How floating point values are written.
+///
+/// A value is formatted if its column is recorded as `DType::F64`, or if
+/// it parses as a float but not as an integer. Missing and non-finite
+/// values are written unchanged. The default writes every value as it is.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::DataTable;
+/// use rusty_data::writer::{NumberFormat, WriterOptions};
+///
+/// let records = vec![vec!["1", "0.126"], vec!["2", "31415.9"], vec!["3", "0.00002"]];
+/// let table = DataTable::from_records(Some(vec!["id", "x"]), records).unwrap();
+///
+/// let options = WriterOptions {
+///     delimiter: ';',
+///     number_format: NumberFormat {
+///         precision: Some(2),
+///         scientific_above: Some(1e4),
+///         scientific_below: Some(1e-3),
+///         decimal_separator: ',',
+///     },
+///     ..WriterOptions::default()
+/// };
+///
+/// assert_eq!(table.to_csv_string(&options), "id;x\n1;0,13\n2;3,14e4\n3;2,00e-5\n");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    /// The number of digits after the decimal point, or `None` to keep them all
+    pub precision: Option<usize>,
+    /// Values of at least this size are written in scientific notation
+    pub scientific_above: Option<f64>,
+    /// Values smaller than this, other than zero, are written in scientific notation
+    pub scientific_below: Option<f64>,
+    /// The character written in place of the decimal point
+    pub decimal_separator: char,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat {
+            precision: None,
+            scientific_above: None,
+            scientific_below: None,
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Formats the value if it is a float, see `NumberFormat`.
+    pub fn format<'a>(&self, value: &'a str, is_float_column: bool) -> Cow<'a, str> {
+        let parsed = match value.trim().parse::<f64>() {
+            Ok(v) if v.is_finite() => v,
+            _ => return Cow::Borrowed(value),
+        };
+        if !is_float_column && value.trim().parse::<i64>().is_ok() {
+            return Cow::Borrowed(value);
+        }
+
+        let size = parsed.abs();
+        let scientific = self.scientific_above.is_some_and(|above| size >= above) ||
+                         self.scientific_below.is_some_and(|below| size != 0.0 && size < below);
+
+        let text = match (scientific, self.precision) {
+            (true, Some(p)) => Cow::Owned(format!("{:.*e}", p, parsed)),
+            (true, None) => Cow::Owned(format!("{:e}", parsed)),
+            (false, Some(p)) => Cow::Owned(format!("{:.*}", p, parsed)),
+            (false, None) => Cow::Borrowed(value),
+        };
+
+        if self.decimal_separator == '.' {
+            text
+        } else {
+            Cow::Owned(text.replace('.', self.decimal_separator.encode_utf8(&mut [0; 4])))
+        }
+    }
+}
+
+impl Default for WriterOptions {
+    fn default() -> WriterOptions {
+        WriterOptions {
+            has_header: true,
+            delimiter: ',',
+            quote_marker: '"',
+            line_ending: "\n".to_string(),
+            number_format: NumberFormat::default(),
+            round_trip: false,
+        }
+    }
+}
+
+impl WriterOptions {
+    /// The options which write a table so that it loads back unchanged.
+    ///
+    /// Loading the output with `LoaderOptions::round_trip` gives a table
+    /// with the same names, values and missing values. Values holding
+    /// delimiters, quotes or line breaks, and empty values, are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::{DataColumn, DataTable};
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    /// use rusty_data::writer::WriterOptions;
+    ///
+    /// let mut text = DataColumn::empty();
+    /// text.name = Some("text, quoted".to_string());
+    /// text.extend(vec!["a,b", "say \"hi\"", ""]);
+    ///
+    /// let mut unnamed = DataColumn::empty();
+    /// unnamed.push("two\nlines");
+    /// unnamed.push_missing("n/a");
+    /// unnamed.push(" padded ");
+    ///
+    /// let table = DataTable { data_cols: vec![text, unnamed] };
+    /// let written = table.to_csv_string(&WriterOptions::round_trip());
+    ///
+    /// let loaded = Loader::from_str(&written, LoaderOptions::round_trip()).load_file().unwrap();
+    ///
+    /// assert!(loaded.compare(&table).is_empty());
+    /// assert_eq!(loaded[0].name, table[0].name);
+    /// assert_eq!(loaded[1].name, None);
+    /// assert!(!loaded[0].is_missing(2) && loaded[1].is_missing(1));
+    /// ```
+    pub fn round_trip() -> WriterOptions {
+        WriterOptions {
+            round_trip: true,
+            ..WriterOptions::default()
+        }
+    }
+}
+
+impl DataTable {
+    /// Writes the table as delimited text.
+    ///
+    /// Missing values are written as their text.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The writer failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::writer::WriterOptions;
+    ///
+    /// let records = vec![vec!["1", "a,b"], vec!["2", "say \"hi\""]];
+    /// let table = DataTable::from_records(Some(vec!["id", "text"]), records).unwrap();
+    ///
+    /// let mut out = Vec::new();
+    /// table.write_csv(&mut out, &WriterOptions::default()).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(out).unwrap(),
+    ///            "id,text\n1,\"a,b\"\n2,\"say \"\"hi\"\"\"\n");
+    /// ```
+    pub fn write_csv<W: Write>(&self,
+                               writer: &mut W,
+                               options: &WriterOptions)
+                               -> Result<(), DataError> {
+        let mut line = String::new();
+
+        if options.has_header {
+            for (col, column) in self.data_cols.iter().enumerate() {
+                push_field(&mut line, col, column.name.as_deref(), options);
+            }
+            line.push_str(&options.line_ending);
+            writer.write_all(line.as_bytes())?;
+        }
+
+        let reformat = !options.round_trip && options.number_format != NumberFormat::default();
+        let float_columns: Vec<bool> =
+            self.data_cols.iter().map(|c| c.dtype() == Some(DType::F64)).collect();
+
+        for row in 0..self.rows() {
+            line.clear();
+            for (col, column) in self.data_cols.iter().enumerate() {
+                if options.round_trip && column.is_missing(row) {
+                    push_field(&mut line, col, None, options);
+                } else if reformat && !column.is_missing(row) {
+                    let value = options.number_format.format(&column[row], float_columns[col]);
+                    push_field(&mut line, col, Some(&value), options);
+                } else {
+                    push_field(&mut line, col, Some(&column[row]), options);
+                }
+            }
+            line.push_str(&options.line_ending);
+            writer.write_all(line.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the table as delimited text to a String.
+    pub fn to_csv_string(&self, options: &WriterOptions) -> String {
+        let mut out = Vec::new();
+        self.write_csv(&mut out, options).expect("write to a Vec failed");
+        String::from_utf8(out).expect("written values are valid UTF-8")
+    }
+
+    /// Writes the table as delimited text to a file.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save_csv<P: AsRef<Path>>(&self,
+                                    path: P,
+                                    options: &WriterOptions)
+                                    -> Result<(), DataError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_csv(&mut writer, options)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the table as JSON Lines, one object per row.
+    ///
+    /// Each object maps the column names to the values of the row, keying
+    /// unnamed columns by their index as in `to_row_maps`. Values are
+    /// written as strings and missing values as `null`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The writer failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::loader::{Loader, LoaderOptions};
+    ///
+    /// let options = LoaderOptions {
+    ///     has_header: true,
+    ///     na_tokens: vec!["NA".to_string()],
+    ///     ..LoaderOptions::default()
+    /// };
+    /// let table = Loader::from_str("id,text\n1,say \"hi\"\n2,NA\n", options).load_file().unwrap();
+    ///
+    /// assert_eq!(table.to_json_string(),
+    ///            "{\"id\":\"1\",\"text\":\"say \\\"hi\\\"\"}\n{\"id\":\"2\",\"text\":null}\n");
+    /// ```
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> Result<(), DataError> {
+        let keys: Vec<String> = self.data_cols
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let mut key = String::new();
+                write_json_string(&mut key, &c.name.clone().unwrap_or_else(|| i.to_string()));
+                key
+            })
+            .collect();
+
+        let mut line = String::new();
+
+        for row in 0..self.rows() {
+            line.clear();
+            line.push('{');
+            for (col, column) in self.data_cols.iter().enumerate() {
+                if col > 0 {
+                    line.push(',');
+                }
+                line.push_str(&keys[col]);
+                line.push(':');
+
+                if column.is_missing(row) {
+                    line.push_str("null");
+                } else {
+                    write_json_string(&mut line, &column[row]);
+                }
+            }
+            line.push_str("}\n");
+            writer.write_all(line.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the table as JSON Lines to a String.
+    pub fn to_json_string(&self) -> String {
+        let mut out = Vec::new();
+        self.write_json(&mut out).expect("write to a Vec failed");
+        String::from_utf8(out).expect("written values are valid UTF-8")
+    }
+
+    /// Writes the table as JSON Lines to a file.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_json(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl DataTable {
+    /// Writes the table as gzip compressed delimited text.
+    ///
+    /// The output is compressed at flate2's default level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate flate2;
+    /// extern crate rusty_data;
+    ///
+    /// use std::io::Read;
+    ///
+    /// use flate2::read::GzDecoder;
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::writer::WriterOptions;
+    ///
+    /// # fn main() {
+    /// let records = (0..100).map(|i| vec![i.to_string(), "repeated text".to_string()]);
+    /// let table = DataTable::from_records(Some(vec!["id", "text"]), records).unwrap();
+    ///
+    /// let options = WriterOptions::default();
+    /// let compressed = table.to_csv_gz(&options);
+    ///
+    /// let mut text = String::new();
+    /// GzDecoder::new(&compressed[..]).read_to_string(&mut text).unwrap();
+    ///
+    /// assert!(compressed.len() < text.len() / 2);
+    /// assert_eq!(text, table.to_csv_string(&options));
+    /// # }
+    /// ```
+    pub fn to_csv_gz(&self, options: &WriterOptions) -> Vec<u8> {
+        let mut writer = gzip_writer(Vec::new());
+        self.write_csv(&mut writer, options).expect("write to a Vec failed");
+        writer.finish().expect("write to a Vec failed")
+    }
+
+    /// Writes the table as gzip compressed delimited text to a file.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save_csv_gz<P: AsRef<Path>>(&self,
+                                       path: P,
+                                       options: &WriterOptions)
+                                       -> Result<(), DataError> {
+        let mut writer = gzip_writer(BufWriter::new(File::create(path)?));
+        self.write_csv(&mut writer, options)?;
+        writer.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Writes the table as gzip compressed JSON Lines.
+    pub fn to_json_gz(&self) -> Vec<u8> {
+        let mut writer = gzip_writer(Vec::new());
+        self.write_json(&mut writer).expect("write to a Vec failed");
+        writer.finish().expect("write to a Vec failed")
+    }
+
+    /// Writes the table as gzip compressed JSON Lines to a file.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save_json_gz<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        let mut writer = gzip_writer(BufWriter::new(File::create(path)?));
+        self.write_json(&mut writer)?;
+        writer.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl DataTable {
+    /// Writes the table as zstd compressed delimited text.
+    ///
+    /// The output is compressed at zstd's default level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate rusty_data;
+    /// extern crate zstd;
+    ///
+    /// use rusty_data::datatable::DataTable;
+    /// use rusty_data::writer::WriterOptions;
+    ///
+    /// # fn main() {
+    /// let records = (0..100).map(|i| vec![i.to_string(), "repeated text".to_string()]);
+    /// let table = DataTable::from_records(Some(vec!["id", "text"]), records).unwrap();
+    ///
+    /// let options = WriterOptions::default();
+    /// let compressed = table.to_csv_zst(&options);
+    ///
+    /// assert!(compressed.len() < table.to_csv_string(&options).len() / 2);
+    /// assert_eq!(zstd::decode_all(&compressed[..]).unwrap(),
+    ///            table.to_csv_string(&options).into_bytes());
+    /// # }
+    /// ```
+    pub fn to_csv_zst(&self, options: &WriterOptions) -> Vec<u8> {
+        let mut writer = zstd_writer(Vec::new()).expect("write to a Vec failed");
+        self.write_csv(&mut writer, options).expect("write to a Vec failed");
+        writer.finish().expect("write to a Vec failed")
+    }
+
+    /// Writes the table as zstd compressed delimited text to a file.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save_csv_zst<P: AsRef<Path>>(&self,
+                                        path: P,
+                                        options: &WriterOptions)
+                                        -> Result<(), DataError> {
+        let mut writer = zstd_writer(BufWriter::new(File::create(path)?))?;
+        self.write_csv(&mut writer, options)?;
+        writer.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Writes the table as zstd compressed JSON Lines.
+    pub fn to_json_zst(&self) -> Vec<u8> {
+        let mut writer = zstd_writer(Vec::new()).expect("write to a Vec failed");
+        self.write_json(&mut writer).expect("write to a Vec failed");
+        writer.finish().expect("write to a Vec failed")
+    }
+
+    /// Writes the table as zstd compressed JSON Lines to a file.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be written.
+    pub fn save_json_zst<P: AsRef<Path>>(&self, path: P) -> Result<(), DataError> {
+        let mut writer = zstd_writer(BufWriter::new(File::create(path)?))?;
+        self.write_json(&mut writer)?;
+        writer.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// Constructs a gzip encoder at the default level writing to `inner`.
+#[cfg(feature = "gzip")]
+fn gzip_writer<W: Write>(inner: W) -> GzEncoder<W> {
+    GzEncoder::new(inner, Compression::default())
+}
+
+/// Constructs a zstd encoder at the default level writing to `inner`.
+#[cfg(feature = "zstd")]
+fn zstd_writer<W: Write>(inner: W) -> io::Result<zstd::Encoder<'static, W>> {
+    zstd::Encoder::new(inner, 0)
+}
+
+/// Appends the value to the line, after a delimiter unless it is the first field.
+///
+/// A value of `None` is written as an empty field without quotes.
+fn push_field(line: &mut String, col: usize, value: Option<&str>, options: &WriterOptions) {
+    if col > 0 {
+        line.push(options.delimiter);
+    }
+
+    let value = match value {
+        Some(value) => value,
+        None => return,
+    };
+
+    let quote = options.quote_marker;
+    let special = |c| c == options.delimiter || c == quote || c == '\n' || c == '\r';
+    let needs_quotes = (options.round_trip && value.is_empty()) || value.chars().any(special);
+
+    if !needs_quotes {
+        line.push_str(value);
+        return;
+    }
+
+    line.push(quote);
+    for c in value.chars() {
+        if c == quote {
+            line.push(quote);
+        }
+        line.push(c);
+    }
+    line.push(quote);
+}
@@ -0,0 +1,370 @@
+//! The xml module.
+//!
+//! Provides loading of record oriented XML into DataTables, as published
+//! by many open data portals.
+//!
+//! Only the parts of XML found in such files are read. Document type
+//! declarations are skipped and entities other than the predefined and
+//! numeric ones are not supported.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use datatable::DataTable;
+use error::DataError;
+use interop::FieldRecords;
+
+/// Options used to fine tune loading XML
+///
+/// Each element matching `record_path` is a row. The fields of a row are
+/// the attributes of its element and each of its child elements, named by
+/// their `name_attribute` or else by their tag. The value of a child is
+/// all of the text within it. Fields which a row does not have are missing.
+///
+/// # Examples
+///
+/// ```
+/// use rusty_data::datatable::DataTable;
+/// use rusty_data::xml::XmlOptions;
+///
+/// let text = r#"<?xml version="1.0"?>
+/// <response>
+///   <meta><title>Stations</title></meta>
+///   <data>
+///     <row id="1"><field name="town">Bath</field><field name="rain">7.4</field></row>
+///     <row id="2"><town>Leeds &amp; Bradford</town></row>
+///   </data>
+/// </response>"#;
+///
+/// let options = XmlOptions { record_path: "//data/row".to_string(), ..XmlOptions::default() };
+/// let table = DataTable::from_xml_str(text, &options).unwrap();
+///
+/// assert_eq!(table.headers(), vec!["id", "town", "rain"]);
+/// assert_eq!(table[1].data(), vec!["Bath", "Leeds & Bradford"]);
+/// assert!(table[2].is_missing(1));
+/// ```
+pub struct XmlOptions {
+    /// The path of the record elements, as tag names separated by `/`
+    ///
+    /// A `*` matches any tag. The path starts at the root element, or
+    /// matches at any depth if it starts with `//`.
+    pub record_path: String,
+    /// The attribute of a child element holding the name of its field
+    pub name_attribute: String,
+    /// True if the attributes of the record elements are read as fields
+    pub attributes: bool,
+}
+
+impl Default for XmlOptions {
+    fn default() -> XmlOptions {
+        XmlOptions {
+            record_path: "*/*".to_string(),
+            name_attribute: "name".to_string(),
+            attributes: true,
+        }
+    }
+}
+
+impl DataTable {
+    /// Loads a table from record oriented XML, see `XmlOptions`.
+    ///
+    /// Columns are in the order their fields are first seen. If a row has
+    /// a field more than once the last value is kept.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The text is not well formed XML, or the
+    ///   record path is empty.
+    pub fn from_xml_str(text: &str, options: &XmlOptions) -> Result<DataTable, DataError> {
+        let path = RecordPath::new(&options.record_path)?;
+        let mut parser = Parser { text, pos: 0 };
+        let mut stack: Vec<&str> = Vec::new();
+        let mut record: Option<Record> = None;
+        let mut builder = FieldRecords::default();
+
+        while let Some(event) = parser.next_event()? {
+            match event {
+                Event::Start { name, attributes, empty } => {
+                    stack.push(name);
+
+                    match record {
+                        None if path.matches(&stack) => {
+                            let mut fields = Vec::new();
+                            if options.attributes {
+                                fields.extend(attributes.into_iter().map(|(k, v)| (k.into(), v)));
+                            }
+                            record = Some(Record { depth: stack.len(), fields, field: None });
+                        }
+                        Some(ref mut r) if stack.len() == r.depth + 1 => {
+                            let field = attributes.into_iter()
+                                .find(|&(key, _)| key == options.name_attribute)
+                                .map_or_else(|| name.to_string(), |(_, value)| value);
+                            r.field = Some((field, String::new()));
+                        }
+                        _ => {}
+                    }
+
+                    if empty {
+                        end_element(&mut stack, &mut record, &mut builder, name)?;
+                    }
+                }
+                Event::End(name) => end_element(&mut stack, &mut record, &mut builder, name)?,
+                Event::Text(text) => {
+                    let field = record.as_mut().and_then(|r| r.field.as_mut());
+                    if let Some((_, ref mut value)) = field {
+                        value.push_str(&text);
+                    }
+                }
+            }
+        }
+
+        if !stack.is_empty() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(builder.finish())
+    }
+
+    /// Loads a table from a record oriented XML file, see `XmlOptions`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : The file is not well formed XML, or the
+    ///   record path is empty.
+    pub fn load_xml<P: AsRef<Path>>(path: P, options: &XmlOptions) -> Result<DataTable, DataError> {
+        let mut text = String::new();
+        BufReader::new(File::open(path)?).read_to_string(&mut text)?;
+        DataTable::from_xml_str(&text, options)
+    }
+}
+
+/// Closes the element, completing the field or record it holds.
+fn end_element<'a>(stack: &mut Vec<&'a str>,
+                   record: &mut Option<Record>,
+                   builder: &mut FieldRecords,
+                   name: &'a str)
+                   -> Result<(), DataError> {
+    if stack.pop() != Some(name) {
+        return Err(DataError::InvalidStateError);
+    }
+
+    let depth = stack.len();
+    let done = match *record {
+        Some(ref mut r) if depth == r.depth => {
+            if let Some(field) = r.field.take() {
+                r.fields.push(field);
+            }
+            false
+        }
+        Some(ref r) => depth < r.depth,
+        None => false,
+    };
+
+    if done {
+        builder.push(record.take().expect("record was checked").fields);
+    }
+
+    Ok(())
+}
+
+/// The record being read.
+struct Record {
+    depth: usize,
+    fields: Vec<(String, String)>,
+    field: Option<(String, String)>,
+}
+
+/// The steps of a record path.
+struct RecordPath {
+    steps: Vec<String>,
+    anywhere: bool,
+}
+
+impl RecordPath {
+    fn new(path: &str) -> Result<RecordPath, DataError> {
+        let anywhere = path.starts_with("//");
+        let steps: Vec<String> =
+            path.trim_start_matches('/').split('/').map(String::from).collect();
+
+        if steps.iter().any(|s| s.is_empty()) {
+            return Err(DataError::InvalidStateError);
+        }
+
+        Ok(RecordPath { steps, anywhere })
+    }
+
+    /// Returns true if the open elements, from the root, end at a record.
+    fn matches(&self, stack: &[&str]) -> bool {
+        let fits = if self.anywhere {
+            stack.len() >= self.steps.len()
+        } else {
+            stack.len() == self.steps.len()
+        };
+
+        fits &&
+        stack[stack.len() - self.steps.len()..]
+            .iter()
+            .zip(self.steps.iter())
+            .all(|(tag, step)| step == "*" || tag == step)
+    }
+}
+
+/// A part of an XML document.
+enum Event<'a> {
+    Start {
+        name: &'a str,
+        attributes: Vec<(&'a str, String)>,
+        empty: bool,
+    },
+    End(&'a str),
+    Text(Cow<'a, str>),
+}
+
+/// Splits an XML document into events.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, DataError> {
+        loop {
+            let rest = &self.text[self.pos..];
+            if rest.is_empty() {
+                return Ok(None);
+            }
+
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                return Ok(Some(Event::Text(decode(&rest[..end])?)));
+            }
+
+            if rest.starts_with("<!--") {
+                self.skip_past(rest, "-->")?;
+            } else if let Some(data) = rest.strip_prefix("<![CDATA[") {
+                let end = data.find("]]>").ok_or(DataError::InvalidStateError)?;
+                self.pos += "<![CDATA[".len() + end + "]]>".len();
+                return Ok(Some(Event::Text(Cow::Borrowed(&data[..end]))));
+            } else if rest.starts_with("<?") {
+                self.skip_past(rest, "?>")?;
+            } else if rest.starts_with("<!") {
+                self.skip_declaration(rest)?;
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                let end = tag.find('>').ok_or(DataError::InvalidStateError)?;
+                self.pos += 2 + end + 1;
+                return Ok(Some(Event::End(tag[..end].trim_end())));
+            } else {
+                return self.start_tag(rest).map(Some);
+            }
+        }
+    }
+
+    fn skip_past(&mut self, rest: &str, marker: &str) -> Result<(), DataError> {
+        let end = rest.find(marker).ok_or(DataError::InvalidStateError)?;
+        self.pos += end + marker.len();
+        Ok(())
+    }
+
+    /// Skips a declaration such as a document type, with any internal subset.
+    fn skip_declaration(&mut self, rest: &str) -> Result<(), DataError> {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth == 0 => {
+                    self.pos += i + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err(DataError::InvalidStateError)
+    }
+
+    fn start_tag(&mut self, rest: &'a str) -> Result<Event<'a>, DataError> {
+        let mut quote = None;
+        let end = rest.char_indices()
+            .find(|&(_, c)| {
+                match quote {
+                    Some(q) if c == q => quote = None,
+                    Some(_) => {}
+                    None if c == '"' || c == '\'' => quote = Some(c),
+                    None => return c == '>',
+                }
+                false
+            })
+            .map(|(i, _)| i)
+            .ok_or(DataError::InvalidStateError)?;
+        self.pos += end + 1;
+
+        let mut tag = &rest[1..end];
+        let empty = tag.ends_with('/');
+        if empty {
+            tag = &tag[..tag.len() - 1];
+        }
+
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        if name.is_empty() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut attributes = Vec::new();
+        let mut rest = tag[name_end..].trim_start();
+        while !rest.is_empty() {
+            let eq = rest.find('=').ok_or(DataError::InvalidStateError)?;
+            let key = rest[..eq].trim_end();
+            let value = rest[eq + 1..].trim_start();
+
+            let q = value.chars().next().filter(|&c| c == '"' || c == '\'');
+            let q = q.ok_or(DataError::InvalidStateError)?;
+            let close = value[1..].find(q).ok_or(DataError::InvalidStateError)? + 1;
+
+            attributes.push((key, decode(&value[1..close])?.into_owned()));
+            rest = value[close + 1..].trim_start();
+        }
+
+        Ok(Event::Start { name, attributes, empty })
+    }
+}
+
+/// Replaces the entities in the text.
+fn decode(text: &str) -> Result<Cow<'_, str>, DataError> {
+    if !text.contains('&') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or(DataError::InvalidStateError)? + start;
+
+        let c = match &rest[start + 1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => {
+                let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|d| d.parse().ok()),
+                };
+                code.and_then(::std::char::from_u32)
+            }
+        };
+
+        decoded.push(c.ok_or(DataError::InvalidStateError)?);
+        rest = &rest[end + 1..];
+    }
+    decoded.push_str(rest);
+
+    Ok(Cow::Owned(decoded))
+}
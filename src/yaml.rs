@@ -0,0 +1,469 @@
+//! The yaml module.
+//!
+//! Provides loading of YAML sequences of mappings into DataTables.
+//!
+//! Only block style documents are read, as used for configuration and
+//! small datasets. Flow collections such as `[a, b]` are kept as written,
+//! apart from the empty `[]` and `{}`, and anchors, aliases and tags are
+//! not supported.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use datatable::DataTable;
+use error::DataError;
+use interop::FieldRecords;
+use toml::unescape;
+
+impl DataTable {
+    /// Loads the sequence of mappings at `path` from YAML text.
+    ///
+    /// The path holds the keys leading to the sequence separated by `.`,
+    /// or is empty if the document is the sequence. Each mapping is a row.
+    /// Keys of nested mappings are joined to their parent's key with a
+    /// `.`, and nested sequences of scalars are written as `[a, b]`. Keys
+    /// which a mapping does not have, or which are null, are missing.
+    ///
+    /// # Failures
+    ///
+    /// - InvalidStateError : The text is not valid YAML, there is no
+    ///   sequence of mappings at the path, or a mapping holds a sequence
+    ///   of collections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let text = "
+    /// study:
+    ///   runs:
+    ///     - name: baseline   # the reference
+    ///       seed: 1
+    ///       model:
+    ///         layers: 2
+    ///     - name: 'wide, deep'
+    ///       seed: ~
+    ///       tags:
+    ///         - large
+    ///         - slow
+    /// ";
+    ///
+    /// let table = DataTable::from_yaml_str(text, "study.runs").unwrap();
+    ///
+    /// assert_eq!(table.headers(), vec!["name", "seed", "model.layers", "tags"]);
+    /// assert_eq!(table[0].data(), vec!["baseline", "wide, deep"]);
+    /// assert!(table[1].is_missing(1));
+    /// assert_eq!(table[3][1], *"[large, slow]");
+    /// ```
+    ///
+    /// Blank lines in a folded scalar are kept as line breaks.
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let text = "
+    /// - note: >
+    ///     first
+    ///     paragraph
+    ///
+    ///     second
+    /// ";
+    ///
+    /// let table = DataTable::from_yaml_str(text, "").unwrap();
+    /// assert_eq!(table[0][0], *"first paragraph\nsecond\n");
+    /// ```
+    ///
+    /// An empty flow sequence is a sequence, so it can hold no rows.
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let table = DataTable::from_yaml_str("runs: []", "runs").unwrap();
+    /// assert_eq!(table.cols(), 0);
+    ///
+    /// let table = DataTable::from_yaml_str("- tags: []", "").unwrap();
+    /// assert_eq!(table[0][0], *"[]");
+    /// ```
+    ///
+    /// A quoted scalar may end with an escaped backslash before a comment.
+    ///
+    /// ```
+    /// use rusty_data::datatable::DataTable;
+    ///
+    /// let table = DataTable::from_yaml_str(r#"- path: "C:\\" # the root"#, "").unwrap();
+    /// assert_eq!(table[0][0], *"C:\\");
+    /// ```
+    pub fn from_yaml_str(text: &str, path: &str) -> Result<DataTable, DataError> {
+        let mut parser = Parser {
+            lines: text.lines().collect(),
+            next: 0,
+            pending: None,
+        };
+        parser.skip_document_start();
+
+        let mut node = match parser.peek() {
+            Some((indent, _)) => parser.node(indent)?,
+            None => Node::Scalar(None),
+        };
+        if parser.peek().is_some() {
+            return Err(DataError::InvalidStateError);
+        }
+
+        for key in path.split('.').filter(|k| !k.is_empty()) {
+            node = match node {
+                Node::Map(entries) => {
+                    entries.into_iter()
+                        .rev()
+                        .find(|(k, _)| k == key)
+                        .map(|(_, n)| n)
+                        .ok_or(DataError::InvalidStateError)?
+                }
+                _ => return Err(DataError::InvalidStateError),
+            };
+        }
+
+        let items = match node {
+            Node::Seq(items) => items,
+            _ => return Err(DataError::InvalidStateError),
+        };
+
+        let mut records = FieldRecords::default();
+        for item in items {
+            let mut fields = Vec::new();
+            match item {
+                Node::Map(entries) => flatten("", entries, &mut fields)?,
+                _ => return Err(DataError::InvalidStateError),
+            }
+            records.push(fields);
+        }
+
+        Ok(records.finish())
+    }
+
+    /// Loads the sequence of mappings at `path` from a YAML file, see `from_yaml_str`.
+    ///
+    /// # Failures
+    ///
+    /// - Io : The file could not be read.
+    /// - InvalidStateError : The file is not valid YAML, or there is no
+    ///   sequence of mappings at the path.
+    pub fn load_yaml<P: AsRef<Path>>(path: P, key_path: &str) -> Result<DataTable, DataError> {
+        let mut text = String::new();
+        BufReader::new(File::open(path)?).read_to_string(&mut text)?;
+        DataTable::from_yaml_str(&text, key_path)
+    }
+}
+
+/// Adds the scalars of the mapping to the fields, with keys after the prefix.
+fn flatten(prefix: &str,
+           entries: Vec<(String, Node)>,
+           fields: &mut Vec<(String, String)>)
+           -> Result<(), DataError> {
+    for (key, node) in entries {
+        let key = format!("{}{}", prefix, key);
+        match node {
+            Node::Scalar(Some(value)) => fields.push((key, value)),
+            Node::Scalar(None) => {}
+            Node::Map(entries) => flatten(&format!("{}.", key), entries, fields)?,
+            Node::Seq(items) => {
+                let values = items.into_iter()
+                    .map(|item| match item {
+                        Node::Scalar(value) => Ok(value.unwrap_or_else(|| "null".to_string())),
+                        _ => Err(DataError::InvalidStateError),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                fields.push((key, format!("[{}]", values.join(", "))));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A parsed YAML value.
+enum Node {
+    /// A scalar, or `None` if it is null.
+    Scalar(Option<String>),
+    Map(Vec<(String, Node)>),
+    Seq(Vec<Node>),
+}
+
+/// Reads block style YAML line by line.
+struct Parser<'a> {
+    lines: Vec<&'a str>,
+    next: usize,
+    // The rest of a line after a sequence item's `- `, with its indent.
+    pending: Option<(usize, &'a str)>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_document_start(&mut self) {
+        while let Some(&line) = self.lines.get(self.next) {
+            let line = strip_comment(line).trim();
+            if line.is_empty() || line == "---" || line.starts_with('%') {
+                self.next += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The indent and content of the next line which is not blank.
+    fn peek(&mut self) -> Option<(usize, &'a str)> {
+        if self.pending.is_some() {
+            return self.pending;
+        }
+
+        while let Some(&line) = self.lines.get(self.next) {
+            let content = strip_comment(line).trim_end();
+            let text = content.trim_start();
+            if text == "..." {
+                return None;
+            }
+            if !text.is_empty() {
+                return Some((content.len() - text.len(), text));
+            }
+            self.next += 1;
+        }
+        None
+    }
+
+    fn advance(&mut self) {
+        if self.pending.take().is_none() {
+            self.next += 1;
+        }
+    }
+
+    /// Reads the node starting at the next line, which has the indent.
+    fn node(&mut self, indent: usize) -> Result<Node, DataError> {
+        let (_, text) = self.peek().ok_or(DataError::InvalidStateError)?;
+
+        if is_item(text) {
+            self.sequence(indent)
+        } else if split_key(text).is_some() {
+            self.mapping(indent)
+        } else {
+            self.advance();
+            flow_node(text)
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Node, DataError> {
+        let mut items = Vec::new();
+
+        while let Some((line_indent, text)) = self.peek() {
+            if line_indent < indent || (line_indent == indent && !is_item(text)) {
+                break;
+            }
+            if line_indent > indent {
+                return Err(DataError::InvalidStateError);
+            }
+
+            self.advance();
+            let rest = text[1..].trim_start();
+            if rest.is_empty() {
+                items.push(self.child(indent, false)?);
+            } else {
+                let item_indent = indent + text.len() - rest.len();
+                self.pending = Some((item_indent, rest));
+                items.push(self.node(item_indent)?);
+            }
+        }
+
+        Ok(Node::Seq(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Node, DataError> {
+        let mut entries = Vec::new();
+
+        while let Some((line_indent, text)) = self.peek() {
+            if line_indent < indent || (line_indent == indent && is_item(text)) {
+                break;
+            }
+            let (key, value) = match split_key(text) {
+                Some(parts) if line_indent == indent => parts,
+                _ => return Err(DataError::InvalidStateError),
+            };
+
+            self.advance();
+            let node = match value {
+                "" => self.child(indent, true)?,
+                _ if value.starts_with('|') || value.starts_with('>') => {
+                    self.block_scalar(indent, value)?
+                }
+                _ => flow_node(value)?,
+            };
+            entries.push((key, node));
+        }
+
+        Ok(Node::Map(entries))
+    }
+
+    /// Reads the node held by a key or item with nothing after it on its line.
+    ///
+    /// The sequence of a key may have the same indent as the key.
+    fn child(&mut self, indent: usize, is_key: bool) -> Result<Node, DataError> {
+        match self.peek() {
+            Some((child, _)) if child > indent => self.node(child),
+            Some((child, text)) if is_key && child == indent && is_item(text) => self.node(child),
+            _ => Ok(Node::Scalar(None)),
+        }
+    }
+
+    /// Reads a `|` or `>` scalar from the lines indented past the key.
+    fn block_scalar(&mut self, indent: usize, header: &str) -> Result<Node, DataError> {
+        let header = header.trim();
+        let folded = header.starts_with('>');
+        let chomp = &header[1..];
+        if !["", "-", "+"].contains(&chomp) {
+            return Err(DataError::InvalidStateError);
+        }
+
+        let mut lines = Vec::new();
+        let mut block_indent = None;
+        while let Some(&line) = self.lines.get(self.next) {
+            let text = line.trim_start();
+            let line_indent = line.len() - text.len();
+            if !text.is_empty() && line_indent <= indent {
+                break;
+            }
+            if !text.is_empty() && block_indent.is_none() {
+                block_indent = Some(line_indent);
+            }
+            lines.push(line);
+            self.next += 1;
+        }
+
+        let block_indent = block_indent.unwrap_or(0);
+        let lines: Vec<&str> = lines.iter().map(|l| l.get(block_indent..).unwrap_or("")).collect();
+        let mut value = if folded { fold(&lines) } else { lines.join("\n") };
+
+        let trimmed = value.trim_end_matches([' ', '\n']).len();
+        match chomp {
+            "-" => value.truncate(trimmed),
+            "+" => value.push('\n'),
+            _ => {
+                value.truncate(trimmed);
+                value.push('\n');
+            }
+        }
+
+        Ok(Node::Scalar(Some(value)))
+    }
+}
+
+/// Joins the lines of a folded scalar.
+///
+/// A line break between two lines becomes a space, and each blank line
+/// becomes a line break. Breaks next to lines indented further than the
+/// scalar are kept.
+fn fold(lines: &[&str]) -> String {
+    let mut value = String::new();
+    let mut breaks = 0;
+    // Whether the last line was indented further, or `None` before any line.
+    let mut spaced = None;
+
+    for line in lines {
+        if line.trim().is_empty() {
+            breaks += 1;
+            continue;
+        }
+
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+        match spaced {
+            None => {}
+            Some(false) if !indented && breaks == 0 => value.push(' '),
+            Some(false) if !indented => {}
+            Some(_) => value.push('\n'),
+        }
+        value.extend((0..breaks).map(|_| '\n'));
+        value.push_str(line);
+        breaks = 0;
+        spaced = Some(indented);
+    }
+
+    value.extend((0..breaks).map(|_| '\n'));
+    value
+}
+
+/// Reads a value written on the line of its key or item.
+///
+/// Empty flow collections are read as collections, other flow
+/// collections are kept as written.
+fn flow_node(text: &str) -> Result<Node, DataError> {
+    match text.trim() {
+        "[]" => Ok(Node::Seq(Vec::new())),
+        "{}" => Ok(Node::Map(Vec::new())),
+        text => scalar(text).map(Node::Scalar),
+    }
+}
+
+/// Returns true if the line starts a sequence item.
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Splits a `key: value` line, unquoting the key.
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let colon = if text.starts_with('"') || text.starts_with('\'') {
+        let close = text[1..].find(&text[..1])? + 1;
+        match text[close + 1..].trim_start().strip_prefix(':') {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                text.len() - rest.len() - 1
+            }
+            _ => return None,
+        }
+    } else if text.starts_with('[') || text.starts_with('{') {
+        return None;
+    } else {
+        text.find(": ").or_else(|| if text.ends_with(':') { Some(text.len() - 1) } else { None })?
+    };
+
+    let key = scalar(text[..colon].trim()).ok()??;
+    Some((key, text[colon + 1..].trim()))
+}
+
+/// Parses a scalar, returning `None` if it is null.
+fn scalar(text: &str) -> Result<Option<String>, DataError> {
+    let text = text.trim();
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Ok(None),
+        _ => {}
+    }
+
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner.strip_suffix('"').ok_or(DataError::InvalidStateError)?;
+        unescape(inner).map(Some)
+    } else if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner.strip_suffix('\'').ok_or(DataError::InvalidStateError)?;
+        Ok(Some(inner.replace("''", "'")))
+    } else if text.starts_with('&') || text.starts_with('*') || text.starts_with('!') {
+        Err(DataError::InvalidStateError)
+    } else {
+        Ok(Some(text.to_string()))
+    }
+}
+
+/// Removes a comment from the line, ignoring `#` within quoted scalars.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some('"') if c == '"' => quote = None,
+            Some('\'') if c == '\'' => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && (prev == ' ' || prev == '\t') => quote = Some(c),
+            None if c == '#' && (prev == ' ' || prev == '\t') => return &line[..i],
+            None => {}
+        }
+        prev = c;
+    }
+    line
+}